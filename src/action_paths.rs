@@ -1,6 +1,7 @@
 use crate::{node, Error};
 use node::path::Path;
 use std::borrow::Cow;
+use std::collections::HashMap;
 
 pub fn get_action_name() -> Cow<'static, str> {
     "ferrous-actions".into()
@@ -18,3 +19,116 @@ pub fn get_action_share_dir() -> Result<Path, Error> {
 pub fn get_action_cache_dir() -> Result<Path, Error> {
     Ok(node::os::homedir().join(".cache").join(get_action_name().as_ref()))
 }
+
+/// The Cargo home directory, which is `CARGO_HOME` if set and `.cargo` in the
+/// home directory otherwise
+pub fn cargo_home_from_env(env: &HashMap<String, String>, home: &Path) -> Path {
+    env.get("CARGO_HOME")
+        .filter(|path| !path.is_empty())
+        .map_or_else(|| home.join(".cargo"), |path| Path::from(path.as_str()))
+}
+
+/// The Rustup home directory, which is `RUSTUP_HOME` if set and `.rustup` in
+/// the home directory otherwise
+pub fn rustup_home_from_env(env: &HashMap<String, String>, home: &Path) -> Path {
+    env.get("RUSTUP_HOME")
+        .filter(|path| !path.is_empty())
+        .map_or_else(|| home.join(".rustup"), |path| Path::from(path.as_str()))
+}
+
+/// Checks that a directory named by the environment variable `variable` is an
+/// absolute path other than the filesystem root which either is, or can be
+/// created as, a writable directory
+pub async fn validate_home_dir(variable: &str, dir: &Path) -> Result<(), Error> {
+    use node::fs::AccessMode;
+
+    let problem = if !dir.is_absolute() {
+        Some("it is not an absolute path".to_string())
+    } else if dir.parent() == *dir {
+        Some("it is the filesystem root".to_string())
+    } else {
+        // The directory need not exist yet, in which case it is created
+        // inside the nearest folder that does
+        let mut existing = dir.clone();
+        while !existing.exists().await && existing.parent() != existing {
+            existing = existing.parent();
+        }
+        let location = if existing == *dir {
+            "it".to_string()
+        } else {
+            existing.to_string()
+        };
+        if !node::fs::metadata(&existing).await.map_or(false, |m| m.is_directory()) {
+            Some(format!("{} is not a directory", location))
+        } else if node::fs::access(&existing, AccessMode::WRITE).await.is_err() {
+            Some(format!("{} is not writable", location))
+        } else {
+            None
+        }
+    };
+    match problem {
+        Some(problem) => Err(Error::UnusableHomeDir(variable.into(), dir.to_string(), problem)),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the home directory, which holds the action's own data, and the
+/// Cargo and Rustup homes can be used
+pub async fn check_home_dir() -> Result<(), Error> {
+    let home = node::os::try_homedir()
+        .map_err(|_| Error::UnusableHomeDir("HOME".into(), "(unknown)".into(), "it could not be determined".into()))?;
+    validate_home_dir("HOME", &home).await?;
+    let env = node::process::get_env();
+    validate_home_dir("CARGO_HOME", &cargo_home_from_env(&env, &home)).await?;
+    validate_home_dir("RUSTUP_HOME", &rustup_home_from_env(&env, &home)).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cargo_home_from_env, validate_home_dir};
+    use crate::node::path::Path;
+    use crate::{node, Error};
+    use std::collections::HashMap;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn cargo_home_override() {
+        let home = Path::from("/home/runner");
+        let mut env = HashMap::new();
+        assert_eq!(cargo_home_from_env(&env, &home), home.join(".cargo"));
+        env.insert("CARGO_HOME".to_string(), String::new());
+        assert_eq!(cargo_home_from_env(&env, &home), home.join(".cargo"));
+        env.insert("CARGO_HOME".to_string(), "/opt/cargo".to_string());
+        assert_eq!(cargo_home_from_env(&env, &home), Path::from("/opt/cargo"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn unusable_home_dirs() -> Result<(), JsValue> {
+        let is_unusable = |result: Result<(), Error>| matches!(result, Err(Error::UnusableHomeDir(..)));
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-home-")).await?;
+        validate_home_dir("HOME", temp_dir.path()).await.unwrap();
+        // A directory that does not exist yet can be created
+        let missing = temp_dir.path().join("missing");
+        validate_home_dir("CARGO_HOME", &missing.join(".cargo")).await.unwrap();
+        assert!(is_unusable(
+            validate_home_dir("HOME", &Path::from("relative/home")).await
+        ));
+        let root = node::fs::canonicalize(node::os::temp_dir()).await?;
+        let root = std::iter::successors(Some(root), |p| (p.parent() != *p).then(|| p.parent()))
+            .last()
+            .expect("Path has no root");
+        assert!(is_unusable(validate_home_dir("HOME", &root).await));
+
+        // A path beneath a regular file can never be written, even by root
+        let file = temp_dir.path().join("file");
+        node::fs::write_file(&file, b"").await?;
+        let error = validate_home_dir("RUSTUP_HOME", &file.join("rustup"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Set RUSTUP_HOME"));
+        assert!(error.to_string().contains("is not a directory"));
+        temp_dir.close().await?;
+        Ok(())
+    }
+}
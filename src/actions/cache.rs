@@ -1,12 +1,23 @@
-use crate::node;
 use crate::node::path::Path;
+use crate::system::retry::{retry, Policy as RetryPolicy};
+use crate::{info, node, warning};
 use js_sys::JsString;
+use lazy_static::lazy_static;
 use std::convert::Into;
 use wasm_bindgen::prelude::*;
 
 const WORKSPACE_ENV_VAR: &str = "GITHUB_WORKSPACE";
 const WORKSPACE_OVERRIDDEN_TAG: &str = "#WORKSPACE_OVERRIDEN";
 
+/// Environment variables from which the GitHub Actions Toolkit reads the cache
+/// service endpoint. The first is used by the original cache service and the
+/// second by the newer results service. GitHub Enterprise Server sets these to
+/// its own endpoints.
+const CACHE_SERVICE_URL_VARS: [&str; 2] = ["ACTIONS_CACHE_URL", "ACTIONS_RESULTS_URL"];
+
+/// Whether we have already warned that the cache service is unavailable
+static UNAVAILABLE_WARNING_ISSUED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
 // Actually getting caching to work cross platform is complicated. First of all,
 // the action takes patterns not paths (which is unhelpful for apps that don't
 // want to use globs), It also means that on Windows you're going to need to
@@ -44,6 +55,13 @@ const WORKSPACE_OVERRIDDEN_TAG: &str = "#WORKSPACE_OVERRIDEN";
 // location. This is a hack, but in general it means that we can reliably cache
 // and restore paths to locations that may change across time.
 
+lazy_static! {
+    /// Held for the duration of each save or restore. Since `ScopedWorkspace`
+    /// changes process-wide state across an await point, concurrent operations
+    /// would otherwise see each other's working directory.
+    static ref OPERATION_LOCK: futures::lock::Mutex<()> = futures::lock::Mutex::new(());
+}
+
 /// Changes the current working directory and `$GITHUB_WORKSPACE` to a specified
 /// path and changes it back when it is dropped. This enables us to:
 /// - supply consistent relative paths (patterns rather) to the actions API
@@ -82,6 +100,158 @@ impl Drop for ScopedWorkspace {
     }
 }
 
+/// Failures of the cache service which should not be fatal to an action, since
+/// caching is best-effort
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceFailure {
+    /// The cache service is unavailable, failing or disabled
+    Unavailable,
+
+    /// Another job is already saving a cache entry with the same key
+    ReserveConflict,
+}
+
+impl ServiceFailure {
+    /// Classifies an error returned by the cache API. Returns `None` if the
+    /// error did not originate from the cache service, in which case it is
+    /// likely a usage error.
+    pub fn classify(error: &JsValue) -> Option<ServiceFailure> {
+        let property = |name: &str| {
+            js_sys::Reflect::get(error, &name.into())
+                .ok()
+                .and_then(|value| value.as_string())
+                .unwrap_or_default()
+        };
+        Self::classify_message(&property("name"), &property("message"))
+    }
+
+    fn classify_message(name: &str, message: &str) -> Option<ServiceFailure> {
+        const UNAVAILABLE_MESSAGES: [&str; 4] = [
+            "cache service url not found",
+            "cache service is not available",
+            "cache service responded with 5",
+            "cache storage quota",
+        ];
+
+        let message = message.to_lowercase();
+        if name == "ReserveCacheError" || message.contains("unable to reserve cache") {
+            Some(ServiceFailure::ReserveConflict)
+        } else if name == "ValidationError" {
+            None
+        } else if UNAVAILABLE_MESSAGES.iter().any(|m| message.contains(m)) {
+            Some(ServiceFailure::Unavailable)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether the environment specifies a cache service endpoint
+fn cache_service_configured(env: &std::collections::HashMap<String, String>) -> bool {
+    CACHE_SERVICE_URL_VARS
+        .iter()
+        .any(|name| env.get(*name).map_or(false, |value| !value.trim().is_empty()))
+}
+
+/// Whether the cache service can be used. If not, a warning is emitted the
+/// first time this is called.
+pub fn is_service_available() -> bool {
+    use std::sync::atomic::Ordering;
+
+    let available = cache_service_configured(&node::process::get_env());
+    if !available && !UNAVAILABLE_WARNING_ISSUED.swap(true, Ordering::Relaxed) {
+        warning!(
+            "Caching is unavailable because none of {} are set. Caching may be disabled, or this GitHub Enterprise Server version may not support it.",
+            CACHE_SERVICE_URL_VARS.join(", ")
+        );
+    }
+    available
+}
+
+/// Why a single attempt to restore a cache entry failed.
+///
+/// `restoreCache` logs failures of the cache service and returns `undefined`
+/// rather than throwing, so that outcome is the only indication that an
+/// attempt is worth retrying. Errors which are thrown are usage errors and are
+/// never retried.
+#[derive(Debug)]
+enum AttemptFailure {
+    Error(JsValue),
+    Unsuccessful,
+}
+
+impl AttemptFailure {
+    fn is_retryable(&self) -> bool {
+        matches!(self, AttemptFailure::Unsuccessful)
+    }
+}
+
+/// Interprets the value returned by `saveCache`, which is -1 if the entry was
+/// not saved
+fn parse_save_result(result: &JsValue) -> Result<SaveOutcome, JsValue> {
+    let id = result
+        .dyn_ref::<js_sys::Number>()
+        .ok_or_else(|| JsError::new("saveCache didn't return a number"))
+        .map(|n| {
+            #[allow(clippy::cast_possible_truncation)]
+            let id = n.value_of() as i64;
+            id
+        })?;
+    Ok(if id < 0 {
+        SaveOutcome::NotSaved
+    } else {
+        SaveOutcome::Saved(id)
+    })
+}
+
+/// The outcome of attempting to restore a cache entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    /// No matching cache entry was found
+    Miss,
+
+    /// A matching cache entry was found and its contents restored
+    Restored(String),
+
+    /// A matching cache entry was found but its contents were not restored
+    /// because the entry was in lookup-only mode
+    Found(String),
+}
+
+impl RestoreOutcome {
+    /// The key of the matching cache entry, if one was found
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            RestoreOutcome::Miss => None,
+            RestoreOutcome::Restored(key) | RestoreOutcome::Found(key) => Some(key),
+        }
+    }
+}
+
+/// The outcome of attempting to save a cache entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveOutcome {
+    /// The entry was saved with the specified cache ID
+    Saved(i64),
+
+    /// The entry was not saved, e.g. because another job reserved the key
+    /// first
+    NotSaved,
+
+    /// None of the entry's paths exist so there was nothing to save
+    Skipped,
+}
+
+impl SaveOutcome {
+    /// The ID of the saved cache entry, if it was saved
+    pub fn cache_id(&self) -> Option<i64> {
+        match self {
+            SaveOutcome::Saved(id) => Some(*id),
+            SaveOutcome::NotSaved | SaveOutcome::Skipped => None,
+        }
+    }
+}
+
 /// Saves and retrieves cache entries
 pub struct Entry {
     key: JsString,
@@ -89,6 +259,10 @@ pub struct Entry {
     restore_keys: Vec<JsString>,
     cross_os_archive: bool,
     relative_to: Option<Path>,
+    upload_concurrency: Option<u32>,
+    upload_chunk_size: Option<u64>,
+    download_concurrency: Option<u32>,
+    lookup_only: bool,
 }
 
 impl Entry {
@@ -99,6 +273,10 @@ impl Entry {
             restore_keys: Vec::new(),
             cross_os_archive: false,
             relative_to: None,
+            upload_concurrency: None,
+            upload_chunk_size: None,
+            download_concurrency: None,
+            lookup_only: false,
         }
     }
 
@@ -131,6 +309,36 @@ impl Entry {
         self
     }
 
+    /// Sets the number of parallel uploads used when saving the cache entry
+    pub fn upload_concurrency(&mut self, concurrency: u32) -> &mut Entry {
+        self.upload_concurrency = Some(concurrency);
+        self
+    }
+
+    /// Sets the size in bytes of the chunks uploaded when saving the cache
+    /// entry
+    pub fn upload_chunk_size(&mut self, size: u64) -> &mut Entry {
+        self.upload_chunk_size = Some(size);
+        self
+    }
+
+    /// Sets the number of parallel downloads used when restoring the cache
+    /// entry
+    pub fn download_concurrency(&mut self, concurrency: u32) -> &mut Entry {
+        self.download_concurrency = Some(concurrency);
+        self
+    }
+
+    /// Specifies that restoring should only check for the existence of a
+    /// matching cache entry and not download it.
+    ///
+    /// It is a usage error to expect the cached files to be present on disk
+    /// after restoring in this mode.
+    pub fn lookup_only(&mut self, lookup_only: bool) -> &mut Entry {
+        self.lookup_only = lookup_only;
+        self
+    }
+
     /// Specify multiple restore keys
     pub fn restore_keys<I, K>(&mut self, restore_keys: I) -> &mut Entry
     where
@@ -149,22 +357,64 @@ impl Entry {
         self.restore_keys(std::iter::once(restore_key.into()))
     }
 
-    /// Saves the cache entry and returns a numeric cache ID.
-    pub async fn save(&self) -> Result<i64, JsValue> {
+    /// The key the entry will be saved with
+    pub fn get_key(&self) -> String {
+        String::from(&self.key)
+    }
+
+    /// The restore keys of the entry, in the order they will be searched
+    pub fn get_restore_keys(&self) -> Vec<String> {
+        self.restore_keys.iter().map(String::from).collect()
+    }
+
+    /// Saves the cache entry and returns the outcome, which includes a numeric
+    /// cache ID if the entry was saved.
+    ///
+    /// The entry is archived before its key is reserved with the cache
+    /// service, and the GitHub Actions Toolkit does not expose reservation as
+    /// a separate step. If another job reserves the key first, `saveCache`
+    /// logs the conflict itself and the entry is reported as not saved.
+    ///
+    /// If none of the entry's paths exist, nothing is saved. An empty folder
+    /// still counts as existing. If the cache service is not configured, the
+    /// entry is reported as not saved.
+    ///
+    /// Unsuccessful saves are not retried. `saveCache` returns -1 both when
+    /// another job has reserved the key, in which case a retry can only fail
+    /// again after archiving the entry once more, and when the cache service
+    /// failed, so the two cannot be told apart.
+    pub async fn save(&self) -> Result<SaveOutcome, JsValue> {
+        let _operation = OPERATION_LOCK.lock().await;
+        if !self.any_path_exists().await {
+            info!(
+                "Not saving cache entry {} since none of its paths exist",
+                String::from(&self.key)
+            );
+            return Ok(SaveOutcome::Skipped);
+        }
+        if !is_service_available() {
+            return Ok(SaveOutcome::NotSaved);
+        }
         let patterns = self.build_patterns();
-        let result = {
-            let _caching_scope = self.build_action_scope()?;
-            ffi::save_cache(patterns, &self.key, None, self.cross_os_archive).await?
-        };
-        let result = result
-            .dyn_ref::<js_sys::Number>()
-            .ok_or_else(|| JsError::new("saveCache didn't return a number"))
-            .map(|n| {
-                #[allow(clippy::cast_possible_truncation)]
-                let id = n.value_of() as i64;
-                id
-            })?;
-        Ok(result)
+        let _caching_scope = self.build_action_scope()?;
+        let result = ffi::save_cache(
+            patterns,
+            &self.key,
+            Some(self.build_upload_options()),
+            self.cross_os_archive,
+        )
+        .await?;
+        parse_save_result(&result)
+    }
+
+    async fn any_path_exists(&self) -> bool {
+        let cwd = node::process::cwd();
+        for path in &self.paths {
+            if cwd.join(path).exists().await {
+                return true;
+            }
+        }
+        false
     }
 
     /// Saves the cache entry if either:
@@ -176,7 +426,7 @@ impl Entry {
     ///
     /// This functionality is a Ferrous Actions extension and not part of the
     /// GitHub Actions Toolkit API.
-    pub async fn save_if_update(&self, old_restore_key: Option<&str>) -> Result<Option<i64>, JsValue> {
+    pub async fn save_if_update(&self, old_restore_key: Option<&str>) -> Result<Option<SaveOutcome>, JsValue> {
         let new_restore_key = self.peek_restore().await?;
         if new_restore_key.is_none() || new_restore_key.as_deref() == old_restore_key {
             self.save().await.map(Some)
@@ -185,6 +435,28 @@ impl Entry {
         }
     }
 
+    fn build_upload_options(&self) -> js_sys::Object {
+        let options = js_sys::Map::new();
+        if let Some(concurrency) = self.upload_concurrency {
+            options.set(&"uploadConcurrency".into(), &concurrency.into());
+        }
+        if let Some(size) = self.upload_chunk_size {
+            #[allow(clippy::cast_precision_loss)]
+            let size = size as f64;
+            options.set(&"uploadChunkSize".into(), &size.into());
+        }
+        js_sys::Object::from_entries(&options).expect("Failed to convert options map to object")
+    }
+
+    fn build_download_options(&self, lookup_only: bool) -> js_sys::Object {
+        let options = js_sys::Map::new();
+        options.set(&"lookupOnly".into(), &lookup_only.into());
+        if let Some(concurrency) = self.download_concurrency {
+            options.set(&"downloadConcurrency".into(), &concurrency.into());
+        }
+        js_sys::Object::from_entries(&options).expect("Failed to convert options map to object")
+    }
+
     fn build_patterns(&self) -> Vec<JsString> {
         let cwd = node::process::cwd();
         let mut result = Vec::with_capacity(self.paths.len());
@@ -243,35 +515,87 @@ impl Entry {
 
     /// Attempts to restore the cache entry. If one was found, the key is
     /// returned.
+    ///
+    /// If the entry is in lookup-only mode, the key is returned without the
+    /// cache contents being restored. If the cache service is not configured,
+    /// nothing is found.
     pub async fn restore(&self) -> Result<Option<String>, JsValue> {
-        self.peek_or_restore(false).await
+        self.peek_or_restore(self.lookup_only).await
+    }
+
+    /// Attempts to restore the cache entry, distinguishing between an entry
+    /// that was restored and one that was only found because the entry is in
+    /// lookup-only mode
+    pub async fn restore_outcome(&self) -> Result<RestoreOutcome, JsValue> {
+        let key = self.restore().await?;
+        Ok(match key {
+            None => RestoreOutcome::Miss,
+            Some(key) if self.lookup_only => RestoreOutcome::Found(key),
+            Some(key) => RestoreOutcome::Restored(key),
+        })
     }
 
     async fn peek_restore(&self) -> Result<Option<String>, JsValue> {
         self.peek_or_restore(true).await
     }
 
+    /// Restores the cache entry, or only looks it up if `peek` is true.
+    ///
+    /// `restoreCache` returns `undefined` both on a miss and when downloading
+    /// fails, so when nothing is restored a lookup is made to tell the two
+    /// apart. The restore is retried only if the lookup finds an entry.
     pub async fn peek_or_restore(&self, peek: bool) -> Result<Option<String>, JsValue> {
-        use js_sys::Object;
-
+        if !is_service_available() {
+            return Ok(None);
+        }
+        let _operation = OPERATION_LOCK.lock().await;
         let patterns = self.build_patterns();
-        let options = {
-            let options = js_sys::Map::new();
-            options.set(&"lookupOnly".into(), &peek.into());
-            Object::from_entries(&options).expect("Failed to convert options map to object")
-        };
+        let options = self.build_download_options(peek);
         let result = {
             let _caching_scope = self.build_action_scope()?;
-            ffi::restore_cache(
-                patterns,
-                &self.key,
-                self.restore_keys.clone(),
-                Some(options),
-                self.cross_os_archive,
+            retry(
+                &RetryPolicy::default(),
+                "restore cache entry",
+                AttemptFailure::is_retryable,
+                || {
+                    let patterns = patterns.clone();
+                    let options = options.clone();
+                    async move {
+                        let result = ffi::restore_cache(
+                            patterns.clone(),
+                            &self.key,
+                            self.restore_keys.clone(),
+                            Some(options),
+                            self.cross_os_archive,
+                        )
+                        .await
+                        .map_err(AttemptFailure::Error)?;
+                        let key: Option<String> = result.dyn_ref::<JsString>().map(Into::into);
+                        if key.is_none() && !peek {
+                            let found = ffi::restore_cache(
+                                patterns,
+                                &self.key,
+                                self.restore_keys.clone(),
+                                Some(self.build_download_options(true)),
+                                self.cross_os_archive,
+                            )
+                            .await
+                            .map_err(AttemptFailure::Error)?;
+                            if found.is_string() {
+                                return Err(AttemptFailure::Unsuccessful);
+                            }
+                        }
+                        Ok(key)
+                    }
+                },
             )
-            .await?
+            .await
         };
-        Ok(result.dyn_ref::<JsString>().map(Into::into))
+        match result {
+            Ok(key) => Ok(key),
+            Err(AttemptFailure::Unsuccessful) => Ok(None),
+            Err(AttemptFailure::Error(e)) => Err(e),
+        }
     }
 }
 
@@ -300,3 +624,151 @@ pub mod ffi {
         ) -> Result<JsValue, JsValue>;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Entry, SaveOutcome};
+    use crate::node;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn get(object: &js_sys::Object, name: &str) -> JsValue {
+        js_sys::Reflect::get(object, &name.into()).expect("Failed to read property")
+    }
+
+    #[wasm_bindgen_test]
+    fn transfer_options() {
+        let mut entry = Entry::new("key");
+        let upload = entry.build_upload_options();
+        assert!(get(&upload, "uploadConcurrency").is_undefined());
+        assert!(get(&upload, "uploadChunkSize").is_undefined());
+        let download = entry.build_download_options(false);
+        assert!(get(&download, "downloadConcurrency").is_undefined());
+
+        entry
+            .upload_concurrency(2)
+            .upload_chunk_size(64 * 1024 * 1024)
+            .download_concurrency(16);
+        let upload = entry.build_upload_options();
+        assert_eq!(get(&upload, "uploadConcurrency").as_f64(), Some(2.0));
+        assert_eq!(get(&upload, "uploadChunkSize").as_f64(), Some(67_108_864.0));
+        let download = entry.build_download_options(false);
+        assert_eq!(get(&download, "downloadConcurrency").as_f64(), Some(16.0));
+    }
+
+    #[wasm_bindgen_test]
+    fn lookup_only_option() {
+        let mut entry = Entry::new("key");
+        let download = entry.build_download_options(entry.lookup_only);
+        assert_eq!(get(&download, "lookupOnly").as_bool(), Some(false));
+
+        entry.lookup_only(true);
+        let download = entry.build_download_options(entry.lookup_only);
+        assert_eq!(get(&download, "lookupOnly").as_bool(), Some(true));
+    }
+
+    #[wasm_bindgen_test]
+    fn classify_service_failures() {
+        use super::ServiceFailure;
+
+        assert_eq!(
+            ServiceFailure::classify_message(
+                "ReserveCacheError",
+                "Unable to reserve cache with key foo, another job may be creating this cache."
+            ),
+            Some(ServiceFailure::ReserveConflict)
+        );
+        assert_eq!(
+            ServiceFailure::classify_message("Error", "Cache Service Url not found, unable to restore cache."),
+            Some(ServiceFailure::Unavailable)
+        );
+        assert_eq!(
+            ServiceFailure::classify_message("Error", "Cache service responded with 503"),
+            Some(ServiceFailure::Unavailable)
+        );
+        assert_eq!(
+            ServiceFailure::classify_message("ValidationError", "Path Validation Error: bad path"),
+            None
+        );
+        assert_eq!(ServiceFailure::classify_message("TypeError", "x is undefined"), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn save_results() {
+        use super::{parse_save_result, SaveOutcome};
+
+        assert_eq!(parse_save_result(&JsValue::from(42)).ok(), Some(SaveOutcome::Saved(42)));
+        assert!(parse_save_result(&JsValue::UNDEFINED).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn save_reserve_conflict() {
+        use super::{parse_save_result, SaveOutcome};
+
+        // `saveCache` logs a reservation conflict and returns -1, which must be
+        // reported as not saved rather than as an error to be retried
+        assert_eq!(parse_save_result(&JsValue::from(-1)).ok(), Some(SaveOutcome::NotSaved));
+    }
+
+    #[wasm_bindgen_test]
+    fn service_configuration() {
+        use super::cache_service_configured;
+        use std::collections::HashMap;
+
+        let env = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert!(!cache_service_configured(&env(&[])));
+        assert!(!cache_service_configured(&env(&[("ACTIONS_CACHE_URL", " ")])));
+        assert!(cache_service_configured(&env(&[(
+            "ACTIONS_CACHE_URL",
+            "https://ghes.example.com/_services/pipelines/"
+        )])));
+        assert!(cache_service_configured(&env(&[(
+            "ACTIONS_RESULTS_URL",
+            "https://results.example.com/"
+        )])));
+    }
+
+    #[wasm_bindgen_test]
+    async fn missing_service_is_graceful() -> Result<(), JsValue> {
+        let saved: Vec<_> = super::CACHE_SERVICE_URL_VARS
+            .iter()
+            .map(|name| (*name, node::process::get_env().get(*name).cloned()))
+            .collect();
+        for (name, _) in &saved {
+            node::process::remove_var(name);
+        }
+        let mut entry = Entry::new("key");
+        entry.path(node::process::cwd());
+        let restored = entry.restore().await;
+        let saved_outcome = entry.save().await;
+        for (name, value) in &saved {
+            if let Some(value) = value {
+                node::process::set_var(name, value);
+            }
+        }
+        assert_eq!(restored?, None);
+        assert_eq!(saved_outcome?, SaveOutcome::NotSaved);
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    fn restore_outcome_key() {
+        use super::RestoreOutcome;
+
+        assert_eq!(RestoreOutcome::Miss.key(), None);
+        assert_eq!(RestoreOutcome::Restored("a".into()).key(), Some("a"));
+        assert_eq!(RestoreOutcome::Found("b".into()).key(), Some("b"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn save_missing_path_is_skipped() -> Result<(), JsValue> {
+        let mut entry = Entry::new("key");
+        entry.path(node::os::temp_dir().join("ferrous-actions-cache-save-missing-path"));
+        assert_eq!(entry.save().await?, SaveOutcome::Skipped);
+        assert_eq!(SaveOutcome::Skipped.cache_id(), None);
+        assert_eq!(SaveOutcome::Saved(3).cache_id(), Some(3));
+        Ok(())
+    }
+}
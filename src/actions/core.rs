@@ -1,5 +1,9 @@
 use crate::node::path::Path;
 use js_sys::{JsString, Number, Object};
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use wasm_bindgen::JsValue;
 
 /// Formats and outputs a GitHub actions log line at debug level
@@ -47,12 +51,14 @@ macro_rules! error {
 
 /// Outputs a GitHub actions log line at debug level
 pub fn debug<S: Into<JsString>>(message: S) {
-    ffi::debug(&message.into());
+    let message = message.into();
+    emit(move || ffi::debug(&message));
 }
 
 /// Outputs a GitHub actions log line at info level
 pub fn info<S: Into<JsString>>(message: S) {
-    ffi::info(&message.into());
+    let message = message.into();
+    emit(move || ffi::info(&message));
 }
 
 /// Outputs a GitHub actions log line at notice level (this will be
@@ -67,7 +73,7 @@ pub fn warning<A: Into<Annotation>>(message: A) {
     message.into().warning();
 }
 
-/// Formats and outputs a GitHub actions log line at error level (this will be
+/// Outputs a GitHub actions log line at error level (this will be
 /// an annotation)
 pub fn error<A: Into<Annotation>>(message: A) {
     message.into().error();
@@ -131,6 +137,13 @@ impl Input {
         ffi.required = Some(true);
         ffi::get_input(&self.name, Some(ffi)).map(String::from)
     }
+
+    /// Gets the specified input as a list of lines. Empty lines are discarded.
+    pub fn get_multiline(&mut self) -> Result<Vec<String>, JsValue> {
+        let ffi = self.to_ffi();
+        let lines = ffi::get_multiline_input(&self.name, Some(ffi))?;
+        Ok(lines.iter().map(|line| JsString::from(line).into()).collect())
+    }
 }
 
 /// Builder for outputting annotations
@@ -216,7 +229,7 @@ impl Annotation {
             properties.set(&"title".into(), JsString::from(title.as_str()).as_ref());
         }
         if let Some(file) = &self.file {
-            properties.set(&"file".into(), file.to_js_string().as_ref());
+            properties.set(&"file".into(), file.as_js_string().as_ref());
         }
         for (name, value) in [
             ("startLine", &self.start_line),
@@ -250,11 +263,11 @@ impl Annotation {
     pub fn output(&self, level: AnnotationLevel) {
         let message = JsString::from(self.message.as_str());
         let properties = self.build_js_properties();
-        match level {
+        emit(move || match level {
             AnnotationLevel::Error => ffi::error(&message, Some(properties)),
             AnnotationLevel::Warning => ffi::warning(&message, Some(properties)),
             AnnotationLevel::Notice => ffi::notice(&message, Some(properties)),
-        }
+        });
     }
 }
 
@@ -264,14 +277,88 @@ pub fn get_input<I: Into<Input>>(input: I) -> Result<Option<String>, JsValue> {
     input.get()
 }
 
+/// Options controlling how an action input is retrieved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputOptions {
+    /// Whether an error should be returned if the input is not defined
+    pub required: bool,
+
+    /// Whether leading and trailing whitespace should be removed from the
+    /// value
+    pub trim_whitespace: bool,
+}
+
+impl Default for InputOptions {
+    fn default() -> InputOptions {
+        InputOptions {
+            required: false,
+            trim_whitespace: true,
+        }
+    }
+}
+
+/// Retrieves the action input of the specified name using the specified
+/// options. An undefined input that is not required is returned as an empty
+/// string.
+pub fn get_input_with_options<I: Into<Input>>(input: I, options: InputOptions) -> Result<String, JsValue> {
+    let mut input = input.into();
+    input.trim_whitespace(options.trim_whitespace);
+    if options.required {
+        input.get_required()
+    } else {
+        input.get().map(Option::unwrap_or_default)
+    }
+}
+
+/// Retrieves the action input of the specified name as a list of lines
+pub fn get_multiline_input<I: Into<Input>>(input: I) -> Result<Vec<String>, JsValue> {
+    let mut input = input.into();
+    input.get_multiline()
+}
+
+/// Retrieves the action input of the specified name as a list of items
+/// separated by whitespace, commas or newlines
+pub fn get_input_list<I: Into<Input>>(input: I) -> Result<Vec<String>, JsValue> {
+    let value = get_input(input)?.unwrap_or_default();
+    Ok(split_list(&value).map(String::from).collect())
+}
+
+/// Splits a list of items separated by whitespace, commas or newlines
+pub fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|item| !item.is_empty())
+}
+
 /// Mark this action as failed for the specified reason
 pub fn set_failed<M: Into<JsString>>(message: M) {
     ffi::set_failed(&message.into());
 }
 
-/// Adds the specified path into `$PATH` for use by later actions
+/// Adds the specified path into `$PATH` for use by later actions. `$PATH` is
+/// also updated for the current process, so executables in the path can be
+/// invoked immediately.
+///
+/// Nothing is done if the path is already present in `$PATH`, since later
+/// steps inherit it from this one.
 pub fn add_path(path: &Path) {
-    ffi::add_path(&path.into());
+    let current = crate::node::process::get_env().get("PATH").cloned().unwrap_or_default();
+    if path_list_contains(&current, path) {
+        debug!("Not adding {} to PATH since it is already present", path);
+    } else {
+        ffi::add_path(&path.into());
+        super::io::invalidate_which_cache();
+    }
+}
+
+/// Whether a list of paths separated by the platform delimiter contains the
+/// specified path
+fn path_list_contains(path_list: &str, path: &Path) -> bool {
+    let delimiter = crate::node::path::delimiter();
+    path_list
+        .split(delimiter.as_ref())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| Path::from(entry) == *path)
 }
 
 /// Exports an environment variable from the action
@@ -300,6 +387,16 @@ pub fn get_state<N: Into<JsString>>(name: N) -> Option<String> {
     }
 }
 
+/// Enables or disables the echoing of workflow commands to the log
+pub fn set_command_echo(enabled: bool) {
+    ffi::set_command_echo(enabled);
+}
+
+/// Returns whether step debug logging is enabled for the workflow run
+pub fn is_debug() -> bool {
+    ffi::is_debug()
+}
+
 /// Starts a foldable group
 pub fn start_group<N: Into<JsString>>(name: N) {
     ffi::start_group(&name.into());
@@ -310,10 +407,104 @@ pub fn end_group() {
     ffi::end_group();
 }
 
+type DeferredOutput = Box<dyn FnOnce()>;
+
+thread_local! {
+    /// Log lines and annotations held back by the future currently being
+    /// polled by `capture_output`, if any
+    static CAPTURED: RefCell<Option<Vec<DeferredOutput>>> = RefCell::new(None);
+}
+
+/// Writes output immediately, or holds it back if it is being captured
+fn emit<F: FnOnce() + 'static>(output: F) {
+    let mut output = Some(output);
+    CAPTURED.with(|captured| {
+        if let Some(captured) = captured.borrow_mut().as_mut() {
+            captured.push(Box::new(output.take().expect("Output already taken")));
+        }
+    });
+    if let Some(output) = output {
+        output();
+    }
+}
+
+/// Log lines and annotations held back by `capture_output`
+pub struct CapturedOutput {
+    outputs: Vec<DeferredOutput>,
+}
+
+impl std::fmt::Debug for CapturedOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CapturedOutput")
+            .field("len", &self.outputs.len())
+            .finish()
+    }
+}
+
+impl CapturedOutput {
+    /// Writes the captured output. If this happens while output is being
+    /// captured again, it is passed on to the enclosing capture.
+    pub fn flush(self) {
+        for output in self.outputs {
+            emit(output);
+        }
+    }
+}
+
+struct Capture<F: Future> {
+    inner: Pin<Box<F>>,
+    outputs: Vec<DeferredOutput>,
+}
+
+impl<F: Future> Future for Capture<F> {
+    type Output = (F::Output, CapturedOutput);
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let outputs = std::mem::take(&mut this.outputs);
+        let enclosing = CAPTURED.with(|captured| captured.replace(Some(outputs)));
+        let result = this.inner.as_mut().poll(cx);
+        this.outputs = CAPTURED
+            .with(|captured| captured.replace(enclosing))
+            .unwrap_or_default();
+        result.map(|value| {
+            let outputs = std::mem::take(&mut this.outputs);
+            (value, CapturedOutput { outputs })
+        })
+    }
+}
+
+/// Runs a future, holding back the log lines and annotations it writes until
+/// the returned `CapturedOutput` is flushed. This keeps the output of
+/// concurrent operations readable. Output written from callbacks that run
+/// outside the future (e.g. those of a spawned process) is not captured.
+pub async fn capture_output<F: Future>(future: F) -> (F::Output, CapturedOutput) {
+    Capture {
+        inner: Box::pin(future),
+        outputs: Vec::new(),
+    }
+    .await
+}
+
+/// Converts the separators in a path to the `/` form used on POSIX platforms
+pub fn to_posix_path<P: Into<JsString>>(path: P) -> String {
+    ffi::to_posix_path(&path.into()).into()
+}
+
+/// Converts the separators in a path to the `\\` form used on Windows
+pub fn to_win32_path<P: Into<JsString>>(path: P) -> String {
+    ffi::to_win32_path(&path.into()).into()
+}
+
+/// Converts the separators in a path to the form used on the current platform
+pub fn to_platform_path<P: Into<JsString>>(path: P) -> String {
+    ffi::to_platform_path(&path.into()).into()
+}
+
 /// Low-level bindings to the GitHub Actions Toolkit "core" API
 #[allow(clippy::drop_non_drop)]
 pub mod ffi {
-    use js_sys::{JsString, Object};
+    use js_sys::{Array, JsString, Object};
     use wasm_bindgen::prelude::*;
 
     #[wasm_bindgen]
@@ -330,6 +521,10 @@ pub mod ffi {
         #[wasm_bindgen(js_name = "getInput", catch)]
         pub fn get_input(name: &JsString, options: Option<InputOptions>) -> Result<JsString, JsValue>;
 
+        /// Gets the values of a multiline input. Each value is also trimmed.
+        #[wasm_bindgen(js_name = "getMultilineInput", catch)]
+        pub fn get_multiline_input(name: &JsString, options: Option<InputOptions>) -> Result<Array, JsValue>;
+
         /// Writes info
         #[wasm_bindgen]
         pub fn info(message: &JsString);
@@ -371,10 +566,152 @@ pub mod ffi {
         #[wasm_bindgen(js_name = "getState")]
         pub fn get_state(name: &JsString) -> JsString;
 
+        #[wasm_bindgen(js_name = "setCommandEcho")]
+        pub fn set_command_echo(enabled: bool);
+
+        #[wasm_bindgen(js_name = "isDebug")]
+        pub fn is_debug() -> bool;
+
         #[wasm_bindgen(js_name = "startGroup")]
         pub fn start_group(name: &JsString);
 
         #[wasm_bindgen(js_name = "endGroup")]
         pub fn end_group();
+
+        #[wasm_bindgen(js_name = "toPosixPath")]
+        pub fn to_posix_path(path: &JsString) -> JsString;
+
+        #[wasm_bindgen(js_name = "toWin32Path")]
+        pub fn to_win32_path(path: &JsString) -> JsString;
+
+        #[wasm_bindgen(js_name = "toPlatformPath")]
+        pub fn to_platform_path(path: &JsString) -> JsString;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{capture_output, emit, get_input_with_options, split_list, InputOptions};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    async fn captured_output_is_deferred() {
+        let log: Rc<RefCell<Vec<&str>>> = Rc::default();
+        let record = |line: &'static str| {
+            let log = log.clone();
+            move || log.borrow_mut().push(line)
+        };
+        let first = capture_output(async {
+            emit(record("first"));
+            let (_, inner) = capture_output(async { emit(record("nested")) }).await;
+            inner.flush();
+        });
+        let second = capture_output(async { emit(record("second")) });
+        let ((_, first), (_, second)) = futures::future::join(first, second).await;
+        emit(record("uncaptured"));
+        assert_eq!(*log.borrow(), ["uncaptured"]);
+        second.flush();
+        first.flush();
+        assert_eq!(*log.borrow(), ["uncaptured", "second", "first", "nested"]);
+    }
+
+    fn split(value: &str) -> Vec<&str> {
+        split_list(value).collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn split_mixed_separators() {
+        let expected = ["indices", "crates", "git-repos"];
+        assert_eq!(split("indices crates git-repos"), expected);
+        assert_eq!(split("indices,crates,git-repos"), expected);
+        assert_eq!(split("indices\ncrates\ngit-repos\n"), expected);
+        assert_eq!(split(" indices, crates\n\tgit-repos ,"), expected);
+    }
+
+    #[wasm_bindgen_test]
+    fn add_path_updates_process_path() {
+        use crate::node;
+
+        let folder = node::os::temp_dir().join("ferrous-actions-add-path-test");
+        super::add_path(&folder);
+        let path = node::process::get_env().get("PATH").cloned().unwrap_or_default();
+        let first = path.split(node::path::delimiter().as_ref()).next();
+        assert_eq!(first, Some(folder.to_string().as_str()));
+    }
+
+    #[wasm_bindgen_test]
+    fn add_path_skips_existing_entries() {
+        use crate::node;
+
+        let folder = node::os::temp_dir().join("ferrous-actions-add-path-twice-test");
+        let count = || {
+            let path = node::process::get_env().get("PATH").cloned().unwrap_or_default();
+            path.split(node::path::delimiter().as_ref())
+                .filter(|entry| *entry == folder.to_string())
+                .count()
+        };
+        super::add_path(&folder);
+        assert_eq!(count(), 1);
+        super::add_path(&folder);
+        assert_eq!(count(), 1);
+
+        let delimiter = node::path::delimiter();
+        let list = format!("{}{}{}", "other", delimiter, folder);
+        assert!(super::path_list_contains(&list, &folder));
+        assert!(!super::path_list_contains("other", &folder));
+    }
+
+    #[wasm_bindgen_test]
+    fn input_options() {
+        use crate::node;
+
+        let name = format!("ferrous-actions-options-{}", crate::nonce::build(8));
+        let missing = format!("{}-missing", name);
+        let variable = format!("INPUT_{}", name.to_uppercase());
+        node::process::set_var(&variable, "  value  ");
+        let trimmed = get_input_with_options(name.as_str(), InputOptions::default());
+        let untrimmed = get_input_with_options(
+            name.as_str(),
+            InputOptions {
+                trim_whitespace: false,
+                ..InputOptions::default()
+            },
+        );
+        node::process::remove_var(&variable);
+        assert_eq!(trimmed.expect("Failed to get input"), "value");
+        assert_eq!(untrimmed.expect("Failed to get input"), "  value  ");
+
+        let required = InputOptions {
+            required: true,
+            ..InputOptions::default()
+        };
+        assert!(get_input_with_options(missing.as_str(), required).is_err());
+        assert_eq!(
+            get_input_with_options(missing.as_str(), InputOptions::default()).expect("Failed to get input"),
+            ""
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn path_separator_conversion() {
+        use crate::node;
+
+        let mixed = "a/b\\c/d";
+        assert_eq!(super::to_posix_path(mixed), "a/b/c/d");
+        assert_eq!(super::to_win32_path(mixed), "a\\b\\c\\d");
+        let expected = if node::os::platform() == "win32" {
+            "a\\b\\c\\d"
+        } else {
+            "a/b/c/d"
+        };
+        assert_eq!(super::to_platform_path(mixed), expected);
+    }
+
+    #[wasm_bindgen_test]
+    fn split_empty() {
+        assert!(split("").is_empty());
+        assert!(split(" ,\n, ").is_empty());
     }
 }
@@ -5,6 +5,7 @@ use crate::node::path::Path;
 use js_sys::{JsString, Object};
 use parking_lot::Mutex;
 use std::sync::Arc;
+use std::time::Duration;
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsValue;
 
@@ -85,6 +86,48 @@ impl AsRef<JsValue> for StreamToLines {
     }
 }
 
+/// The result of executing a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecResult {
+    /// The exit code of the command
+    pub exit_code: i32,
+
+    /// The wall-clock time taken to execute the command
+    pub duration: Duration,
+}
+
+/// The captured output of a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecOutput {
+    /// The exit code of the command
+    pub exit_code: i32,
+
+    /// Everything the command wrote to standard output
+    pub stdout: String,
+
+    /// Everything the command wrote to standard error
+    pub stderr: String,
+}
+
+/// Arguments for a single execution of a command, along with the objects that
+/// must outlive it
+struct Invocation {
+    command: JsString,
+    args: Vec<JsString>,
+    options: Object,
+    outline_adapter: Option<StreamToLines>,
+    errline_adapter: Option<StreamToLines>,
+    _sink: noop_stream::Sink,
+}
+
+impl Drop for Invocation {
+    fn drop(&mut self) {
+        // Be explicit about line-buffer flushing
+        drop(self.outline_adapter.take());
+        drop(self.errline_adapter.take());
+    }
+}
+
 /// Builder for executing a command
 pub struct Command {
     command: Path,
@@ -117,6 +160,53 @@ impl Command {
 
     /// Executes the command and returns the status code
     pub async fn exec(&mut self) -> Result<i32, JsValue> {
+        self.exec_timed().await.map(|result| result.exit_code)
+    }
+
+    /// Executes the command and returns the status code along with how long
+    /// the command took to execute
+    pub async fn exec_timed(&mut self) -> Result<ExecResult, JsValue> {
+        let invocation = self.prepare(false);
+        let start = node::time::monotonic_ms();
+        let result = ffi::exec(&invocation.command, Some(invocation.args.clone()), &invocation.options)
+            .await
+            .map(|r| {
+                #[allow(clippy::cast_possible_truncation)]
+                let exit_code = r.as_f64().expect("exec didn't return a number") as i32;
+                let elapsed_ms = (node::time::monotonic_ms() - start).max(0.0);
+                ExecResult {
+                    exit_code,
+                    duration: Duration::from_secs_f64(elapsed_ms / 1000.0),
+                }
+            });
+        drop(invocation);
+        result
+    }
+
+    /// Executes the command and captures its standard output and error. Unlike
+    /// `exec`, a non-zero exit code is not treated as an error.
+    ///
+    /// Captured output is also passed to any line callbacks and written to
+    /// the configured `Stdio` locations.
+    pub async fn exec_output(&mut self) -> Result<ExecOutput, JsValue> {
+        let invocation = self.prepare(true);
+        let result =
+            ffi::get_exec_output(&invocation.command, Some(invocation.args.clone()), &invocation.options).await;
+        drop(invocation);
+        let result = result?;
+        let get = |name: &str| js_sys::Reflect::get(&result, &name.into());
+        #[allow(clippy::cast_possible_truncation)]
+        let exit_code = get("exitCode")?
+            .as_f64()
+            .expect("getExecOutput exit code wasn't a number") as i32;
+        Ok(ExecOutput {
+            exit_code,
+            stdout: get("stdout")?.as_string().unwrap_or_default(),
+            stderr: get("stderr")?.as_string().unwrap_or_default(),
+        })
+    }
+
+    fn prepare(&self, ignore_return_code: bool) -> Invocation {
         let command = self.command.to_string();
         let command = Self::escape_command(command.as_str());
         let command: JsString = command.into();
@@ -133,7 +223,7 @@ impl Command {
             listeners.set(&"stderr".into(), callback.as_ref());
         }
 
-        options.set(&"cwd".into(), &self.cwd.to_js_string());
+        options.set(&"cwd".into(), self.cwd.as_js_string());
         let sink = noop_stream::Sink::default();
         if let StdioEnum::Null = self.stdout.inner {
             options.set(&"outStream".into(), sink.as_ref());
@@ -141,28 +231,37 @@ impl Command {
         if let StdioEnum::Null = self.stderr.inner {
             options.set(&"errStream".into(), sink.as_ref());
         }
+        if ignore_return_code {
+            options.set(&"ignoreReturnCode".into(), &JsValue::TRUE);
+        }
 
         let listeners = Object::from_entries(&listeners).expect("Failed to convert listeners map to object");
         options.set(&"listeners".into(), &listeners);
         let options = Object::from_entries(&options).expect("Failed to convert options map to object");
-        let result = ffi::exec(&command, Some(args), &options).await.map(|r| {
-            #[allow(clippy::cast_possible_truncation)]
-            let code = r.as_f64().expect("exec didn't return a number") as i32;
-            code
-        });
-
-        // Be explicit about line-buffer flushing
-        drop(outline_adapter);
-        drop(errline_adapter);
-        result
+        Invocation {
+            command,
+            args,
+            options,
+            outline_adapter,
+            errline_adapter,
+            _sink: sink,
+        }
     }
 
     /// Sets a callback to be called each time a new line is written to standard
     /// output. Note that line splitting is done by an internal
     /// re-implementation of line splitting and not the GitHub Actions
-    /// Toolkit one due to issues with the latter.
+    /// Toolkit one due to issues with the latter. If a callback has already
+    /// been set, both are called, in the order they were set.
     pub fn outline<F: Fn(&str) + 'static + Sync + Send>(&mut self, callback: F) -> &mut Command {
-        self.outline = Some(Arc::new(Box::new(callback)));
+        let callback: Box<dyn Fn(&str)> = match self.outline.take() {
+            Some(previous) => Box::new(move |line| {
+                previous(line);
+                callback(line);
+            }),
+            None => Box::new(callback),
+        };
+        self.outline = Some(Arc::new(callback));
         self
     }
 
@@ -246,5 +345,64 @@ pub mod ffi {
             args: Option<Vec<JsString>>,
             options: &JsValue,
         ) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(js_name = "getExecOutput", catch)]
+        pub async fn get_exec_output(
+            comand_line: &JsString,
+            args: Option<Vec<JsString>>,
+            options: &JsValue,
+        ) -> Result<JsValue, JsValue>;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Command;
+    use crate::node::path::Path;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn chained_args() {
+        let mut command = Command::from(&Path::from("cargo"));
+        command
+            .arg("+nightly")
+            .arg(String::from("build"))
+            .args(["--release", "--locked"])
+            .arg("--verbose");
+        let args: Vec<String> = command.args.iter().map(String::from).collect();
+        assert_eq!(args, ["+nightly", "build", "--release", "--locked", "--verbose"]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn exec_duration() -> Result<(), wasm_bindgen::JsValue> {
+        use crate::actions::io;
+        use std::time::Duration;
+
+        let node_path = io::which("node", true).await?;
+        let result = Command::from(&node_path)
+            .args(["-e", "setTimeout(() => {}, 50)"])
+            .exec_timed()
+            .await?;
+        assert_eq!(result.exit_code, 0);
+        assert!(result.duration >= Duration::from_millis(40), "{:?}", result.duration);
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn exec_output_capture() -> Result<(), wasm_bindgen::JsValue> {
+        use crate::actions::exec::Stdio;
+        use crate::actions::io;
+
+        let node_path = io::which("node", true).await?;
+        let output = Command::from(&node_path)
+            .args(["-e", "console.log('hello'); console.error('oops'); process.exit(3)"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .exec_output()
+            .await?;
+        assert_eq!(output.exit_code, 3);
+        assert_eq!(output.stdout.trim_end(), "hello");
+        assert_eq!(output.stderr.trim_end(), "oops");
+        Ok(())
     }
 }
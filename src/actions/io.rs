@@ -1,13 +1,68 @@
 use crate::node::path::Path;
 use js_sys::JsString;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use wasm_bindgen::JsValue;
 
+lazy_static! {
+    /// Paths of commands previously found by `which`. Only successful lookups
+    /// are recorded, so a command installed later in the run will still be
+    /// found.
+    static ref WHICH_CACHE: Mutex<HashMap<String, String>> = Mutex::default();
+}
+
+/// Forgets the locations of all previously located commands. This should be
+/// called when `$PATH` changes or a command may have been replaced.
+pub fn invalidate_which_cache() {
+    WHICH_CACHE.lock().clear();
+}
+
+async fn which_cached(tool: JsString) -> Result<Option<Path>, JsValue> {
+    let name = String::from(&tool);
+    let cached = WHICH_CACHE.lock().get(&name).cloned();
+    if let Some(cached) = cached {
+        let cached = Path::from(&cached);
+        // The command may have been removed since it was located
+        if cached.exists().await {
+            return Ok(Some(cached));
+        }
+    }
+    let path = ffi::which(&tool, Some(false)).await?;
+    let path: JsString = path.into();
+    if path.length() == 0 {
+        WHICH_CACHE.lock().remove(&name);
+        Ok(None)
+    } else {
+        let path = Path::from(path);
+        WHICH_CACHE.lock().insert(name, path.to_string());
+        Ok(Some(path))
+    }
+}
+
 /// Locates the absolute path of the command with the specified name. If `check`
 /// is set, an error will be returned if the command doesn't exist.
+///
+/// Located commands are remembered for the rest of the run. See
+/// `invalidate_which_cache`.
 pub async fn which<T: Into<JsString>>(tool: T, check: bool) -> Result<Path, JsValue> {
-    let path = ffi::which(&tool.into(), Some(check)).await?;
-    let path: JsString = path.into();
-    Ok(Path::from(path))
+    let tool = tool.into();
+    match which_cached(tool.clone()).await? {
+        Some(path) => Ok(path),
+        None if check => {
+            // Let the toolkit produce its usual error
+            let path = ffi::which(&tool, Some(true)).await?;
+            let path: JsString = path.into();
+            Ok(Path::from(path))
+        }
+        None => Ok(Path::from("")),
+    }
+}
+
+/// Locates the absolute path of the command with the specified name, returning
+/// `None` if it doesn't exist
+pub async fn which_optional<T: Into<JsString>>(tool: T) -> Result<Option<Path>, JsValue> {
+    which_cached(tool.into()).await
 }
 
 /// Recursively removes all files at the specified path
@@ -31,3 +86,36 @@ pub mod ffi {
         pub async fn rm_rf(path: &JsString) -> Result<JsValue, JsValue>;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{invalidate_which_cache, which_optional, WHICH_CACHE};
+    use crate::node;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const FAKE_TOOL: &str = "ferrous-actions-which-cache-test-tool";
+
+    #[wasm_bindgen_test]
+    async fn which_cache() -> Result<(), JsValue> {
+        // A cached location that exists is returned without searching
+        let existing = node::process::cwd();
+        WHICH_CACHE.lock().insert(FAKE_TOOL.into(), existing.to_string());
+        assert_eq!(which_optional(FAKE_TOOL).await?, Some(existing));
+
+        // A stale location is ignored and replaced by a real search
+        let missing = node::os::temp_dir().join("ferrous-actions-which-cache-missing");
+        WHICH_CACHE.lock().insert(FAKE_TOOL.into(), missing.to_string());
+        assert_eq!(which_optional(FAKE_TOOL).await?, None);
+        assert!(!WHICH_CACHE.lock().contains_key(FAKE_TOOL));
+
+        let node_path = which_optional("node").await?.expect("node should be on the path");
+        assert_eq!(
+            WHICH_CACHE.lock().get("node").map(String::as_str),
+            Some(node_path.to_string().as_str())
+        );
+        invalidate_which_cache();
+        assert!(WHICH_CACHE.lock().is_empty());
+        Ok(())
+    }
+}
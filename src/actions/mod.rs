@@ -13,5 +13,8 @@ pub mod exec;
 /// The IO API (file system utilities)
 pub mod io;
 
+/// The job summary API (Markdown displayed on the workflow run summary page)
+pub mod summary;
+
 /// The tool cache API (downloading and extracting files)
 pub mod tool_cache;
@@ -0,0 +1,157 @@
+use crate::node;
+use crate::node::path::Path;
+use wasm_bindgen::{JsError, JsValue};
+
+/// Environment variable containing the path of the job summary file
+const SUMMARY_ENV_VAR: &str = "GITHUB_STEP_SUMMARY";
+
+/// Builder for Markdown content displayed on the summary page of a workflow
+/// run.
+///
+/// This is modelled on the `summary` API of the GitHub Actions Toolkit but is a
+/// Ferrous Actions re-implementation which emits Markdown rather than HTML.
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    buffer: String,
+}
+
+fn escape_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', "<br>")
+}
+
+impl Summary {
+    /// Returns the path of the job summary file, if one is defined
+    pub fn file_path() -> Option<Path> {
+        node::process::get_env()
+            .get(SUMMARY_ENV_VAR)
+            .filter(|path| !path.is_empty())
+            .map(Path::from)
+    }
+
+    /// Appends text without any formatting
+    pub fn add_raw(&mut self, text: &str) -> &mut Summary {
+        self.buffer += text;
+        self
+    }
+
+    /// Appends a line ending
+    pub fn add_eol(&mut self) -> &mut Summary {
+        self.buffer.push('\n');
+        self
+    }
+
+    /// Appends a heading of the specified level, which is clamped to between 1
+    /// and 6
+    pub fn add_heading(&mut self, text: &str, level: usize) -> &mut Summary {
+        let level = level.clamp(1, 6);
+        self.buffer += &format!("{} {}\n\n", "#".repeat(level), text);
+        self
+    }
+
+    /// Appends a fenced code block, optionally annotated with a language
+    pub fn add_code_block(&mut self, code: &str, language: Option<&str>) -> &mut Summary {
+        let code = code.trim_end_matches('\n');
+        self.buffer += &format!("```{}\n{}\n```\n\n", language.unwrap_or_default(), code);
+        self
+    }
+
+    /// Appends a table with the specified header and rows. Rows with fewer
+    /// cells than the header are padded.
+    pub fn add_table<R, C>(&mut self, header: &[&str], rows: R) -> &mut Summary
+    where
+        R: IntoIterator<Item = C>,
+        C: IntoIterator,
+        C::Item: AsRef<str>,
+    {
+        let render_row = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        self.buffer += &render_row(header.iter().map(|cell| escape_cell(cell)).collect());
+        self.buffer += &render_row(vec!["---".to_string(); header.len()]);
+        for row in rows {
+            let mut cells: Vec<String> = row.into_iter().map(|cell| escape_cell(cell.as_ref())).collect();
+            if cells.len() < header.len() {
+                cells.resize(header.len(), String::new());
+            }
+            self.buffer += &render_row(cells);
+        }
+        self.buffer.push('\n');
+        self
+    }
+
+    /// Returns the content accumulated so far
+    pub fn stringify(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Returns whether any content has been accumulated
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Discards accumulated content
+    pub fn clear(&mut self) -> &mut Summary {
+        self.buffer.clear();
+        self
+    }
+
+    /// Appends the accumulated content to the specified file and clears it
+    pub async fn write_to(&mut self, path: &Path) -> Result<(), JsValue> {
+        node::fs::append_file(path, self.buffer.as_bytes()).await?;
+        self.clear();
+        Ok(())
+    }
+
+    /// Appends the accumulated content to the job summary file and clears it
+    pub async fn write(&mut self) -> Result<(), JsValue> {
+        let path = Self::file_path().ok_or_else(|| {
+            JsError::new(&format!(
+                "Unable to find environment variable {}. Job summaries may not be supported by this runner.",
+                SUMMARY_ENV_VAR
+            ))
+        })?;
+        self.write_to(&path).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Summary;
+    use crate::node;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    async fn write_summary() -> Result<(), JsValue> {
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-summary-")).await?;
+        let path = temp_dir.path().join("summary.md");
+        let mut summary = Summary::default();
+        summary
+            .add_heading("Cache", 2)
+            .add_table(&["Type", "Saved"], [["crates", "1"], ["git|repos", "0"]])
+            .add_code_block("fn main() {}\n", Some("rust"))
+            .add_raw("Done")
+            .add_eol();
+        summary.write_to(&path).await?;
+        assert!(summary.is_empty());
+        summary.add_raw("Appended\n");
+        summary.write_to(&path).await?;
+
+        let written = String::from_utf8(node::fs::read_file(&path).await?).expect("Summary was not UTF-8");
+        let expected = "\
+## Cache
+
+| Type | Saved |
+| --- | --- |
+| crates | 1 |
+| git\\|repos | 0 |
+
+```rust
+fn main() {}
+```
+
+Done
+Appended
+";
+        assert_eq!(written, expected);
+        temp_dir.close().await
+    }
+}
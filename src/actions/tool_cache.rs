@@ -11,6 +11,7 @@ pub struct DownloadTool {
     url: JsString,
     dest: Option<Path>,
     auth: Option<JsString>,
+    headers: Vec<(String, String)>,
 }
 
 impl<U: Into<JsString>> From<U> for DownloadTool {
@@ -20,6 +21,7 @@ impl<U: Into<JsString>> From<U> for DownloadTool {
             url: url.into(),
             dest: None,
             auth: None,
+            headers: Vec::new(),
         }
     }
 }
@@ -37,6 +39,29 @@ impl DownloadTool {
         self
     }
 
+    /// Authorize the download with a bearer token, such as a GitHub token
+    pub fn bearer_token(&mut self, token: &str) -> &mut Self {
+        self.auth(format!("Bearer {}", token))
+    }
+
+    /// Specify an additional HTTP header. The authorization header should be
+    /// set using `auth` instead.
+    pub fn header(&mut self, name: &str, value: &str) -> &mut Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn build_headers(&self) -> Option<js_sys::Object> {
+        if self.headers.is_empty() {
+            return None;
+        }
+        let headers = js_sys::Map::new();
+        for (name, value) in &self.headers {
+            headers.set(&name.into(), &value.into());
+        }
+        Some(js_sys::Object::from_entries(&headers).expect("Failed to convert headers map to object"))
+    }
+
     /// Perform the download and return the path the file was downloaded to
     pub async fn download(&mut self) -> Result<Path, JsValue> {
         let dest = self.dest.as_ref().map(|dest| {
@@ -44,7 +69,8 @@ impl DownloadTool {
             resolved.push(dest.clone());
             JsString::from(&resolved)
         });
-        ffi::download_tool(&self.url, dest.as_ref(), self.auth.as_ref(), None)
+        let headers = self.build_headers();
+        ffi::download_tool(&self.url, dest.as_ref(), self.auth.as_ref(), headers.as_ref())
             .await
             .map(Into::<JsString>::into)
             .map(Into::<Path>::into)
@@ -111,7 +137,7 @@ pub async fn cache_dir(tool: &str, version: &str, path: &Path, arch: Option<&str
 
 /// Low level bindings for the GitHub Actions Toolkit "tool cache" API
 pub mod ffi {
-    use js_sys::{JsString, Map};
+    use js_sys::{JsString, Object};
     use wasm_bindgen::prelude::*;
 
     #[wasm_bindgen(module = "@actions/tool-cache")]
@@ -121,7 +147,7 @@ pub mod ffi {
             url: &JsString,
             dest: Option<&JsString>,
             auth: Option<&JsString>,
-            headers: Option<&Map>,
+            headers: Option<&Object>,
         ) -> Result<JsValue, JsValue>;
 
         #[wasm_bindgen(js_name = "cacheDir", catch)]
@@ -140,3 +166,27 @@ pub mod ffi {
         ) -> Result<JsValue, JsValue>;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::DownloadTool;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn auth_and_headers() {
+        let mut download = DownloadTool::from("https://example.com/tool.tar.gz");
+        assert!(download.auth.is_none());
+        assert!(download.build_headers().is_none());
+
+        download
+            .bearer_token("secret")
+            .header("Accept", "application/octet-stream");
+        assert_eq!(
+            download.auth.as_ref().map(String::from).as_deref(),
+            Some("Bearer secret")
+        );
+        let headers = download.build_headers().expect("Headers should be set");
+        let accept = js_sys::Reflect::get(&headers, &"Accept".into()).expect("Failed to read header");
+        assert_eq!(accept.as_string().as_deref(), Some("application/octet-stream"));
+    }
+}
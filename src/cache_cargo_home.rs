@@ -1,40 +1,57 @@
-use crate::action_paths::get_action_cache_dir;
-use crate::actions::cache::Entry as CacheEntry;
+use crate::action_paths::{cargo_home_from_env, get_action_cache_dir, rustup_home_from_env};
+use crate::actions::cache::{Entry as CacheEntry, SaveOutcome, ServiceFailure};
 use crate::actions::core;
 use crate::agnostic_path::AgnosticPath;
+use crate::cache_key_builder::Attribute;
+use crate::cargo_config::{self, CargoConfig};
 use crate::delta::{render_list as render_delta_list, Action as DeltaAction};
-use crate::dir_tree::match_relative_paths;
-use crate::fingerprinting::{fingerprint_path_with_ignores, Fingerprint, Ignores};
+use crate::dir_tree::{find_up, match_relative_paths};
+use crate::fingerprinting::{fingerprint_path_with_ignores, fingerprint_revision, Fingerprint, Ignores};
 use crate::hasher::Blake3 as Blake3Hasher;
-use crate::input_manager::{self, Input};
+use crate::input_manager::{self, parse_optional_input, Input};
 use crate::job::Job;
 use crate::node::os::homedir;
 use crate::node::path::Path;
-use crate::{actions, error, info, node, notice, safe_encoding, warning, Error};
+use crate::toolchain_file::ToolchainFile;
+use crate::{actions, debug, error, git_refs, github, info, node, notice, safe_encoding, timestamps, warning, Error};
 use chrono::{DateTime, Utc};
-use lazy_static::lazy_static;
 use rustup_toolchain_manifest::HashValue;
 use serde::{Deserialize, Serialize};
 use simple_path_match::{PathMatch, PathMatchBuilder};
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::Hash as _;
 use std::str::FromStr;
 use strum::{Display, EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
 
 const ATIMES_SUPPORTED_KEY: &str = "ACCESS_TIMES_SUPPORTED";
+const CACHE_HITS_KEY: &str = "CACHE_HITS";
 const DEFAULT_CROSS_OS_SHARING: CrossPlatformSharing = CrossPlatformSharing::All;
+const EXTRA_PATH_HASHES_KEY: &str = "EXTRA_PATH_HASHES";
+const CACHED_FOLDER_INFO_VERSION: u32 = 1;
+const RUSTUP_CACHE_NAME: &str = "rustup";
+const RUSTUP_KEY_DATA_KEY: &str = "RUSTUP_KEY_DATA";
 const SCOPE_HASH_KEY: &str = "SCOPE_HASH";
-
-lazy_static! {
-    static ref CARGO_HOME: String = {
-        node::process::get_env()
-            .get("CARGO_HOME")
-            .map(String::as_str)
-            .map_or_else(|| homedir().join(".cargo"), Path::from)
-            .to_string()
-    };
-}
+const TARGET_CACHE_NAME: &str = "target";
+const TARGET_DIR_KEY: &str = "TARGET_DIR";
+
+/// Binaries in `$CARGO_HOME/bin` which are installed and managed by Rustup
+const RUSTUP_PROXIES: [&str; 14] = [
+    "cargo",
+    "cargo-clippy",
+    "cargo-fmt",
+    "cargo-miri",
+    "clippy-driver",
+    "rls",
+    "rust-analyzer",
+    "rust-gdb",
+    "rust-gdbgui",
+    "rust-lldb",
+    "rustc",
+    "rustdoc",
+    "rustfmt",
+    "rustup",
+];
 
 #[derive(Clone, Copy, Debug, EnumString)]
 enum CrossPlatformSharing {
@@ -64,6 +81,37 @@ impl CrossPlatformSharing {
     }
 }
 
+/// Tuning of cache uploads and downloads. Unset values use the GitHub Actions
+/// Toolkit defaults.
+#[derive(Clone, Copy, Debug, Default)]
+struct TransferOptions {
+    upload_concurrency: Option<u32>,
+    upload_chunk_size: Option<u64>,
+    download_concurrency: Option<u32>,
+}
+
+impl TransferOptions {
+    fn apply(self, entry: &mut CacheEntry) {
+        if let Some(concurrency) = self.upload_concurrency {
+            entry.upload_concurrency(concurrency);
+        }
+        if let Some(size) = self.upload_chunk_size {
+            entry.upload_chunk_size(size);
+        }
+        if let Some(concurrency) = self.download_concurrency {
+            entry.download_concurrency(concurrency);
+        }
+    }
+}
+
+/// Counts of cache groups by what happened to them when saving
+#[derive(Clone, Copy, Debug, Default)]
+struct SaveStats {
+    saved: usize,
+    unchanged: usize,
+    not_saved: usize,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct Group {
     restore_key: Option<String>,
@@ -80,6 +128,21 @@ impl Group {
     }
 }
 
+/// A hash of the names and sizes of the files in a group. Contents are not
+/// hashed since modification times and line endings may legitimately differ
+/// once an entry is restored, so corruption which preserves sizes is not
+/// detected.
+fn integrity_hash(entries: &BTreeMap<AgnosticPath, Fingerprint>) -> String {
+    use std::hash::Hasher as _;
+
+    let mut hasher = Blake3Hasher::default();
+    for (path, fingerprint) in entries {
+        path.hash(&mut hasher);
+        fingerprint.size_hash().hash(&mut hasher);
+    }
+    safe_encoding::encode(hasher.finish().to_le_bytes())
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct Cache {
     cache_type: CacheType,
@@ -123,12 +186,15 @@ impl Cache {
         let entry_depth_relative = entry_depth - grouping_depth;
         let mut map = BTreeMap::new();
         for group in top_depth_paths {
+            if cache_type.excludes_group(&group) {
+                continue;
+            }
             let group_path = folder_path.join(&group);
             map.insert(
                 AgnosticPath::from(&group),
                 Group {
                     restore_key: sources.remove(&AgnosticPath::from(&group)),
-                    entries: Self::build_group(cache_type, &group_path, entry_depth_relative).await?,
+                    entries: Self::build_group(cache_type, &group_path, entry_depth_relative, true).await?,
                 },
             );
         }
@@ -161,7 +227,9 @@ impl Cache {
         cache_type: CacheType,
         scope: &HashValue,
         cross_platform_sharing: CrossPlatformSharing,
-    ) -> Result<Cache, Error> {
+        transfer_options: TransferOptions,
+        verify: bool,
+    ) -> Result<(Cache, HitKind), Error> {
         use crate::access_times::revert_folder;
         use itertools::Itertools as _;
 
@@ -169,7 +237,7 @@ impl Cache {
 
         // Delete existing cache
         let folder_path = find_path(cache_type);
-        if folder_path.exists().await {
+        if cache_type.manages_whole_folder() && folder_path.exists().await {
             warning!(
                 concat!(
                     "Cache action will delete existing contents of {} and derived information. ",
@@ -177,12 +245,13 @@ impl Cache {
                 ),
                 folder_path
             );
-            actions::io::rm_rf(&folder_path).await?;
+            node::fs::remove_dir_all_safe(&folder_path).await?;
         }
 
         let entry = build_cache_entry_dependencies(cache_type, scope, &job)?;
-        let restore_key = entry.restore().await.map_err(Error::Js)?;
+        let restore_key = tolerate_service_failure(entry.restore().await, "restore dependency list")?.flatten();
         let mut restore_keys = HashMap::new();
+        let mut expected_groups = None;
         if let Some(restore_key) = restore_key {
             info!(
                 "Located dependencies list for {} in cache using key {}.",
@@ -194,6 +263,7 @@ impl Cache {
                 let file_contents = node::fs::read_file(&dep_file_path).await?;
                 postcard::from_bytes(&file_contents)?
             };
+            expected_groups = Some(groups.len());
             let group_list_string = groups.iter().map(|g| &g.path).join(", ");
             info!(
                 "The following groups will be restored for cache type {}: {}",
@@ -201,10 +271,34 @@ impl Cache {
                 group_list_string
             );
             for group in &groups {
-                let entry = Self::group_identifier_to_cache_entry(cache_type, group, cross_platform_sharing);
-                if let Some(name) = entry.restore().await? {
+                let entry = Self::group_identifier_to_cache_entry(
+                    cache_type,
+                    group,
+                    cross_platform_sharing,
+                    transfer_options,
+                    None,
+                    None,
+                );
+                let restored = tolerate_service_failure(entry.restore().await, "restore cache group")?;
+                if let Some(name) = restored.flatten() {
                     info!("Restored cache key: {}", name);
-                    restore_keys.insert(group.path.clone(), name);
+                    let group_path = folder_path.join(&group.path);
+                    if node::fs::symlink_metadata(&group_path).await?.is_directory() {
+                        let num_updated = timestamps::apply(&group_path).await?;
+                        if num_updated != 0 {
+                            info!("Restored modification times of {} files", num_updated);
+                        }
+                    }
+                    if verify && !Self::verify_group(cache_type, group, &name).await? {
+                        warning!(
+                            "Restored {} cache group {} failed verification. It will be treated as a cache miss.",
+                            cache_type.friendly_name(),
+                            group.path
+                        );
+                        actions::io::rm_rf(&folder_path.join(&group.path)).await?;
+                    } else {
+                        restore_keys.insert(group.path.clone(), name);
+                    }
                 } else {
                     info!(
                         "Failed to find {} cache entry for {}",
@@ -218,18 +312,45 @@ impl Cache {
         }
         // Ensure we at least have an empty folder
         node::fs::create_dir_all(&folder_path).await?;
+        if cache_type == CacheType::Bin {
+            ensure_executable(&folder_path).await?;
+        }
         // Revert access times
         revert_folder(&folder_path).await?;
-        Self::new_with_sources(cache_type, restore_keys).await
+        let hit = HitKind::from_counts(expected_groups, restore_keys.len());
+        let cache = Self::new_with_sources(cache_type, restore_keys).await?;
+        Ok((cache, hit))
     }
 
-    pub async fn save_changes(
-        &self,
-        old: &Cache,
-        scope_hash: &HashValue,
-        min_recache_interval: &chrono::Duration,
-        cross_platform_sharing: CrossPlatformSharing,
-    ) -> Result<(), Error> {
+    async fn verify_group(cache_type: CacheType, group: &GroupIdentifier, restored_key: &str) -> Result<bool, Error> {
+        use crate::cache_key_builder::CacheKeyBuilder;
+
+        let expected = if let Some(expected) = CacheKeyBuilder::get_attribute(restored_key, Attribute::Integrity) {
+            expected
+        } else {
+            info!("Cache entry has no integrity information so cannot be verified");
+            return Ok(true);
+        };
+        let group_path = find_path(cache_type).join(&group.path);
+        if !group_path.exists().await {
+            return Ok(false);
+        }
+        Ok(Self::group_integrity_hash(cache_type, &group_path).await? == expected)
+    }
+
+    /// The integrity hash of the group at the specified path. Every file is
+    /// visited, including those of Git indices which are otherwise
+    /// fingerprinted by revision, so that missing object and pack files are
+    /// detected.
+    async fn group_integrity_hash(cache_type: CacheType, group_path: &Path) -> Result<String, Error> {
+        let entry_level = cache_type.entry_depth() - cache_type.grouping_depth();
+        let entries = Self::build_group(cache_type, group_path, entry_level, false).await?;
+        Ok(integrity_hash(&entries))
+    }
+
+    async fn save_changes(&self, old: &Cache, options: SaveOptions<'_>) -> Result<SaveStats, Error> {
+        let scope_hash = options.scope_hash;
+        let min_recache_interval = &options.min_recache_interval;
         let job = Job::from_env()?;
         let dep_file_path = dependency_file_path(self.cache_type, scope_hash, &job)?;
         let old_groups = if dep_file_path.exists().await {
@@ -252,10 +373,13 @@ impl Cache {
             }
             node::fs::write_file(&dep_file_path, &serialized_groups).await?;
             let dependencies_entry = build_cache_entry_dependencies(self.cache_type, scope_hash, &job)?;
-            dependencies_entry.save().await?;
-            info!("{} dependency list was successfully saved.", self.cache_type);
+            let saved = tolerate_service_failure(dependencies_entry.save().await, "save dependency list")?;
+            if saved.and_then(|outcome| outcome.cache_id()).is_some() {
+                info!("{} dependency list was successfully saved.", self.cache_type);
+            }
         }
 
+        let mut stats = SaveStats::default();
         for (path, group) in &self.root {
             let (attempt_save, old_restore_key) = if let Some(old_group) = old.root.get(path) {
                 let group_delta = Self::compare_groups(&old_group.entries, &group.entries);
@@ -297,35 +421,91 @@ impl Cache {
 
             if attempt_save {
                 let identifier = self.build_group_identifier(path);
-                let entry = Self::group_identifier_to_cache_entry(self.cache_type, &identifier, cross_platform_sharing);
+                let group_path = self.get_root_path().join(path);
+                let integrity = Self::group_integrity_hash(self.cache_type, &group_path).await?;
+                let entry = Self::group_identifier_to_cache_entry(
+                    self.cache_type,
+                    &identifier,
+                    options.cross_platform_sharing,
+                    options.transfer_options,
+                    Some(integrity),
+                    options.rustc_version,
+                );
                 info!(
                     "Saving modified {} cache group {}",
                     self.cache_type.friendly_name(),
                     path
                 );
-                if entry.save_if_update(old_restore_key).await?.is_some() {
-                    info!(
-                        "{} cache group {} saved successfully.",
-                        self.cache_type.friendly_name(),
-                        path
-                    );
-                } else {
-                    info!(
-                        concat!(
-                            "It looks like the changed {} cache group {} already exists. ",
-                            "Not saving our version this time around because we can't be certain it's a useful update. "
-                        ),
-                        self.cache_type.friendly_name(),
-                        path
-                    );
+                let record_timestamps =
+                    options.preserve_timestamps && node::fs::symlink_metadata(&group_path).await?.is_directory();
+                if record_timestamps {
+                    timestamps::record(&group_path).await?;
+                }
+                let saved = entry.save_if_update(old_restore_key).await;
+                if record_timestamps {
+                    timestamps::remove_record(&group_path).await?;
+                }
+                match tolerate_service_failure(saved, "save cache group")? {
+                    Some(Some(SaveOutcome::NotSaved)) => {
+                        info!(
+                            "{} cache group {} was not saved. Another job may already be saving it.",
+                            self.cache_type.friendly_name(),
+                            path
+                        );
+                        stats.not_saved += 1;
+                    }
+                    Some(Some(SaveOutcome::Skipped)) => {
+                        info!(
+                            "{} cache group {} no longer exists so was not saved.",
+                            self.cache_type.friendly_name(),
+                            path
+                        );
+                        stats.not_saved += 1;
+                    }
+                    Some(Some(SaveOutcome::Saved(_))) => {
+                        info!(
+                            "{} cache group {} saved successfully.",
+                            self.cache_type.friendly_name(),
+                            path
+                        );
+                        stats.saved += 1;
+                    }
+                    Some(None) => {
+                        info!(
+                            concat!(
+                                "It looks like the changed {} cache group {} already exists. ",
+                                "Not saving our version this time around because we can't be certain it's a useful update. "
+                            ),
+                            self.cache_type.friendly_name(),
+                            path
+                        );
+                        stats.not_saved += 1;
+                    }
+                    None => stats.not_saved += 1,
                 }
+            } else {
+                stats.unchanged += 1;
             }
         }
-        Ok(())
+        Ok(stats)
     }
 
-    async fn build_entry(cache_type: CacheType, entry_path: &Path) -> Result<Fingerprint, Error> {
-        let ignores = cache_type.ignores();
+    /// Fingerprints an entry of a group. If `by_revision` is set, Git indices
+    /// are fingerprinted by their fetched commit rather than their files.
+    async fn build_entry(cache_type: CacheType, entry_path: &Path, by_revision: bool) -> Result<Fingerprint, Error> {
+        if by_revision && cache_type == CacheType::Indices && IndexLayout::detect(entry_path).await == IndexLayout::Git
+        {
+            // Fingerprinting a Git index by its fetched commit is far cheaper than
+            // visiting every file within it
+            if let Some((revision_file, revision)) = git_refs::resolve_head(entry_path).await {
+                return fingerprint_revision(&revision_file, &revision).await;
+            }
+            debug!(
+                "Unable to determine revision of Git index at {}. Fingerprinting all files instead.",
+                entry_path
+            );
+        }
+        let ignores = cache_type.ignores(entry_path).await;
         fingerprint_path_with_ignores(entry_path, &ignores).await
     }
 
@@ -333,6 +513,7 @@ impl Cache {
         cache_type: CacheType,
         group_path: &Path,
         entry_level: usize,
+        by_revision: bool,
     ) -> Result<BTreeMap<AgnosticPath, Fingerprint>, Error> {
         let entry_level_glob = depth_to_match(entry_level)?;
         let entry_level_paths = match_relative_paths(group_path, &entry_level_glob, true).await?;
@@ -341,7 +522,7 @@ impl Cache {
             let entry_path = group_path.join(&path);
             map.insert(
                 AgnosticPath::from(&path),
-                Self::build_entry(cache_type, &entry_path).await?,
+                Self::build_entry(cache_type, &entry_path, by_revision).await?,
             );
         }
         Ok(map)
@@ -394,8 +575,11 @@ impl Cache {
         cache_type: CacheType,
         group_id: &GroupIdentifier,
         cross_platform_sharing: CrossPlatformSharing,
+        transfer_options: TransferOptions,
+        integrity: Option<String>,
+        rustc_version: Option<&str>,
     ) -> CacheEntry {
-        use crate::cache_key_builder::{Attribute, CacheKeyBuilder};
+        use crate::cache_key_builder::CacheKeyBuilder;
 
         let name = format!("{} (content)", cache_type.friendly_name());
         let mut builder = CacheKeyBuilder::new(&name);
@@ -408,6 +592,9 @@ impl Cache {
             safe_encoding::encode(lsb)
         };
         builder.set_attribute(Attribute::EntriesHash, entries_hash);
+        if let Some(integrity) = integrity {
+            builder.set_attribute(Attribute::Integrity, integrity);
+        }
 
         let sharing_platform = cross_platform_sharing.current_platform();
         let origin_platform = node::os::platform();
@@ -415,8 +602,19 @@ impl Cache {
             builder.set_attribute(Attribute::OriginPlatform, origin_platform);
         }
         builder.set_key_attribute(Attribute::Platform, sharing_platform.to_string());
+        if cache_type == CacheType::Bin {
+            // Installed binaries may link against system libraries that differ
+            // between runner images
+            if let Some(image) = runner_image(&node::process::get_env()) {
+                builder.set_key_attribute(Attribute::RunnerImage, image);
+            }
+        }
+        if let Some(rustc_version) = rustc_version {
+            builder.add_qualifier(Attribute::RustcVersion, rustc_version.to_string());
+        }
 
         let mut entry = builder.into_entry();
+        transfer_options.apply(&mut entry);
         entry.root(find_cargo_home());
         let root_path = find_path(cache_type);
         let path = root_path.join(&group_id.path);
@@ -479,8 +677,28 @@ impl Cache {
     }
 }
 
+async fn ensure_executable(folder_path: &Path) -> Result<(), Error> {
+    // The cache archive should preserve permissions, but a restored binary that
+    // cannot be executed is useless
+    if node::os::platform() == "win32" {
+        return Ok(());
+    }
+    for entry in node::fs::read_dir(folder_path).await? {
+        if entry.file_type().is_file() {
+            let path = entry.path();
+            let mode = node::fs::symlink_metadata(&path).await?.mode();
+            if mode & 0o111 == 0 {
+                #[allow(clippy::cast_possible_truncation)]
+                let mode = ((mode | 0o111) & 0o7777) as u16;
+                node::fs::chmod(&path, mode).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn find_cargo_home() -> Path {
-    Path::from(CARGO_HOME.as_str())
+    cargo_home_from_env(&node::process::get_env(), &homedir())
 }
 
 fn find_path(cache_type: CacheType) -> Path {
@@ -503,17 +721,48 @@ async fn find_additional_delete_paths(cache_type: CacheType) -> Result<Vec<Path>
     cache_type.add_additional_delete_paths(&mut path_match_builder)?;
     let path_matcher = path_match_builder.build()?;
     let home_path = find_cargo_home();
-    let result = if home_path.exists().await {
+    let candidates = if home_path.exists().await {
         match_relative_paths(&home_path, &path_matcher, false).await?
     } else {
         Vec::new()
     };
+    let mut result = Vec::with_capacity(candidates.len());
+    for path in candidates {
+        // The `.cache` folder of a sparse index is the index content itself rather than
+        // derived information, so it must be kept
+        if cache_type == CacheType::Indices && IndexLayout::detect(&path.parent()).await == IndexLayout::Sparse {
+            continue;
+        }
+        result.push(path);
+    }
     Ok(result)
 }
 
+/// The folder holding the cached folder information recorded by this job
+fn cached_folder_info_dir() -> Result<Path, Error> {
+    let namespace = github::Context::from_env()?.job_namespace();
+    Ok(node::path::join_all(
+        &get_action_cache_dir()?,
+        ["cached-folder-info", namespace.as_str()],
+    ))
+}
+
 fn cached_folder_info_path(cache_type: CacheType) -> Result<Path, Error> {
     let file_name = format!("{}.postcard", cache_type.short_name());
-    Ok(get_action_cache_dir()?.join("cached-folder-info").join(&file_name))
+    Ok(cached_folder_info_dir()?.join(file_name.as_str()))
+}
+
+/// Removes the cached folder information recorded by this job. This is called
+/// from the post step whether or not anything is saved, since otherwise
+/// self-hosted runners accumulate a folder for every job.
+pub async fn remove_cached_folder_info() {
+    let result = match cached_folder_info_dir() {
+        Ok(dir) => actions::io::rm_rf(&dir).await.map_err(Error::Js),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warning!("Failed to remove cached folder information: {}", e);
+    }
 }
 
 fn dependency_files_dir() -> Result<Path, Error> {
@@ -521,7 +770,20 @@ fn dependency_files_dir() -> Result<Path, Error> {
 }
 
 #[derive(
-    Debug, Clone, Copy, EnumIter, EnumString, Eq, Hash, PartialEq, IntoStaticStr, Display, Serialize, Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    EnumString,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    IntoStaticStr,
+    Display,
+    Serialize,
+    Deserialize,
 )]
 enum CacheType {
     #[strum(serialize = "indices")]
@@ -532,6 +794,9 @@ enum CacheType {
 
     #[strum(serialize = "git-repos")]
     GitRepos,
+
+    #[strum(serialize = "bin")]
+    Bin,
 }
 
 impl CacheType {
@@ -545,6 +810,7 @@ impl CacheType {
             CacheType::Indices => "registry indices",
             CacheType::Crates => "crate files",
             CacheType::GitRepos => "Git repositories",
+            CacheType::Bin => "installed binaries",
         }
         .into()
     }
@@ -554,6 +820,7 @@ impl CacheType {
             CacheType::Indices => Path::from("registry").join("index"),
             CacheType::Crates => Path::from("registry").join("cache"),
             CacheType::GitRepos => Path::from("git").join("db"),
+            CacheType::Bin => Path::from("bin"),
         }
     }
 
@@ -565,6 +832,7 @@ impl CacheType {
         // repeatedly being evicted then restored.
         match self {
             CacheType::Indices => {
+                // Sparse indices are filtered out in `find_additional_delete_paths`
                 match_builder.add_pattern("registry/index/*/.cache")?;
             }
             CacheType::Crates => {
@@ -573,20 +841,44 @@ impl CacheType {
             CacheType::GitRepos => {
                 match_builder.add_pattern("git/checkouts")?;
             }
+            CacheType::Bin => {}
         }
         Ok(())
     }
 
-    fn ignores(self) -> Ignores {
-        // Depths are relative to the entry
-        let mut ignores = Ignores::default();
+    async fn ignores(self, entry_path: &Path) -> Ignores {
         match self {
-            CacheType::Indices => {
-                ignores.add(1, ".last-updated");
+            CacheType::Indices => IndexLayout::detect(entry_path).await.ignores(),
+            CacheType::Crates | CacheType::GitRepos | CacheType::Bin => Ignores::default(),
+        }
+    }
+
+    fn excludes_group(self, group: &Path) -> bool {
+        match self {
+            CacheType::Bin => {
+                // Rustup proxies are managed by Rustup rather than `cargo install`
+                let name = group.file_name();
+                let name = name.strip_suffix(".exe").unwrap_or(name.as_str());
+                RUSTUP_PROXIES.contains(&name)
             }
-            CacheType::Crates | CacheType::GitRepos => {}
+            CacheType::Indices | CacheType::Crates | CacheType::GitRepos => false,
+        }
+    }
+
+    /// Whether everything in the folder belongs to the cache. If not, existing
+    /// content is not deleted before restoring and unused content is not
+    /// pruned.
+    fn manages_whole_folder(self) -> bool {
+        match self {
+            CacheType::Indices | CacheType::Crates | CacheType::GitRepos => true,
+            CacheType::Bin => false,
         }
-        ignores
+    }
+
+    fn cached_by_default(self) -> bool {
+        // Restored binaries are not registered with `cargo install`, so caching them
+        // must be requested explicitly
+        self != CacheType::Bin
     }
 
     #[allow(clippy::unused_self)]
@@ -596,7 +888,7 @@ impl CacheType {
 
     fn entry_depth(self) -> usize {
         match self {
-            CacheType::Indices | CacheType::GitRepos => 1,
+            CacheType::Indices | CacheType::GitRepos | CacheType::Bin => 1,
             CacheType::Crates => {
                 // This means we can prune individual crate files within an index
                 2
@@ -616,180 +908,2099 @@ impl CacheType {
             CacheType::Indices => input_manager::Input::MinRecacheIndices,
             CacheType::GitRepos => input_manager::Input::MinRecacheGitRepos,
             CacheType::Crates => input_manager::Input::MinRecacheCrates,
+            CacheType::Bin => input_manager::Input::MinRecacheBinaries,
         }
     }
 }
 
-fn get_cross_platform_sharing(input_manager: &input_manager::Manager) -> Result<CrossPlatformSharing, Error> {
-    Ok(if let Some(value) = input_manager.get(Input::CrossPlatformSharing) {
-        CrossPlatformSharing::from_str(value).map_err(|_| Error::ParseCrossPlatformSharing(value.to_string()))?
-    } else {
-        DEFAULT_CROSS_OS_SHARING
-    })
+/// The on-disk layout of a single registry index
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum IndexLayout {
+    /// A clone of a Git-based registry index
+    Git,
+
+    /// Index files fetched on demand using the sparse HTTP protocol
+    Sparse,
 }
 
-fn get_types_to_cache(input_manager: &input_manager::Manager) -> Result<Vec<CacheType>, Error> {
-    let mut result = HashSet::new();
-    if let Some(types) = input_manager.get(Input::CacheOnly) {
-        let types = types.split_whitespace();
-        for cache_type in types {
-            let cache_type =
-                CacheType::from_str(cache_type).map_err(|_| Error::ParseCacheableItem(cache_type.to_string()))?;
-            result.insert(cache_type);
+impl IndexLayout {
+    async fn detect(index_path: &Path) -> IndexLayout {
+        // Cargo stores Git-based indices as repositories. Sparse indices only contain
+        // `config.json` and the `.cache` folder of fetched index files.
+        if index_path.join(".git").exists().await {
+            IndexLayout::Git
+        } else {
+            IndexLayout::Sparse
         }
-    } else {
-        result.extend(CacheType::iter());
     }
-    Ok(result.into_iter().collect())
+
+    fn ignores(self) -> Ignores {
+        // Depths are relative to the index folder
+        let mut ignores = Ignores::default();
+        match self {
+            IndexLayout::Git => ignores.add(1, ".last-updated"),
+            IndexLayout::Sparse => {}
+        }
+        ignores
+    }
 }
 
-fn get_min_recache_interval(
-    input_manager: &input_manager::Manager,
-    cache_type: CacheType,
-) -> Result<chrono::Duration, Error> {
-    let result = if let Some(duration) = input_manager.get(cache_type.min_recache_input()) {
-        let duration = humantime::parse_duration(duration)?;
-        chrono::Duration::from_std(duration)?
+fn get_cross_platform_sharing(input_manager: &input_manager::Manager) -> Result<CrossPlatformSharing, Error> {
+    Ok(if let Some(value) = input_manager.get(Input::CrossPlatformSharing) {
+        CrossPlatformSharing::from_str(value).map_err(|_| Error::ParseCrossPlatformSharing(value.to_string()))?
     } else {
-        cache_type.default_min_recache_interval()
-    };
-    Ok(result)
+        DEFAULT_CROSS_OS_SHARING
+    })
 }
 
-fn dependency_file_path(cache_type: CacheType, scope: &HashValue, job: &Job) -> Result<Path, Error> {
-    let dependency_dir = dependency_files_dir()?;
-    let mut hasher = Blake3Hasher::default();
-    scope.hash(&mut hasher);
-    cache_type.hash(&mut hasher);
-    job.hash(&mut hasher);
-    let file_name = format!("{}.postcard", hasher.hash_value());
-    Ok(dependency_dir.join(&file_name))
+/// A folder specified by the user which is cached as a single entry in
+/// addition to the Cargo home items
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ExtraCachePath {
+    name: String,
+    path: String,
+    key_data: String,
+    ignores: Ignores,
+    key_attributes: Vec<(Attribute, String)>,
 }
 
-fn build_cache_entry_dependencies(cache_type: CacheType, scope: &HashValue, job: &Job) -> Result<CacheEntry, Error> {
-    use crate::cache_key_builder::{Attribute, CacheKeyBuilder};
-    let name = format!("{} (dependency list)", cache_type.friendly_name());
-    let mut key_builder = CacheKeyBuilder::new(&name);
-    key_builder.add_key_data(scope);
-    key_builder.set_key_attribute(Attribute::Workflow, job.get_workflow().to_string());
-    key_builder.set_key_attribute(Attribute::Job, job.get_job_id().to_string());
-    if let Some(properties) = job.matrix_properties_as_string() {
-        key_builder.set_key_attribute(Attribute::Matrix, properties);
+impl ExtraCachePath {
+    fn parse(line: &str) -> Result<ExtraCachePath, Error> {
+        let invalid = |reason| Error::InvalidExtraCachePath(line.to_string(), reason);
+        // Split on the first colon since Windows paths may contain one
+        let (name, path) = line.split_once(':').ok_or_else(|| invalid("expected `name:path`"))?;
+        let (name, path) = (name.trim(), path.trim());
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(invalid("names may only contain letters, digits, `-` and `_`"));
+        }
+        if [RUSTUP_CACHE_NAME, TARGET_CACHE_NAME].contains(&name)
+            || CacheType::iter().any(|cache_type| cache_type.short_name() == name)
+        {
+            return Err(invalid("name is already used by a built-in cache item"));
+        }
+        if path.is_empty() {
+            return Err(invalid("path is empty"));
+        }
+        Ok(ExtraCachePath {
+            name: name.to_string(),
+            path: path.to_string(),
+            key_data: String::new(),
+            ignores: Ignores::default(),
+            key_attributes: Vec::new(),
+        })
     }
-    let mut cache_entry = key_builder.into_entry();
-    cache_entry.root(node::os::homedir());
-    let path = dependency_file_path(cache_type, scope, job)?;
-    cache_entry.path(path);
-    Ok(cache_entry)
-}
 
-pub async fn restore_cargo_cache(input_manager: &input_manager::Manager) -> Result<(), Error> {
-    use crate::access_times::supports_atime;
-    use crate::cargo_lock_hashing::hash_cargo_lock_files;
+    /// The absolute path of the folder. A leading `~` refers to the home
+    /// folder and relative paths are relative to the working directory.
+    fn resolved_path(&self) -> Path {
+        let home_relative = self
+            .path
+            .strip_prefix('~')
+            .filter(|rest| rest.is_empty() || rest.starts_with(['/', '\\']));
+        match home_relative {
+            Some(rest) => homedir().join(rest.trim_start_matches(['/', '\\'])),
+            None => node::process::cwd().join(self.path.as_str()),
+        }
+    }
 
-    info!("Checking to see if filesystem supports access times...");
-    let atimes_supported = supports_atime().await?;
-    if atimes_supported {
-        info!(concat!(
-            "File access times supported. Hooray! ",
-            "These will be used to intelligently decide what can be dropped from within cached cargo home items."
-        ));
-    } else {
-        notice!(concat!("File access times not supported - cannot perform intelligent cache pruning. ",
-            "Likely this platform is Windows. ",
-            "The hash of all Cargo.lock files found in this folder will be used as part of the key for cached cargo home entries. ",
-            "This means certain caches will be rebuilt from scratch whenever a Cargo.lock file changes. ",
-            "This is to avoid cache entries growing monotonically. ",
-            "Note that enabling file access times on Windows is generally a bad idea since Microsoft never implemented relatime semantics.")
-        );
+    fn cache_entry(
+        &self,
+        transfer_options: TransferOptions,
+        content_hash: Option<u64>,
+        rustc_version: Option<&str>,
+    ) -> CacheEntry {
+        use crate::cache_key_builder::CacheKeyBuilder;
+
+        let path = self.resolved_path();
+        let name = format!("{} (extra path)", self.name);
+        let mut builder = CacheKeyBuilder::new(&name);
+        builder.add_key_data(&path.normalize_separators());
+        builder.add_key_data(&self.key_data);
+        builder.set_attribute(Attribute::Path, self.path.clone());
+        for (attribute, value) in &self.key_attributes {
+            builder.set_key_attribute(*attribute, value.clone());
+        }
+        if let Some(rustc_version) = rustc_version {
+            builder.add_qualifier(Attribute::RustcVersion, rustc_version.to_string());
+        }
+        if let Some(content_hash) = content_hash {
+            builder.set_attribute(
+                Attribute::EntriesHash,
+                safe_encoding::encode(content_hash.to_le_bytes()),
+            );
+        }
+        let mut entry = builder.into_entry();
+        transfer_options.apply(&mut entry);
+        entry.root(path.parent());
+        entry.path(path);
+        entry
     }
-    core::save_state(ATIMES_SUPPORTED_KEY, serde_json::to_string(&atimes_supported)?);
 
-    let scope_hash = if atimes_supported {
-        // We can't use the empty array because it will encode to an empty string, which
-        // doesn't play well with `save_state`.
-        HashValue::from_bytes(&[42u8])
-    } else {
-        let cwd = node::process::cwd();
-        let lock_hash = hash_cargo_lock_files(&cwd).await?;
-        HashValue::from_bytes(&lock_hash.bytes)
-    };
-    core::save_state(SCOPE_HASH_KEY, safe_encoding::encode(&scope_hash));
+    /// Hashes of the folder content, or `None` if it does not exist
+    async fn folder_info(&self) -> Result<Option<CachedFolderInfo>, Error> {
+        let path = self.resolved_path();
+        if !path.exists().await {
+            return Ok(None);
+        }
+        let fingerprint = fingerprint_path_with_ignores(&path, &self.ignores).await?;
+        Ok(Some(CachedFolderInfo::from(&fingerprint)))
+    }
 
-    let cross_platform_sharing = get_cross_platform_sharing(input_manager)?;
-    let cached_types = get_types_to_cache(input_manager)?;
-    for cache_type in cached_types {
-        core::start_group(cache_type.friendly_name().to_string());
-        // Mark as used to avoid spurious warnings (we only use this when we save the
-        // entries)
-        let _ = get_min_recache_interval(input_manager, cache_type)?;
+    /// Moves ignored content out of the folder so that it is not included
+    /// when the folder is saved
+    async fn stash_ignored(&self) -> Result<Stash, Error> {
+        Stash::create(&self.resolved_path(), &self.ignores).await
+    }
 
-        // Build the cache
-        let cache = Cache::restore_from_env(cache_type, &scope_hash, cross_platform_sharing).await?;
-        let serialized_cache = postcard::to_stdvec(&cache)?;
-        let cached_info_path = cached_folder_info_path(cache_type)?;
-        {
-            let parent = cached_info_path.parent();
-            node::fs::create_dir_all(&parent).await?;
+    /// Whether the folder should be saved given its content hash after it was
+    /// restored and its content hash now
+    fn needs_save(restored: Option<u64>, current: Option<u64>) -> bool {
+        match (restored, current) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(restored), Some(current)) => restored != current,
         }
-        node::fs::write_file(&cached_info_path, &serialized_cache).await?;
-        core::end_group();
     }
-    Ok(())
 }
 
-pub async fn save_cargo_cache(input_manager: &input_manager::Manager) -> Result<(), Error> {
-    let scope_hash = core::get_state(SCOPE_HASH_KEY).expect("Failed to find scope ID hash");
-    let scope_hash = safe_encoding::decode(&scope_hash).expect("Failed to decode scope ID hash");
-    let scope_hash = HashValue::from_bytes(&scope_hash);
+/// Content moved out of a folder while the folder is saved, so that it is left
+/// out of the cache entry without being deleted
+#[derive(Debug)]
+struct Stash {
+    folder: Path,
+    moved: Vec<(Path, Path)>,
+}
 
-    let atimes_supported = core::get_state(ATIMES_SUPPORTED_KEY).expect("Failed to find access times support flag");
-    let atimes_supported: bool = serde_json::de::from_str(&atimes_supported)?;
+impl Stash {
+    /// Moves the ignored content of `path` into a sibling folder, which is on
+    /// the same filesystem but outside the cache entry
+    async fn create(path: &Path, ignores: &Ignores) -> Result<Stash, Error> {
+        use crate::dir_tree::find_ignored_paths;
 
-    let cross_platform_sharing = get_cross_platform_sharing(input_manager)?;
-    let cached_types = get_types_to_cache(input_manager)?;
-    for cache_type in cached_types {
-        core::start_group(cache_type.friendly_name().to_string());
-        // Delete items that should never make it into the cache
-        for delete_path in find_additional_delete_paths(cache_type).await? {
-            if delete_path.exists().await {
-                info!("Pruning redundant cache element: {}", delete_path);
-                actions::io::rm_rf(&delete_path).await?;
+        let folder_name = format!(".ferrous-actions-stash-{}", crate::nonce::build(8));
+        let mut stash = Stash {
+            folder: path.parent().join(folder_name.as_str()),
+            moved: Vec::new(),
+        };
+        if !path.exists().await {
+            return Ok(stash);
+        }
+        let ignored = find_ignored_paths(path, ignores).await?;
+        if ignored.is_empty() {
+            return Ok(stash);
+        }
+        node::fs::create_dir_all(&stash.folder).await?;
+        for (index, original) in ignored.into_iter().enumerate() {
+            info!("Excluding from cache: {}", original);
+            let staged = stash.folder.join(index.to_string().as_str());
+            if let Err(e) = node::fs::rename(&original, &staged).await {
+                stash.restore().await?;
+                return Err(e.into());
             }
+            stash.moved.push((original, staged));
         }
+        Ok(stash)
+    }
 
-        // Restore the old cache
-        let cache_old: Cache = {
-            let cached_info_path = cached_folder_info_path(cache_type)?;
-            let cache_serialized = node::fs::read_file(&cached_info_path).await?;
-            postcard::from_bytes(&cache_serialized)?
-        };
+    /// Moves the content back into the folder
+    async fn restore(self) -> Result<(), Error> {
+        for (original, staged) in self.moved {
+            node::fs::rename(&staged, &original).await?;
+        }
+        if self.folder.exists().await {
+            node::fs::remove_dir(&self.folder).await?;
+        }
+        Ok(())
+    }
+}
 
-        // Construct the new cache
-        let mut cache = Cache::new(cache_type).await?;
+/// Hashes of a cached folder, stored between restoring and saving it
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct CachedFolderInfo {
+    version: u32,
+    content_hash: u64,
+    entries: BTreeMap<String, u64>,
+}
 
-        // Check the path to the cached items hasn't changed
-        if cache.get_root_path() != cache_old.get_root_path() {
-            use wasm_bindgen::JsError;
-            let error = JsError::new(&format!(
-                "Path to cache changed from {} to {}. Perhaps CARGO_HOME changed?",
-                cache_old.get_root_path(),
-                cache.get_root_path()
-            ));
-            return Err(Error::Js(error.into()));
+impl From<&Fingerprint> for CachedFolderInfo {
+    fn from(fingerprint: &Fingerprint) -> CachedFolderInfo {
+        CachedFolderInfo {
+            version: CACHED_FOLDER_INFO_VERSION,
+            content_hash: fingerprint.content_hash(),
+            entries: fingerprint.top_level_hashes(),
         }
+    }
+}
 
-        // Prune unused items (if we have access time suppport)
-        if atimes_supported {
-            cache.prune_unused(&cache_old).await?;
-        }
+impl CachedFolderInfo {
+    /// Whether this was stored in the format understood by this build
+    fn is_current_version(&self) -> bool {
+        self.version == CACHED_FOLDER_INFO_VERSION
+    }
 
-        // Save groups to cache if they have changed
-        let min_recache_interval = get_min_recache_interval(input_manager, cache_type)?;
-        cache
-            .save_changes(&cache_old, &scope_hash, &min_recache_interval, cross_platform_sharing)
-            .await?;
-        core::end_group();
+    /// The top-level entries that were added, removed or modified between
+    /// `other` and `self`
+    fn changes_from(&self, other: &CachedFolderInfo) -> Vec<(&str, DeltaAction)> {
+        use itertools::{EitherOrBoth, Itertools as _};
+
+        other
+            .entries
+            .iter()
+            .merge_join_by(self.entries.iter(), |left, right| left.0.cmp(right.0))
+            .filter_map(|element| match element {
+                EitherOrBoth::Left(left) => Some((left.0.as_str(), DeltaAction::Removed)),
+                EitherOrBoth::Right(right) => Some((right.0.as_str(), DeltaAction::Added)),
+                EitherOrBoth::Both(left, right) => {
+                    (left.1 != right.1).then_some((right.0.as_str(), DeltaAction::Changed))
+                }
+            })
+            .collect()
+    }
+}
+
+fn find_rustup_home() -> Path {
+    rustup_home_from_env(&node::process::get_env(), &homedir())
+}
+
+/// Items in the Rustup home which are not cached. Only `toolchains` and
+/// `update-hashes` are needed for installed toolchains to be reused, and the
+/// settings belong to the runner.
+const RUSTUP_HOME_IGNORES: [&str; 3] = ["downloads", "tmp", "settings.toml"];
+
+/// The parts of Rustup's `settings.toml` which determine the toolchains that
+/// are installed
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RustupSettings {
+    default_toolchain: Option<String>,
+    profile: Option<String>,
+    overrides: BTreeMap<String, String>,
+}
+
+/// Describes the toolchains expected to be installed and the profile they are
+/// installed with
+fn rustup_key_data(settings: &RustupSettings, toolchain_file: Option<&ToolchainFile>) -> String {
+    let mut toolchains: BTreeSet<&str> = settings.overrides.values().map(String::as_str).collect();
+    toolchains.extend(settings.default_toolchain.as_deref());
+    let mut profile = settings.profile.as_deref();
+    let mut components = BTreeSet::new();
+    let mut targets = BTreeSet::new();
+    if let Some(toolchain_file) = toolchain_file {
+        toolchains.insert(toolchain_file.channel.as_str());
+        profile = toolchain_file.profile.as_deref().or(profile);
+        components.extend(toolchain_file.components.iter().map(String::as_str));
+        targets.extend(toolchain_file.targets.iter().map(String::as_str));
+    }
+    let join = |items: BTreeSet<&str>| items.into_iter().collect::<Vec<_>>().join(",");
+    format!(
+        "toolchains={}\nprofile={}\ncomponents={}\ntargets={}\n",
+        join(toolchains),
+        profile.unwrap_or("default"),
+        join(components),
+        join(targets)
+    )
+}
+
+/// Builds the data identifying which Rustup toolchains are expected to be
+/// installed. This must be computed before any toolchain is installed since
+/// installation can change the default toolchain in `settings.toml`.
+async fn build_rustup_key_data(rustup_home: &Path) -> Result<String, Error> {
+    use crate::toolchain_file;
+
+    let settings_path = rustup_home.join("settings.toml");
+    let settings = if settings_path.exists().await {
+        let content = node::fs::read_file(&settings_path).await?;
+        basic_toml::from_str(&String::from_utf8_lossy(&content)).unwrap_or_else(|e| {
+            warning!("Unable to parse {}, so it will be ignored: {}", settings_path, e);
+            RustupSettings::default()
+        })
+    } else {
+        RustupSettings::default()
+    };
+    let toolchain_file = toolchain_file::find_and_parse(&node::process::cwd()).await?;
+    Ok(rustup_key_data(
+        &settings,
+        toolchain_file.as_ref().map(|(_, file)| file),
+    ))
+}
+
+fn rustup_cache_path(rustup_home: &Path, key_data: String) -> ExtraCachePath {
+    let mut ignores = Ignores::default();
+    for name in RUSTUP_HOME_IGNORES {
+        ignores.add(1, name);
+    }
+    ExtraCachePath {
+        name: RUSTUP_CACHE_NAME.to_string(),
+        path: rustup_home.to_string(),
+        key_data,
+        ignores,
+        key_attributes: Vec::new(),
+    }
+}
+
+/// The target directory Cargo will use, determined without running Cargo since
+/// that may install a toolchain before the cache is restored. This is
+/// `CARGO_TARGET_DIR` (relative to the working directory), then the
+/// `build.target-dir` setting and then `target` in the workspace root.
+fn resolve_target_dir(
+    env: &HashMap<String, String>,
+    configured: Option<&Path>,
+    workspace_root: Option<&Path>,
+    cwd: &Path,
+) -> Path {
+    if let Some(dir) = env.get("CARGO_TARGET_DIR").filter(|dir| !dir.is_empty()) {
+        cwd.resolve_from(dir.as_str())
+    } else if let Some(configured) = configured {
+        configured.clone()
+    } else {
+        workspace_root.unwrap_or(cwd).join("target")
+    }
+}
+
+/// The part of a Cargo manifest that marks it as a workspace root
+#[derive(Debug, Deserialize)]
+struct WorkspaceManifest {
+    workspace: Option<serde::de::IgnoredAny>,
+}
+
+fn is_workspace_manifest(content: &str) -> bool {
+    basic_toml::from_str::<WorkspaceManifest>(content).map_or(false, |manifest| manifest.workspace.is_some())
+}
+
+/// Finds the workspace root for the working directory. This is the nearest
+/// folder at or above the closest manifest whose manifest has a `[workspace]`
+/// table, or the folder of the closest manifest if there is none.
+async fn find_workspace_root(cwd: &Path) -> Option<Path> {
+    let package_root = find_up(cwd, &["Cargo.toml"]).await?.parent();
+    let mut current = package_root.clone();
+    loop {
+        if let Ok(content) = node::fs::read_file(&current.join("Cargo.toml")).await {
+            if is_workspace_manifest(&String::from_utf8_lossy(&content)) {
+                return Some(current);
+            }
+        }
+        let parent = current.parent();
+        if parent == current {
+            return Some(package_root);
+        }
+        current = parent;
+    }
+}
+
+/// Reads the Cargo configuration that applies to the working directory. Since
+/// the configuration only refines what is cached, failures are reported and
+/// the default configuration is used.
+async fn load_cargo_config() -> CargoConfig {
+    match cargo_config::load(&node::process::cwd(), &find_cargo_home()).await {
+        Ok(config) => config,
+        Err(e) => {
+            warning!("Unable to read Cargo configuration, so it will be ignored: {}", e);
+            CargoConfig::default()
+        }
+    }
+}
+
+/// Content of the target directory which can be excluded from the cache
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, EnumString, IntoStaticStr)]
+enum TargetExclusion {
+    /// Incremental compilation state, which is large and of little use to a
+    /// fresh build
+    #[strum(serialize = "incremental")]
+    Incremental,
+
+    /// Cargo's freshness information. Excluding this causes all crates to be
+    /// rebuilt, so it only makes sense if the cache is too large otherwise.
+    #[strum(serialize = "fingerprints")]
+    Fingerprints,
+
+    /// Final binaries and shared libraries built from the workspace
+    #[strum(serialize = "artifacts")]
+    Artifacts,
+}
+
+const DEFAULT_TARGET_EXCLUSIONS: [TargetExclusion; 2] = [TargetExclusion::Incremental, TargetExclusion::Artifacts];
+
+/// The target directory and the runner image that built it
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+struct TargetDirInfo {
+    path: String,
+    runner_image: Option<String>,
+}
+
+impl TargetDirInfo {
+    /// Binaries built on one runner image may fail to link or run on another
+    fn key_attributes(&self) -> Vec<(Attribute, String)> {
+        let mut result = Vec::new();
+        if let Some(runner_image) = &self.runner_image {
+            result.push((Attribute::RunnerImage, runner_image.clone()));
+        }
+        result
+    }
+}
+
+/// Identifies the operating system image of a GitHub-hosted runner (e.g.
+/// `ubuntu22`). The platform alone does not distinguish between releases.
+fn runner_image(env: &HashMap<String, String>) -> Option<String> {
+    env.get("ImageOS").filter(|image| !image.is_empty()).cloned()
+}
+
+/// File names of the binaries and shared libraries that the workspace packages
+/// build. Names for all platforms are included since they do not overlap with
+/// Cargo's own files.
+fn artifact_names(metadata: &cargo_metadata::Metadata) -> Vec<String> {
+    let mut result = BTreeSet::new();
+    for target in metadata.packages.iter().flat_map(|package| package.targets.iter()) {
+        if target.kind.iter().any(|kind| kind == "bin") {
+            let name = &target.name;
+            result.extend([
+                name.clone(),
+                format!("{}.exe", name),
+                format!("{}.pdb", name),
+                format!("{}.wasm", name),
+            ]);
+        }
+        if target.kind.iter().any(|kind| kind == "cdylib") {
+            let name = target.name.replace('-', "_");
+            result.extend([
+                format!("lib{}.so", name),
+                format!("lib{}.dylib", name),
+                format!("{}.dll", name),
+                format!("{}.wasm", name),
+            ]);
+        }
+    }
+    result.into_iter().collect()
+}
+
+/// Builds the ignores for the target directory. Output for the host is written
+/// to `target/<profile>` and output when cross-compiling to
+/// `target/<triple>/<profile>`, so both depths are covered.
+fn target_ignores(exclusions: &[TargetExclusion], artifacts: &[String]) -> Ignores {
+    let mut ignores = Ignores::default();
+    // Documentation is written to `target/doc` and `target/<triple>/doc`, in
+    // folders which may share names with artifacts
+    ignores.protect(1, "doc");
+    ignores.protect(2, "doc");
+    for depth in [2, 3] {
+        for exclusion in exclusions {
+            match exclusion {
+                TargetExclusion::Incremental => ignores.add(depth, "incremental"),
+                TargetExclusion::Fingerprints => ignores.add(depth, ".fingerprint"),
+                TargetExclusion::Artifacts => {
+                    for artifact in artifacts {
+                        ignores.add(depth, artifact);
+                    }
+                }
+            }
+        }
+    }
+    ignores
+}
+
+async fn find_target_dir_info(cargo_config: &CargoConfig) -> TargetDirInfo {
+    let cwd = node::process::cwd();
+    let env = node::process::get_env();
+    let workspace_root = find_workspace_root(&cwd).await;
+    let path = resolve_target_dir(&env, cargo_config.target_dir.as_ref(), workspace_root.as_ref(), &cwd);
+    TargetDirInfo {
+        path: path.to_string(),
+        runner_image: runner_image(&env),
+    }
+}
+
+/// The names of the final artifacts built by the workspace. This runs `cargo
+/// metadata`, so is only done when saving, after the build has installed any
+/// toolchain it needs.
+async fn find_artifact_names() -> Vec<String> {
+    use crate::cargo::Cargo;
+
+    let metadata = match Cargo::from_environment().await {
+        Ok(mut cargo) => cargo.metadata().await,
+        Err(e) => Err(e),
+    };
+    match metadata {
+        Ok(metadata) => artifact_names(&metadata),
+        Err(e) => {
+            info!(
+                "Unable to read Cargo metadata, so build artifacts will not be excluded from the target cache: {}",
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// The version of the Rust compiler used in the working directory. This is
+/// only read when saving, after the compiler that built any output has been
+/// installed.
+async fn find_rustc_version() -> Option<String> {
+    use crate::cargo::Cargo;
+
+    let version = match Cargo::from_environment().await {
+        Ok(cargo) => cargo.get_toolchain_version(None, None).await,
+        Err(e) => Err(e),
+    };
+    match version {
+        Ok(version) => Some(version.short().into_owned()),
+        Err(e) => {
+            warning!(
+                "Unable to determine the Rust compiler version, so it will not be part of cache keys: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+fn target_cache_path(info: &TargetDirInfo, exclusions: &[TargetExclusion], artifacts: &[String]) -> ExtraCachePath {
+    ExtraCachePath {
+        name: TARGET_CACHE_NAME.to_string(),
+        path: info.path.clone(),
+        key_data: String::new(),
+        ignores: target_ignores(exclusions, artifacts),
+        key_attributes: info.key_attributes(),
+    }
+}
+
+fn get_target_exclusions(input_manager: &input_manager::Manager) -> Result<Vec<TargetExclusion>, Error> {
+    let mut result = Vec::new();
+    if let Some(values) = input_manager.get_list(Input::TargetCacheExclude) {
+        for value in values.into_iter().filter(|v| *v != "none") {
+            let exclusion = TargetExclusion::from_str(value)
+                .map_err(|_| Error::OptionParseError(Input::TargetCacheExclude.to_string(), value.to_string()))?;
+            result.push(exclusion);
+        }
+    } else {
+        result.extend(DEFAULT_TARGET_EXCLUSIONS);
+    }
+    Ok(result)
+}
+
+/// Folders within the target directory which are of no use to later builds:
+/// generated documentation and compiled examples, for the host and for each
+/// explicitly specified target. The host documentation is normally already
+/// removed by `cargo clean --doc`, but is included in case Cargo could not be
+/// run.
+const TARGET_PRUNE_PATTERNS: [&str; 4] = ["doc", "*/doc", "*/examples", "*/*/examples"];
+
+/// Removes the generated documentation from the target directory with `cargo
+/// clean --doc`. Failures are reported as warnings since the documentation is
+/// also pruned by `prune_target_for_cache`.
+async fn clean_target_docs(target_dir: &Path) {
+    use crate::cargo::Cargo;
+
+    let target_dir = target_dir.to_string();
+    let args = ["--doc", "--target-dir", target_dir.as_str()];
+    let result = match Cargo::from_environment().await {
+        Ok(mut cargo) => cargo.clean(&args).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warning!("Failed to clean documentation from the target directory: {}", e);
+    }
+}
+
+/// Deletes folders from the target directory which are not worth caching,
+/// returning the paths deleted
+async fn prune_target_for_cache(target_dir: &Path) -> Result<Vec<Path>, Error> {
+    if !target_dir.exists().await {
+        return Ok(Vec::new());
+    }
+    let mut builder = PathMatchBuilder::new(&node::path::separator());
+    for pattern in TARGET_PRUNE_PATTERNS {
+        builder.add_pattern(pattern)?;
+    }
+    let matcher = builder.build()?;
+    let mut pruned = match_relative_paths(target_dir, &matcher, false).await?;
+    pruned.sort_by_key(ToString::to_string);
+    for path in &pruned {
+        info!("Pruning {} from the target directory before caching", path);
+        actions::io::rm_rf(path).await?;
+    }
+    Ok(pruned)
+}
+
+const PREFETCH_ARGS: [&str; 1] = ["--locked"];
+
+/// Includes the configured registries in the scope of the cache. The scope is
+/// unchanged if no registries are configured, so existing caches remain valid.
+fn scope_with_registries(scope: HashValue, registry_key_data: &str) -> HashValue {
+    if registry_key_data.is_empty() {
+        scope
+    } else {
+        let mut hasher = Blake3Hasher::default();
+        scope.hash(&mut hasher);
+        registry_key_data.hash(&mut hasher);
+        hasher.hash_value()
+    }
+}
+
+/// Whether dependencies should be fetched after restoring. This is only
+/// worthwhile if the registry index is cached but was not restored, since
+/// otherwise either the first build will not need to update it or the fetched
+/// index would not be saved.
+fn should_prefetch(prefetch: bool, hits: &BTreeMap<CacheType, HitKind>) -> bool {
+    prefetch && hits.get(&CacheType::Indices) == Some(&HitKind::Miss)
+}
+
+/// Runs `cargo fetch` so that later build steps find the index and crates
+/// already present. Failures are reported as warnings since the build itself
+/// will fetch anything missing.
+async fn prefetch_dependencies(input_manager: &input_manager::Manager) {
+    use crate::cargo::Cargo;
+
+    info!("Registry index was not restored, fetching dependencies...");
+    let result = match Cargo::from_environment().await {
+        Ok(mut cargo) => cargo.run(None, "fetch", PREFETCH_ARGS, input_manager).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warning!("Failed to prefetch dependencies: {}", e);
+    }
+}
+
+fn get_extra_cache_paths(input_manager: &input_manager::Manager) -> Result<Vec<ExtraCachePath>, Error> {
+    let lines = input_manager.get(Input::ExtraCachePaths).unwrap_or_default();
+    parse_extra_cache_paths(lines)
+}
+
+fn parse_extra_cache_paths(lines: &str) -> Result<Vec<ExtraCachePath>, Error> {
+    let mut result: Vec<ExtraCachePath> = Vec::new();
+    for line in lines.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let extra_path = ExtraCachePath::parse(line)?;
+        if result.iter().any(|existing| existing.name == extra_path.name) {
+            return Err(Error::InvalidExtraCachePath(
+                line.to_string(),
+                "name is used more than once",
+            ));
+        }
+        result.push(extra_path);
+    }
+    Ok(result)
+}
+
+/// For each extra cache path, finds a reserved or preceding extra path which
+/// overlaps it, in which case it should not be cached. An extra path overlaps a
+/// reserved path if either contains the other, and another extra path if that
+/// one contains it. Of two identical extra paths, the first is kept.
+fn find_overlapping_paths(reserved: &[(String, Path)], extra: &[(String, Path)]) -> Vec<Option<String>> {
+    let mut seen = HashSet::new();
+    extra
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, path))| {
+            let reserved_overlap = reserved
+                .iter()
+                .find(|(_, other)| path.starts_with(other) || other.starts_with(path));
+            let extra_overlap = extra.iter().enumerate().find(|(other_idx, (_, other))| {
+                *other_idx != idx && path.starts_with(other) && !other.starts_with(path)
+            });
+            let overlap = reserved_overlap.or(extra_overlap.map(|(_, entry)| entry));
+            if let Some((name, _)) = overlap {
+                Some(name.clone())
+            } else if seen.insert(path.normalize_separators()) {
+                None
+            } else {
+                extra
+                    .iter()
+                    .find(|(_, other)| other == path)
+                    .map(|(name, _)| name.clone())
+            }
+        })
+        .collect()
+}
+
+/// Removes extra cache paths which overlap the cached parts of the Cargo home
+/// or another extra path, since their content would otherwise be archived
+/// twice
+async fn remove_overlapping_paths(
+    cached_types: &[CacheType],
+    extra_paths: Vec<ExtraCachePath>,
+) -> Result<Vec<ExtraCachePath>, Error> {
+    async fn canonical(path: Path) -> Path {
+        node::fs::canonicalize(&path)
+            .await
+            .unwrap_or_else(|_| Path::resolve([path]))
+    }
+
+    let mut reserved = Vec::with_capacity(cached_types.len());
+    for cache_type in cached_types {
+        reserved.push((
+            cache_type.friendly_name().to_string(),
+            canonical(find_path(*cache_type)).await,
+        ));
+    }
+    let mut extra = Vec::with_capacity(extra_paths.len());
+    for extra_path in &extra_paths {
+        extra.push((extra_path.name.clone(), canonical(extra_path.resolved_path()).await));
+    }
+    let overlaps = find_overlapping_paths(&reserved, &extra);
+    let mut result = Vec::with_capacity(extra_paths.len());
+    for (extra_path, overlap) in extra_paths.into_iter().zip(overlaps) {
+        if let Some(other) = overlap {
+            warning!(
+                "Not caching extra path {} since it overlaps with {}, which is already cached",
+                extra_path.name,
+                other
+            );
+        } else {
+            result.push(extra_path);
+        }
+    }
+    Ok(result)
+}
+
+async fn restore_extra_cache_paths(
+    extra_paths: &[ExtraCachePath],
+    transfer_options: TransferOptions,
+    restore: bool,
+) -> Result<(), Error> {
+    let mut hashes: BTreeMap<String, Option<CachedFolderInfo>> = BTreeMap::new();
+    for extra_path in extra_paths {
+        if !restore {
+            hashes.insert(extra_path.name.clone(), extra_path.folder_info().await?);
+            continue;
+        }
+        let entry = extra_path.cache_entry(transfer_options, None, None);
+        let operation = format!("restore extra cache path {}", extra_path.name);
+        match tolerate_service_failure(entry.restore().await, &operation)?.flatten() {
+            Some(key) => info!(
+                "Restored extra cache path {} from cache with key {}",
+                extra_path.name, key
+            ),
+            None => info!("No cache entry found for extra cache path {}", extra_path.name),
+        }
+        hashes.insert(extra_path.name.clone(), extra_path.folder_info().await?);
+    }
+    core::save_state(EXTRA_PATH_HASHES_KEY, serde_json::to_string(&hashes)?);
+    Ok(())
+}
+
+async fn save_extra_cache_paths(
+    extra_paths: &[ExtraCachePath],
+    transfer_options: TransferOptions,
+    rustc_version: Option<&str>,
+) -> Result<SaveStats, Error> {
+    let restored_hashes: BTreeMap<String, Option<CachedFolderInfo>> = {
+        let hashes =
+            core::get_state(EXTRA_PATH_HASHES_KEY).ok_or_else(|| Error::InvalidState(EXTRA_PATH_HASHES_KEY.into()))?;
+        serde_json::from_str(&hashes)?
+    };
+    let mut stats = SaveStats::default();
+    for extra_path in extra_paths {
+        // Info stored by a different build of the action is treated as absent
+        let restored = restored_hashes
+            .get(&extra_path.name)
+            .and_then(Option::as_ref)
+            .filter(|info| info.is_current_version());
+        let current = extra_path.folder_info().await?;
+        let current_hash = current.as_ref().map(|info| info.content_hash);
+        if !ExtraCachePath::needs_save(restored.map(|info| info.content_hash), current_hash) {
+            info!("Extra cache path {} is unchanged or missing", extra_path.name);
+            stats.unchanged += 1;
+            continue;
+        }
+        if let (Some(restored), Some(current)) = (restored, &current) {
+            let delta = current.changes_from(restored);
+            debug!(
+                "Extra cache path {} changed since it was restored:\n{}",
+                extra_path.name,
+                render_delta_list(&delta)
+            );
+        }
+        let stash = extra_path.stash_ignored().await?;
+        let entry = extra_path.cache_entry(transfer_options, current_hash, rustc_version);
+        let operation = format!("save extra cache path {}", extra_path.name);
+        let saved = entry.save().await;
+        stash.restore().await?;
+        match tolerate_service_failure(saved, &operation)? {
+            Some(SaveOutcome::Saved(id)) => {
+                info!("Extra cache path {} saved with ID {}", extra_path.name, id);
+                stats.saved += 1;
+            }
+            _ => stats.not_saved += 1,
+        }
+    }
+    Ok(stats)
+}
+
+/// Runs an operation for each cache type concurrently, since the types are
+/// stored in independent folders. Every operation runs to completion, even if
+/// another fails. The output of each operation is held back and written in the
+/// order of `cache_types`, followed by any failure. The first failure is
+/// returned.
+async fn run_per_type<T, F, Fut>(cache_types: &[CacheType], operation: &str, mut f: F) -> Result<Vec<T>, Error>
+where
+    F: FnMut(CacheType) -> Fut,
+    Fut: std::future::Future<Output = Result<T, Error>>,
+{
+    let results = futures::future::join_all(
+        cache_types
+            .iter()
+            .map(|cache_type| core::capture_output(f(*cache_type))),
+    )
+    .await;
+    let mut values = Vec::with_capacity(results.len());
+    let mut first_error = None;
+    for (cache_type, (result, output)) in cache_types.iter().zip(results) {
+        output.flush();
+        match result {
+            Ok(value) => values.push(value),
+            Err(e) => {
+                error!("Failed to {} {}: {}", operation, cache_type.friendly_name(), e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    first_error.map_or(Ok(values), Err)
+}
+
+/// How much of a cache type was restored
+#[derive(Clone, Copy, Debug, Deserialize, Display, Eq, PartialEq, Serialize)]
+enum HitKind {
+    /// Every group in the dependency list was restored
+    #[strum(serialize = "exact")]
+    Exact,
+
+    /// Some groups in the dependency list were restored
+    #[strum(serialize = "partial")]
+    Partial,
+
+    /// There was no dependency list or none of its groups were restored
+    #[strum(serialize = "miss")]
+    Miss,
+}
+
+impl HitKind {
+    fn from_counts(expected: Option<usize>, restored: usize) -> HitKind {
+        match expected {
+            None => HitKind::Miss,
+            Some(expected) if restored >= expected => HitKind::Exact,
+            Some(_) if restored == 0 => HitKind::Miss,
+            Some(_) => HitKind::Partial,
+        }
+    }
+}
+
+/// What was restored for a single cache type
+#[derive(Clone, Debug, PartialEq)]
+struct RestoreOutcome {
+    hit: HitKind,
+    path: Path,
+}
+
+/// What was restored for each cache type
+#[derive(Clone, Debug, Default, PartialEq)]
+struct RestoreReport {
+    per_type: HashMap<CacheType, RestoreOutcome>,
+}
+
+impl RestoreReport {
+    fn from_hits<I, F>(hits: I, path_for_type: F) -> RestoreReport
+    where
+        I: IntoIterator<Item = (CacheType, HitKind)>,
+        F: Fn(CacheType) -> Path,
+    {
+        let per_type = hits
+            .into_iter()
+            .map(|(cache_type, hit)| {
+                let outcome = RestoreOutcome {
+                    hit,
+                    path: path_for_type(cache_type),
+                };
+                (cache_type, outcome)
+            })
+            .collect();
+        RestoreReport { per_type }
+    }
+
+    /// The hit kind of each cache type, in a stable order
+    fn hits(&self) -> BTreeMap<CacheType, HitKind> {
+        self.per_type
+            .iter()
+            .map(|(cache_type, outcome)| (*cache_type, outcome.hit))
+            .collect()
+    }
+}
+
+/// Builds the step outputs describing what was restored. `cache-hit` follows
+/// the official cache action and is only `true` if every item was an exact
+/// hit.
+fn hit_outputs(hits: &BTreeMap<CacheType, HitKind>) -> Vec<(String, String)> {
+    let all_exact = !hits.is_empty() && hits.values().all(|hit| *hit == HitKind::Exact);
+    let mut result = vec![("cache-hit".to_string(), all_exact.to_string())];
+    for (cache_type, hit) in hits {
+        result.push((format!("cache-{}-hit", cache_type.short_name()), hit.to_string()));
+    }
+    result
+}
+
+/// A notice for cached items that were restored from older cache entries (e.g.
+/// ones saved by another branch or an earlier dependency list) rather than
+/// an exact match. This is not a problem, but explains why a build may do more
+/// work than expected.
+fn partial_hit_notice(hits: &BTreeMap<CacheType, HitKind>) -> Option<String> {
+    use itertools::Itertools as _;
+
+    let partial: Vec<_> = hits
+        .iter()
+        .filter(|(_, hit)| **hit == HitKind::Partial)
+        .map(|(cache_type, _)| cache_type.friendly_name())
+        .collect();
+    (!partial.is_empty()).then(|| {
+        format!(
+            "Some cached items were restored from non-matching cache entries: {}",
+            partial.iter().join(", ")
+        )
+    })
+}
+
+/// Fails if `fail_on_miss` is set and any cached item was not restored at all.
+/// A partial hit is not considered a miss.
+fn check_cache_misses(fail_on_miss: bool, hits: &BTreeMap<CacheType, HitKind>) -> Result<(), Error> {
+    use itertools::Itertools as _;
+
+    let missed: Vec<_> = hits
+        .iter()
+        .filter(|(_, hit)| **hit == HitKind::Miss)
+        .map(|(cache_type, _)| cache_type.friendly_name())
+        .collect();
+    if fail_on_miss && !missed.is_empty() {
+        Err(Error::CacheMiss(missed.iter().join(", ")))
+    } else {
+        Ok(())
+    }
+}
+
+/// Restores a single cache type and returns how much of it was restored
+async fn restore_cache_type(
+    cache_type: CacheType,
+    scope_hash: &HashValue,
+    cross_platform_sharing: CrossPlatformSharing,
+    transfer_options: TransferOptions,
+    verify: bool,
+) -> Result<HitKind, Error> {
+    let (cache, hit) =
+        Cache::restore_from_env(cache_type, scope_hash, cross_platform_sharing, transfer_options, verify).await?;
+    write_cached_folder_info(cache_type, &cache).await?;
+    Ok(hit)
+}
+
+/// Records the current contents of a cache type without restoring anything,
+/// so that the post step can save it. Always reported as a miss.
+async fn record_cache_type(cache_type: CacheType) -> Result<HitKind, Error> {
+    node::fs::create_dir_all(&find_path(cache_type)).await?;
+    let cache = Cache::new(cache_type).await?;
+    write_cached_folder_info(cache_type, &cache).await?;
+    Ok(HitKind::Miss)
+}
+
+async fn write_cached_folder_info(cache_type: CacheType, cache: &Cache) -> Result<(), Error> {
+    let serialized_cache = postcard::to_stdvec(cache)?;
+    let cached_info_path = cached_folder_info_path(cache_type)?;
+    {
+        let parent = cached_info_path.parent();
+        node::fs::create_dir_all(&parent).await?;
+    }
+    node::fs::write_file_atomic(&cached_info_path, &serialized_cache).await?;
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+struct SaveOptions<'a> {
+    scope_hash: &'a HashValue,
+    atimes_supported: bool,
+    min_recache_interval: chrono::Duration,
+    cross_platform_sharing: CrossPlatformSharing,
+    transfer_options: TransferOptions,
+    preserve_timestamps: bool,
+    rustc_version: Option<&'a str>,
+}
+
+/// Saves a single cache type and returns its row of the job summary
+async fn save_cache_type(cache_type: CacheType, options: SaveOptions<'_>) -> Result<Vec<String>, Error> {
+    // Delete items that should never make it into the cache
+    for delete_path in find_additional_delete_paths(cache_type).await? {
+        if delete_path.exists().await {
+            info!("Pruning redundant cache element: {}", delete_path);
+            actions::io::rm_rf(&delete_path).await?;
+        }
+    }
+
+    // Restore the old cache
+    let cache_old: Cache = {
+        let cached_info_path = cached_folder_info_path(cache_type)?;
+        let cache_serialized = node::fs::read_file(&cached_info_path).await?;
+        postcard::from_bytes(&cache_serialized)?
+    };
+
+    // Construct the new cache
+    let mut cache = Cache::new(cache_type).await?;
+
+    // Check the path to the cached items hasn't changed
+    if cache.get_root_path() != cache_old.get_root_path() {
+        use wasm_bindgen::JsError;
+        let error = JsError::new(&format!(
+            "Path to cache changed from {} to {}. Perhaps CARGO_HOME changed?",
+            cache_old.get_root_path(),
+            cache.get_root_path()
+        ));
+        return Err(Error::Js(error.into()));
+    }
+
+    // Prune unused items (if we have access time suppport)
+    if options.atimes_supported && cache_type.manages_whole_folder() {
+        cache.prune_unused(&cache_old).await?;
+    }
+
+    // Save groups to cache if they have changed
+    let stats = cache.save_changes(&cache_old, options).await?;
+    Ok(vec![
+        cache_type.friendly_name().to_string(),
+        (stats.saved + stats.unchanged + stats.not_saved).to_string(),
+        stats.saved.to_string(),
+        stats.unchanged.to_string(),
+        stats.not_saved.to_string(),
+    ])
+}
+
+/// Logs the addresses of the runner's network interfaces, since failures to
+/// reach the cache service on self-hosted runners can be network related
+fn log_network_interfaces() {
+    let interfaces: BTreeMap<_, _> = node::os::network_interfaces().into_iter().collect();
+    for (name, addresses) in interfaces {
+        for address in addresses {
+            debug!(
+                "Network interface {}: {} ({:?}, internal: {}, MAC: {})",
+                name, address.address, address.family, address.internal, address.mac
+            );
+        }
+    }
+}
+
+/// Caching is best-effort, so failures of the cache service are reported and
+/// otherwise ignored. Returns `None` if the operation did not complete.
+fn tolerate_service_failure<T>(result: Result<T, wasm_bindgen::JsValue>, operation: &str) -> Result<Option<T>, Error> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => match ServiceFailure::classify(&e) {
+            Some(ServiceFailure::Unavailable) => {
+                warning!(
+                    "Unable to {} because the cache service is unavailable: {:?}",
+                    operation,
+                    e
+                );
+                log_network_interfaces();
+                Ok(None)
+            }
+            Some(ServiceFailure::ReserveConflict) => {
+                info!("Unable to {} because another job is already saving it", operation);
+                Ok(None)
+            }
+            None => Err(Error::Js(e)),
+        },
+    }
+}
+
+fn get_transfer_options(input_manager: &input_manager::Manager) -> Result<TransferOptions, Error> {
+    Ok(TransferOptions {
+        upload_concurrency: parse_optional_input(input_manager, Input::CacheUploadConcurrency)?,
+        upload_chunk_size: parse_optional_input(input_manager, Input::CacheUploadChunkSize)?,
+        download_concurrency: parse_optional_input(input_manager, Input::CacheDownloadConcurrency)?,
+    })
+}
+
+fn get_types_to_cache(input_manager: &input_manager::Manager) -> Result<Vec<CacheType>, Error> {
+    let mut result = HashSet::new();
+    if let Some(types) = input_manager.get_list(Input::CacheOnly) {
+        for cache_type in types {
+            let cache_type =
+                CacheType::from_str(cache_type).map_err(|_| Error::ParseCacheableItem(cache_type.to_string()))?;
+            result.insert(cache_type);
+        }
+    } else {
+        result.extend(CacheType::iter().filter(|t| t.cached_by_default()));
+    }
+    // Sorted so that output is in a consistent order
+    let mut result: Vec<_> = result.into_iter().collect();
+    result.sort();
+    Ok(result)
+}
+
+fn get_min_recache_interval(
+    input_manager: &input_manager::Manager,
+    cache_type: CacheType,
+) -> Result<chrono::Duration, Error> {
+    let result = if let Some(duration) = input_manager.get(cache_type.min_recache_input()) {
+        let duration = humantime::parse_duration(duration)?;
+        chrono::Duration::from_std(duration)?
+    } else {
+        cache_type.default_min_recache_interval()
+    };
+    Ok(result)
+}
+
+fn dependency_file_path(cache_type: CacheType, scope: &HashValue, job: &Job) -> Result<Path, Error> {
+    let dependency_dir = dependency_files_dir()?;
+    let mut hasher = Blake3Hasher::default();
+    scope.hash(&mut hasher);
+    cache_type.hash(&mut hasher);
+    job.hash(&mut hasher);
+    let file_name = format!("{}.postcard", hasher.hash_value());
+    Ok(dependency_dir.join(&file_name))
+}
+
+fn build_cache_entry_dependencies(cache_type: CacheType, scope: &HashValue, job: &Job) -> Result<CacheEntry, Error> {
+    use crate::cache_key_builder::CacheKeyBuilder;
+    let name = format!("{} (dependency list)", cache_type.friendly_name());
+    let mut key_builder = CacheKeyBuilder::new(&name);
+    key_builder.add_key_data(scope);
+    key_builder.set_key_attribute(Attribute::Workflow, job.get_workflow().to_string());
+    key_builder.set_key_attribute(Attribute::Job, job.get_job_id().to_string());
+    if let Some(properties) = job.matrix_properties_as_string() {
+        key_builder.set_key_attribute(Attribute::Matrix, properties);
+    }
+    let mut cache_entry = key_builder.into_entry();
+    cache_entry.root(node::os::homedir());
+    let path = dependency_file_path(cache_type, scope, job)?;
+    cache_entry.path(path);
+    Ok(cache_entry)
+}
+
+/// Restores the Cargo home and any extra paths. If `restore` is false, nothing
+/// is downloaded but the state needed by the post step to save is still
+/// recorded.
+pub async fn restore_cargo_cache(input_manager: &input_manager::Manager, restore: bool) -> Result<(), Error> {
+    restore_cargo_cache_with_report(input_manager, restore)
+        .await
+        .map(|_| ())
+}
+
+/// Restores the Cargo home and any extra paths, returning what was restored
+/// for each cache type. The hit kinds are also recorded in the action state
+/// for the post step.
+async fn restore_cargo_cache_with_report(
+    input_manager: &input_manager::Manager,
+    restore: bool,
+) -> Result<RestoreReport, Error> {
+    use crate::access_times::supports_atime;
+    use crate::cargo_lock_hashing::hash_cargo_lock_files;
+
+    info!("Checking to see if filesystem supports access times...");
+    let atimes_supported = supports_atime().await?;
+    if atimes_supported {
+        info!(concat!(
+            "File access times supported. Hooray! ",
+            "These will be used to intelligently decide what can be dropped from within cached cargo home items."
+        ));
+    } else {
+        notice!(concat!("File access times not supported - cannot perform intelligent cache pruning. ",
+            "Likely this platform is Windows. ",
+            "The hash of all Cargo.lock files found in this folder will be used as part of the key for cached cargo home entries. ",
+            "This means certain caches will be rebuilt from scratch whenever a Cargo.lock file changes. ",
+            "This is to avoid cache entries growing monotonically. ",
+            "Note that enabling file access times on Windows is generally a bad idea since Microsoft never implemented relatime semantics.")
+        );
+    }
+    // Pruning relies on access times having been reset after a restore, so an
+    // unrestored Cargo home must never be pruned
+    core::save_state(
+        ATIMES_SUPPORTED_KEY,
+        serde_json::to_string(&(atimes_supported && restore))?,
+    );
+
+    let scope_hash = if atimes_supported {
+        // We can't use the empty array because it will encode to an empty string, which
+        // doesn't play well with `save_state`.
+        HashValue::from_bytes(&[42u8])
+    } else {
+        let cwd = node::process::cwd();
+        let lock_hash = hash_cargo_lock_files(&cwd).await?;
+        HashValue::from_bytes(&lock_hash.bytes)
+    };
+    // Alternate registries and source replacements change which index and crate
+    // folders are used
+    let cargo_config = load_cargo_config().await;
+    let scope_hash = scope_with_registries(scope_hash, &cargo_config.registry_key_data());
+    core::save_state(SCOPE_HASH_KEY, safe_encoding::encode(&scope_hash));
+
+    let cross_platform_sharing = get_cross_platform_sharing(input_manager)?;
+    let transfer_options = get_transfer_options(input_manager)?;
+    let verify = parse_optional_input(input_manager, Input::VerifyCache)?.unwrap_or(false);
+    // Mark as used to avoid spurious warnings (we only use these when we save the
+    // entries)
+    parse_optional_input::<bool>(input_manager, Input::PreserveTimestamps)?;
+    parse_optional_input::<bool>(input_manager, Input::JobSummary)?;
+    parse_optional_input::<bool>(input_manager, Input::PruneTarget)?;
+    let prefetch = parse_optional_input(input_manager, Input::Prefetch)?.unwrap_or(false);
+    let fail_on_cache_miss = parse_optional_input(input_manager, Input::FailOnCacheMiss)?.unwrap_or(false);
+    let cached_types = get_types_to_cache(input_manager)?;
+    let mut extra_paths = get_extra_cache_paths(input_manager)?;
+    if parse_optional_input(input_manager, Input::CacheRustup)?.unwrap_or(false) {
+        let rustup_home = find_rustup_home();
+        let key_data = build_rustup_key_data(&rustup_home).await?;
+        // Serialized since the key data may be empty, which `save_state` does not
+        // preserve
+        core::save_state(RUSTUP_KEY_DATA_KEY, serde_json::to_string(&key_data)?);
+        extra_paths.push(rustup_cache_path(&rustup_home, key_data));
+    }
+    if parse_optional_input(input_manager, Input::CacheTarget)?.unwrap_or(false) {
+        // Recorded so the same folder is saved even if the environment changes
+        let info = find_target_dir_info(&cargo_config).await;
+        core::save_state(TARGET_DIR_KEY, serde_json::to_string(&info)?);
+        // Artifact names are only needed to decide what to save
+        extra_paths.push(target_cache_path(&info, &get_target_exclusions(input_manager)?, &[]));
+    }
+    let extra_paths = remove_overlapping_paths(&cached_types, extra_paths).await?;
+    for cache_type in &cached_types {
+        // Mark as used to avoid spurious warnings (we only use this when we save the
+        // entries)
+        let _ = get_min_recache_interval(input_manager, *cache_type)?;
+    }
+    core::start_group("Cargo home");
+    let restored = if restore {
+        run_per_type(&cached_types, "restore", |cache_type| {
+            restore_cache_type(
+                cache_type,
+                &scope_hash,
+                cross_platform_sharing,
+                transfer_options,
+                verify,
+            )
+        })
+        .await
+    } else {
+        run_per_type(&cached_types, "record", record_cache_type).await
+    };
+    core::end_group();
+    let report = RestoreReport::from_hits(cached_types.iter().copied().zip(restored?), find_path);
+    let hits = report.hits();
+    for cache_type in hits.keys() {
+        let outcome = &report.per_type[cache_type];
+        info!(
+            "Restore of {} to {}: {}",
+            cache_type.friendly_name(),
+            outcome.path,
+            outcome.hit
+        );
+    }
+    for (name, value) in hit_outputs(&hits) {
+        core::set_output(name, value);
+    }
+    core::save_state(CACHE_HITS_KEY, serde_json::to_string(&hits)?);
+    if let Some(message) = partial_hit_notice(&hits) {
+        notice!("{}", message);
+    }
+    if restore {
+        check_cache_misses(fail_on_cache_miss, &hits)?;
+    }
+    if !extra_paths.is_empty() {
+        core::start_group("Extra cache paths");
+        restore_extra_cache_paths(&extra_paths, transfer_options, restore).await?;
+        core::end_group();
+    }
+    if should_prefetch(prefetch, &hits) {
+        core::start_group("Prefetching dependencies");
+        prefetch_dependencies(input_manager).await;
+        core::end_group();
+    }
+    Ok(report)
+}
+
+pub async fn save_cargo_cache(input_manager: &input_manager::Manager) -> Result<(), Error> {
+    let scope_hash = core::get_state(SCOPE_HASH_KEY).ok_or_else(|| Error::InvalidState(SCOPE_HASH_KEY.into()))?;
+    let scope_hash = safe_encoding::decode(&scope_hash).map_err(|_| Error::InvalidState(SCOPE_HASH_KEY.into()))?;
+    let scope_hash = HashValue::from_bytes(&scope_hash);
+
+    let atimes_supported =
+        core::get_state(ATIMES_SUPPORTED_KEY).ok_or_else(|| Error::InvalidState(ATIMES_SUPPORTED_KEY.into()))?;
+    let atimes_supported: bool = serde_json::de::from_str(&atimes_supported)?;
+
+    let cross_platform_sharing = get_cross_platform_sharing(input_manager)?;
+    let transfer_options = get_transfer_options(input_manager)?;
+    let preserve_timestamps = parse_optional_input(input_manager, Input::PreserveTimestamps)?.unwrap_or(false);
+    let job_summary = parse_optional_input(input_manager, Input::JobSummary)?.unwrap_or(false);
+    let cached_types = get_types_to_cache(input_manager)?;
+    let rustc_version = find_rustc_version().await;
+    let mut min_recache_intervals = HashMap::new();
+    for cache_type in &cached_types {
+        min_recache_intervals.insert(*cache_type, get_min_recache_interval(input_manager, *cache_type)?);
+    }
+    core::start_group("Cargo home");
+    let summary_rows = run_per_type(&cached_types, "save", |cache_type| {
+        let options = SaveOptions {
+            scope_hash: &scope_hash,
+            atimes_supported,
+            min_recache_interval: min_recache_intervals[&cache_type],
+            cross_platform_sharing,
+            transfer_options,
+            preserve_timestamps,
+            rustc_version: rustc_version.as_deref(),
+        };
+        save_cache_type(cache_type, options)
+    })
+    .await;
+    core::end_group();
+    let mut summary_rows = summary_rows?;
+    let hits: BTreeMap<CacheType, HitKind> = {
+        let hits = core::get_state(CACHE_HITS_KEY).ok_or_else(|| Error::InvalidState(CACHE_HITS_KEY.into()))?;
+        serde_json::from_str(&hits)?
+    };
+    for (cache_type, row) in cached_types.iter().zip(summary_rows.iter_mut()) {
+        let hit = hits
+            .get(cache_type)
+            .map_or_else(|| "-".to_string(), ToString::to_string);
+        row.insert(1, hit);
+    }
+
+    let mut extra_paths = get_extra_cache_paths(input_manager)?;
+    if parse_optional_input(input_manager, Input::CacheRustup)?.unwrap_or(false) {
+        let key_data =
+            core::get_state(RUSTUP_KEY_DATA_KEY).ok_or_else(|| Error::InvalidState(RUSTUP_KEY_DATA_KEY.into()))?;
+        let key_data: String = serde_json::from_str(&key_data)?;
+        extra_paths.push(rustup_cache_path(&find_rustup_home(), key_data));
+    }
+    if parse_optional_input(input_manager, Input::CacheTarget)?.unwrap_or(false) {
+        let info = core::get_state(TARGET_DIR_KEY).ok_or_else(|| Error::InvalidState(TARGET_DIR_KEY.into()))?;
+        let info: TargetDirInfo = serde_json::from_str(&info)?;
+        if parse_optional_input(input_manager, Input::PruneTarget)?.unwrap_or(false) {
+            let target_dir = Path::from(info.path.as_str());
+            if target_dir.exists().await {
+                clean_target_docs(&target_dir).await;
+            }
+            prune_target_for_cache(&target_dir).await?;
+        }
+        let exclusions = get_target_exclusions(input_manager)?;
+        let artifacts = if exclusions.contains(&TargetExclusion::Artifacts) {
+            find_artifact_names().await
+        } else {
+            Vec::new()
+        };
+        extra_paths.push(target_cache_path(&info, &exclusions, &artifacts));
+    }
+    let extra_paths = remove_overlapping_paths(&cached_types, extra_paths).await?;
+    if !extra_paths.is_empty() {
+        core::start_group("Extra cache paths");
+        let stats = save_extra_cache_paths(&extra_paths, transfer_options, rustc_version.as_deref()).await?;
+        summary_rows.push(vec![
+            "Extra paths".to_string(),
+            "-".to_string(),
+            extra_paths.len().to_string(),
+            stats.saved.to_string(),
+            stats.unchanged.to_string(),
+            stats.not_saved.to_string(),
+        ]);
+        core::end_group();
+    }
+
+    if job_summary {
+        write_job_summary(summary_rows).await?;
+    }
+    Ok(())
+}
+
+async fn write_job_summary(rows: Vec<Vec<String>>) -> Result<(), Error> {
+    use crate::actions::summary::Summary;
+
+    if Summary::file_path().is_none() {
+        warning!("Unable to write job summary since this runner does not appear to support them");
+        return Ok(());
+    }
+    Summary::default()
+        .add_heading("Cargo home cache", 3)
+        .add_table(&["Item", "Restore", "Groups", "Saved", "Unchanged", "Not saved"], rows)
+        .write()
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        check_cache_misses, find_overlapping_paths, get_types_to_cache, hit_outputs, parse_extra_cache_paths,
+        partial_hit_notice, resolve_target_dir, run_per_type, runner_image, rustup_cache_path, rustup_home_from_env,
+        scope_with_registries, should_prefetch, target_ignores, CacheType, CachedFolderInfo, ExtraCachePath, HitKind,
+        IndexLayout, TargetDirInfo, TargetExclusion, CACHED_FOLDER_INFO_VERSION,
+    };
+    use std::collections::HashMap;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn git_index_ignores() {
+        let ignores = IndexLayout::Git.ignores();
+        assert!(ignores.should_ignore(".last-updated", 1));
+        assert!(!ignores.should_ignore(".last-updated", 2));
+        assert!(!ignores.should_ignore(".git", 1));
+    }
+
+    #[wasm_bindgen_test]
+    fn sparse_index_ignores() {
+        let ignores = IndexLayout::Sparse.ignores();
+        assert!(!ignores.should_ignore(".cache", 1));
+        assert!(!ignores.should_ignore("config.json", 1));
+    }
+
+    #[wasm_bindgen_test]
+    async fn per_registry_index_ignores() -> Result<(), crate::Error> {
+        use super::depth_to_match;
+        use crate::dir_tree::match_relative_paths;
+        use crate::node::path::Path;
+        use crate::node::{self};
+
+        // One Git-based and one sparse registry, as for crates.io plus an
+        // alternate registry
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-index-")).await?;
+        let root = temp_dir.path().clone();
+        let git_index = root.join("index.crates.io-1949cf8c6b5b557f");
+        let sparse_index = root.join("my-registry-0123456789abcdef");
+        node::fs::create_dir_all(&git_index.join(".git")).await?;
+        node::fs::write_file(&git_index.join(".last-updated"), b"").await?;
+        node::fs::create_dir_all(&sparse_index.join(".cache")).await?;
+        node::fs::write_file(&sparse_index.join("config.json"), b"{}").await?;
+
+        let mut groups =
+            match_relative_paths(&root, &depth_to_match(CacheType::Indices.grouping_depth())?, true).await?;
+        groups.sort_by_key(ToString::to_string);
+        assert_eq!(groups.len(), 2);
+
+        let git_ignores = CacheType::Indices.ignores(&git_index).await;
+        assert!(git_ignores.should_ignore(".last-updated", 1));
+        let sparse_ignores = CacheType::Indices.ignores(&sparse_index).await;
+        assert!(!sparse_ignores.should_ignore(".last-updated", 1));
+        assert!(!sparse_ignores.should_ignore(".cache", 1));
+
+        temp_dir.close().await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn restore_disabled_records_state() -> Result<(), crate::Error> {
+        use super::{
+            cached_folder_info_dir, cached_folder_info_path, remove_cached_folder_info, restore_cargo_cache_with_report,
+        };
+        use crate::input_manager::{Input, Manager as InputManager};
+        use crate::node::{self};
+
+        // Everything written by the restore, including the action state and
+        // outputs, goes to a temporary directory
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-restore-")).await?;
+        let home = temp_dir.path().join("home");
+        let state_file = temp_dir.path().join("state");
+        let output_file = temp_dir.path().join("output");
+        node::fs::write_file(&state_file, b"").await?;
+        node::fs::write_file(&output_file, b"").await?;
+        let mut env = node::process::ScopedEnv::default();
+        env.set("HOME", &home.to_string())
+            .set("USERPROFILE", &home.to_string())
+            .set("CARGO_HOME", &home.join(".cargo").to_string())
+            .set("GITHUB_STATE", &state_file.to_string())
+            .set("GITHUB_OUTPUT", &output_file.to_string());
+        let context = [
+            ("GITHUB_RUN_ID", "1"),
+            ("GITHUB_RUN_ATTEMPT", "1"),
+            ("GITHUB_JOB", "test"),
+            ("GITHUB_WORKFLOW", "test"),
+            ("GITHUB_REF_NAME", "main"),
+            ("GITHUB_SHA", "0000000000000000000000000000000000000000"),
+            ("GITHUB_REPOSITORY", "octocat/hello-world"),
+            ("GITHUB_ACTOR", "octocat"),
+            ("GITHUB_EVENT_NAME", "push"),
+        ];
+        for (name, value) in context {
+            env.set(name, value);
+        }
+
+        // Nothing is restored, but the folder information needed to save is
+        // recorded
+        let inputs = InputManager::from_inputs([(Input::CacheOnly, "bin")]);
+        let report = restore_cargo_cache_with_report(&inputs, false).await?;
+        assert_eq!(
+            report.hits().into_iter().collect::<Vec<_>>(),
+            [(CacheType::Bin, HitKind::Miss)]
+        );
+        let info_path = cached_folder_info_path(CacheType::Bin)?;
+        assert!(info_path.to_string().starts_with(&home.to_string()));
+        assert!(info_path.exists().await);
+
+        // The per-job folder information is removed by the post step
+        remove_cached_folder_info().await;
+        assert!(!cached_folder_info_dir()?.exists().await);
+        drop(env);
+        temp_dir.close().await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn git_index_integrity_detects_missing_packs() -> Result<(), crate::Error> {
+        use super::Cache;
+        use crate::node;
+
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-index-integrity-")).await?;
+        let index = temp_dir.path().join("github.com-1ecc6299db9ec823");
+        let commit = "0123456789abcdef0123456789abcdef01234567\n";
+        let files = [
+            (".git/HEAD", "ref: refs/heads/master\n"),
+            (".git/refs/heads/master", commit),
+            (".git/objects/pack/pack-1.idx", "index"),
+            (".git/objects/pack/pack-1.pack", "objects"),
+            ("config.json", "{}"),
+        ];
+        for (file, content) in files {
+            let path = node::path::join_all(&index, file.split('/'));
+            node::fs::create_dir_all(&path.parent()).await?;
+            node::fs::write_file(&path, content.as_bytes()).await?;
+        }
+
+        let intact = Cache::group_integrity_hash(CacheType::Indices, &index).await?;
+        assert_eq!(Cache::group_integrity_hash(CacheType::Indices, &index).await?, intact);
+        node::fs::remove_file(&node::path::join_all(
+            &index,
+            [".git", "objects", "pack", "pack-1.pack"],
+        ))
+        .await?;
+        let damaged = Cache::group_integrity_hash(CacheType::Indices, &index).await?;
+        temp_dir.close().await?;
+        assert_ne!(damaged, intact);
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn target_pruning() -> Result<(), crate::Error> {
+        use super::prune_target_for_cache;
+        use crate::node::path::Path;
+        use crate::node::{self};
+
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-prune-")).await?;
+        let root = temp_dir.path().clone();
+        let kept = [
+            "debug/deps/libfoo.rlib",
+            "debug/build/foo/output",
+            "wasm32-unknown-unknown/release/app.wasm",
+        ];
+        let pruned = [
+            "doc/foo/index.html",
+            "debug/examples/demo",
+            "wasm32-unknown-unknown/release/examples/demo.wasm",
+            "wasm32-unknown-unknown/doc/foo/index.html",
+        ];
+        for file in kept.iter().chain(pruned.iter()) {
+            let path = node::path::join_all(&root, file.split('/'));
+            node::fs::create_dir_all(&path.parent()).await?;
+            node::fs::write_file(&path, b"").await?;
+        }
+
+        let removed = prune_target_for_cache(&root).await?;
+        let expected = [
+            "debug/examples",
+            "doc",
+            "wasm32-unknown-unknown/doc",
+            "wasm32-unknown-unknown/release/examples",
+        ];
+        let expected: Vec<Path> = expected
+            .iter()
+            .map(|dir| node::path::join_all(&root, dir.split('/')))
+            .collect();
+        assert_eq!(removed, expected);
+        for file in kept {
+            assert!(node::path::join_all(&root, file.split('/')).exists().await);
+        }
+        for file in pruned {
+            assert!(!node::path::join_all(&root, file.split('/')).exists().await);
+        }
+
+        temp_dir.close().await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_extra_paths() {
+        let parsed =
+            parse_extra_cache_paths("sccache: ~/.cache/sccache\n\ntools:C:\\tools\n").expect("Failed to parse");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].name, "sccache");
+        assert_eq!(parsed[0].path, "~/.cache/sccache");
+        assert_eq!(parsed[1].path, "C:\\tools");
+
+        assert!(parse_extra_cache_paths("crates:/tmp/crates").is_err());
+        assert!(parse_extra_cache_paths("no-separator").is_err());
+        assert!(parse_extra_cache_paths("bad name:/tmp").is_err());
+        assert!(parse_extra_cache_paths("a:/tmp/a\na:/tmp/b").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn extra_path_save_decision() {
+        // (restored hash, current hash, needs save)
+        let cases = [
+            (None, None, false),
+            (None, Some(0x1234), true),
+            (Some(0x1234), None, false),
+            (Some(0x1234), Some(0x1234), false),
+            (Some(0x1234), Some(0x5678), true),
+        ];
+        for (restored, current, expected) in cases {
+            assert_eq!(
+                ExtraCachePath::needs_save(restored, current),
+                expected,
+                "restored: {:?}, current: {:?}",
+                restored,
+                current
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn rustup_paths() {
+        use crate::node::path::Path;
+
+        let home = Path::from("home");
+        let mut env = HashMap::new();
+        assert_eq!(rustup_home_from_env(&env, &home), home.join(".rustup"));
+        env.insert("RUSTUP_HOME".to_string(), String::new());
+        assert_eq!(rustup_home_from_env(&env, &home), home.join(".rustup"));
+        env.insert("RUSTUP_HOME".to_string(), "custom".to_string());
+        let rustup_home = rustup_home_from_env(&env, &home);
+        assert_eq!(rustup_home, Path::from("custom"));
+
+        let cache_path = rustup_cache_path(&rustup_home, String::new());
+        assert_eq!(Path::from(cache_path.path.as_str()), Path::from("custom"));
+        assert!(!cache_path.ignores.should_ignore("toolchains", 1));
+        assert!(!cache_path.ignores.should_ignore("update-hashes", 1));
+        assert!(cache_path.ignores.should_ignore("settings.toml", 1));
+        assert!(cache_path.ignores.should_ignore("downloads", 1));
+        assert!(parse_extra_cache_paths("rustup:/tmp/rustup").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn rustup_key_contents() {
+        use super::{rustup_key_data, RustupSettings};
+        use crate::toolchain_file::ToolchainFile;
+
+        let settings: RustupSettings = basic_toml::from_str(concat!(
+            "version = \"12\"\n",
+            "default_toolchain = \"stable-x86_64-unknown-linux-gnu\"\n",
+            "profile = \"minimal\"\n",
+            "[overrides]\n",
+            "\"/ws\" = \"nightly-2024-01-01\"\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            rustup_key_data(&settings, None),
+            "toolchains=nightly-2024-01-01,stable-x86_64-unknown-linux-gnu\nprofile=minimal\ncomponents=\ntargets=\n"
+        );
+        let toolchain_file = ToolchainFile {
+            channel: "1.74.0".into(),
+            profile: Some("default".into()),
+            components: vec!["rustfmt".into(), "clippy".into()],
+            targets: vec!["wasm32-unknown-unknown".into()],
+        };
+        assert_eq!(
+            rustup_key_data(&settings, Some(&toolchain_file)),
+            concat!(
+                "toolchains=1.74.0,nightly-2024-01-01,stable-x86_64-unknown-linux-gnu\n",
+                "profile=default\n",
+                "components=clippy,rustfmt\n",
+                "targets=wasm32-unknown-unknown\n"
+            )
+        );
+        assert_eq!(
+            rustup_key_data(&RustupSettings::default(), None),
+            "toolchains=\nprofile=default\ncomponents=\ntargets=\n"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn target_dir_resolution() {
+        use crate::node::path::Path;
+
+        let cwd = Path::from("/ws/crates/foo");
+        let workspace_root = Path::from("/ws");
+        let configured = Path::from("/ws/build/target");
+        let mut env = HashMap::new();
+        assert_eq!(resolve_target_dir(&env, None, None, &cwd), cwd.join("target"));
+        assert_eq!(
+            resolve_target_dir(&env, None, Some(&workspace_root), &cwd),
+            workspace_root.join("target")
+        );
+        assert_eq!(
+            resolve_target_dir(&env, Some(&configured), Some(&workspace_root), &cwd),
+            configured
+        );
+        env.insert("CARGO_TARGET_DIR".to_string(), "../custom-target".to_string());
+        assert_eq!(
+            resolve_target_dir(&env, Some(&configured), Some(&workspace_root), &cwd),
+            Path::from("/ws/crates/custom-target")
+        );
+        assert!(parse_extra_cache_paths("target:target").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn workspace_root_discovery() -> Result<(), crate::Error> {
+        use super::find_workspace_root;
+        use crate::node;
+
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-workspace-")).await?;
+        let root = temp_dir.path().clone();
+        let member = root.join("crates").join("foo");
+        node::fs::create_dir_all(&member.join("src")).await?;
+        node::fs::write_file(&root.join("Cargo.toml"), b"[workspace]\nmembers = [\"crates/*\"]\n").await?;
+        node::fs::write_file(&member.join("Cargo.toml"), b"[package]\nname = \"foo\"\n").await?;
+        assert_eq!(find_workspace_root(&member.join("src")).await, Some(root.clone()));
+        assert_eq!(find_workspace_root(&root).await, Some(root.clone()));
+        node::fs::write_file(&root.join("Cargo.toml"), b"[package]\nname = \"outer\"\n").await?;
+        assert_eq!(find_workspace_root(&member).await, Some(member.clone()));
+        temp_dir.close().await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    fn target_exclusions() {
+        let artifacts = ["app".to_string(), "app.exe".to_string()];
+        let ignores = target_ignores(&[TargetExclusion::Incremental, TargetExclusion::Artifacts], &artifacts);
+        assert!(ignores.should_ignore("incremental", 2));
+        assert!(ignores.should_ignore("incremental", 3));
+        assert!(!ignores.should_ignore("deps", 2));
+        assert!(!ignores.should_ignore("deps", 3));
+        assert!(!ignores.should_ignore(".fingerprint", 2));
+        assert!(ignores.should_ignore("app", 2));
+        assert!(!ignores.should_ignore("app", 1));
+
+        let ignores = target_ignores(&[TargetExclusion::Fingerprints], &artifacts);
+        assert!(ignores.should_ignore(".fingerprint", 2));
+        assert!(!ignores.should_ignore("incremental", 2));
+        assert!(!ignores.should_ignore("app", 2));
+    }
+
+    #[wasm_bindgen_test]
+    async fn target_exclusions_are_stashed() -> Result<(), crate::Error> {
+        use super::Stash;
+        use crate::node::{self};
+
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-stash-")).await?;
+        let root = temp_dir.path().clone();
+        let target = root.join("target");
+        let excluded = ["debug/incremental/app-1/s-1", "debug/app"];
+        let kept = ["debug/deps/app-1", "doc/app/index.html"];
+        for file in excluded.iter().chain(kept.iter()) {
+            let path = node::path::join_all(&target, file.split('/'));
+            node::fs::create_dir_all(&path.parent()).await?;
+            node::fs::write_file(&path, b"").await?;
+        }
+        let artifacts = ["app".to_string()];
+        let ignores = target_ignores(&[TargetExclusion::Incremental, TargetExclusion::Artifacts], &artifacts);
+
+        let stash = Stash::create(&target, &ignores).await?;
+        for file in excluded {
+            assert!(!node::path::join_all(&target, file.split('/')).exists().await);
+        }
+        for file in kept {
+            assert!(node::path::join_all(&target, file.split('/')).exists().await);
+        }
+        stash.restore().await?;
+        for file in excluded.iter().chain(kept.iter()) {
+            assert!(node::path::join_all(&target, file.split('/')).exists().await);
+        }
+        assert_eq!(node::fs::read_dir_names(&root).await?, ["target"]);
+        temp_dir.close().await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    fn target_artifact_names() {
+        use super::artifact_names;
+
+        let metadata: cargo_metadata::Metadata = serde_json::from_str(
+            r#"{
+                "packages": [
+                    {
+                        "name": "my-tool",
+                        "version": "0.1.0",
+                        "id": "my-tool 0.1.0 (path+file:///ws)",
+                        "dependencies": [],
+                        "targets": [
+                            {"kind": ["bin"], "crate_types": ["bin"], "name": "my-tool", "src_path": "/ws/src/main.rs"},
+                            {"kind": ["cdylib"], "crate_types": ["cdylib"], "name": "my-tool", "src_path": "/ws/src/lib.rs"}
+                        ],
+                        "features": {},
+                        "manifest_path": "/ws/Cargo.toml"
+                    }
+                ],
+                "workspace_members": ["my-tool 0.1.0 (path+file:///ws)"],
+                "resolve": null,
+                "target_directory": "/ws/target",
+                "version": 1,
+                "workspace_root": "/ws"
+            }"#,
+        )
+        .expect("Failed to parse metadata");
+        let artifacts = artifact_names(&metadata);
+        assert!(artifacts.contains(&"my-tool".to_string()));
+        assert!(artifacts.contains(&"my-tool.exe".to_string()));
+        assert!(artifacts.contains(&"my_tool.wasm".to_string()));
+        assert!(artifacts.contains(&"libmy_tool.so".to_string()));
+        assert!(!artifacts.contains(&"deps".to_string()));
+    }
+
+    #[wasm_bindgen_test]
+    fn target_key_depends_on_environment() {
+        use super::{target_cache_path, TransferOptions};
+
+        let restore_keys = |info: &TargetDirInfo, rustc_version: Option<&str>| {
+            target_cache_path(info, &[], &[])
+                .cache_entry(TransferOptions::default(), None, rustc_version)
+                .get_restore_keys()
+        };
+        let mut info = TargetDirInfo {
+            path: "/ws/target".into(),
+            runner_image: Some("ubuntu22".into()),
+        };
+        let original = restore_keys(&info, None);
+        assert_eq!(original, restore_keys(&info, None));
+        // The compiler version is preferred but an entry built by another
+        // compiler can still be restored
+        let qualified = restore_keys(&info, Some("rustc 1.78.0 (9b00956e5 2024-04-29)"));
+        assert_eq!(qualified.len(), 2);
+        assert_eq!(qualified[1], original[0]);
+        info.runner_image = Some("ubuntu24".into());
+        assert_ne!(original, restore_keys(&info, None));
+
+        let mut env = HashMap::new();
+        assert_eq!(runner_image(&env), None);
+        env.insert("ImageOS".to_string(), "ubuntu20".to_string());
+        assert_eq!(runner_image(&env).as_deref(), Some("ubuntu20"));
+    }
+
+    #[wasm_bindgen_test]
+    fn registries_change_scope() {
+        use rustup_toolchain_manifest::HashValue;
+
+        let scope = HashValue::from_bytes(&[42u8]);
+        assert_eq!(scope_with_registries(scope.clone(), ""), scope);
+        let with_registry = scope_with_registries(scope.clone(), "registry a=https://a.example.com\n");
+        assert_ne!(with_registry, scope);
+        assert_ne!(
+            scope_with_registries(scope, "registry b=https://b.example.com\n"),
+            with_registry
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn prefetch_only_on_index_miss() -> Result<(), crate::Error> {
+        use crate::input_manager::{Input, Manager as InputManager};
+        use std::collections::BTreeMap;
+
+        let hits_for = |inputs: &InputManager, hit: HitKind| -> Result<BTreeMap<CacheType, HitKind>, crate::Error> {
+            let types = get_types_to_cache(inputs)?;
+            Ok(types.into_iter().map(|cache_type| (cache_type, hit)).collect())
+        };
+        let default_types = InputManager::from_inputs([]);
+        assert!(should_prefetch(true, &hits_for(&default_types, HitKind::Miss)?));
+        assert!(!should_prefetch(false, &hits_for(&default_types, HitKind::Miss)?));
+        assert!(!should_prefetch(true, &hits_for(&default_types, HitKind::Partial)?));
+        assert!(!should_prefetch(true, &hits_for(&default_types, HitKind::Exact)?));
+
+        // A fetched index would not be saved if indices are not cached
+        let without_indices = InputManager::from_inputs([(Input::CacheOnly, "crates git-repos")]);
+        assert!(!should_prefetch(true, &hits_for(&without_indices, HitKind::Miss)?));
+        let with_indices = InputManager::from_inputs([(Input::CacheOnly, "indices")]);
+        assert!(should_prefetch(true, &hits_for(&with_indices, HitKind::Miss)?));
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn per_type_failure_does_not_cancel_others() {
+        use crate::Error;
+        use parking_lot::Mutex;
+
+        let completed = Mutex::new(Vec::new());
+        let cache_types = [CacheType::Indices, CacheType::Crates, CacheType::GitRepos];
+        let result = run_per_type(&cache_types, "test", |cache_type| {
+            let completed = &completed;
+            async move {
+                if cache_type == CacheType::Indices {
+                    return Err(Error::InvalidState("test failure".into()));
+                }
+                completed.lock().push(cache_type);
+                Ok(cache_type)
+            }
+        })
+        .await;
+        assert!(matches!(result, Err(Error::InvalidState(_))));
+        assert_eq!(*completed.lock(), [CacheType::Crates, CacheType::GitRepos]);
+
+        let result = run_per_type(&cache_types, "test", |cache_type| async move { Ok(cache_type) }).await;
+        assert_eq!(result.expect("No operation should fail"), cache_types);
+    }
+
+    #[wasm_bindgen_test]
+    fn hit_kind_outputs() {
+        use std::collections::BTreeMap;
+
+        assert_eq!(HitKind::from_counts(None, 0), HitKind::Miss);
+        assert_eq!(HitKind::from_counts(Some(3), 0), HitKind::Miss);
+        assert_eq!(HitKind::from_counts(Some(3), 2), HitKind::Partial);
+        assert_eq!(HitKind::from_counts(Some(3), 3), HitKind::Exact);
+        assert_eq!(HitKind::from_counts(Some(0), 0), HitKind::Exact);
+
+        let output = |hits: &[(CacheType, HitKind)]| {
+            let hits: BTreeMap<_, _> = hits.iter().copied().collect();
+            hit_outputs(&hits)
+        };
+        let pair = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(
+            output(&[
+                (CacheType::Indices, HitKind::Exact),
+                (CacheType::Crates, HitKind::Exact)
+            ]),
+            [
+                pair("cache-hit", "true"),
+                pair("cache-indices-hit", "exact"),
+                pair("cache-crates-hit", "exact")
+            ]
+        );
+        assert_eq!(
+            output(&[(CacheType::GitRepos, HitKind::Partial), (CacheType::Bin, HitKind::Miss)]),
+            [
+                pair("cache-hit", "false"),
+                pair("cache-git-repos-hit", "partial"),
+                pair("cache-bin-hit", "miss")
+            ]
+        );
+        assert_eq!(output(&[]), [pair("cache-hit", "false")]);
+    }
+
+    #[wasm_bindgen_test]
+    fn fail_on_cache_miss() {
+        use crate::Error;
+        use std::collections::BTreeMap;
+
+        let hits: BTreeMap<_, _> = [
+            (CacheType::Indices, HitKind::Partial),
+            (CacheType::Crates, HitKind::Miss),
+            (CacheType::GitRepos, HitKind::Exact),
+        ]
+        .into_iter()
+        .collect();
+        assert!(check_cache_misses(false, &hits).is_ok());
+        match check_cache_misses(true, &hits) {
+            Err(Error::CacheMiss(missed)) => assert_eq!(missed, "crate files"),
+            other => panic!("Expected a cache miss error, got {:?}", other),
+        }
+
+        let hits: BTreeMap<_, _> = [(CacheType::Indices, HitKind::Partial)].into_iter().collect();
+        assert!(check_cache_misses(true, &hits).is_ok());
+    }
+
+    #[wasm_bindgen_test]
+    fn cached_folder_diff() {
+        use crate::delta::Action;
+
+        let info = |entries: &[(&str, u64)]| CachedFolderInfo {
+            version: CACHED_FOLDER_INFO_VERSION,
+            content_hash: 0,
+            entries: entries.iter().map(|(name, hash)| (name.to_string(), *hash)).collect(),
+        };
+        let restored = info(&[("build", 1), ("deps", 2), ("timestamp", 3)]);
+        let current = info(&[("build", 1), ("deps", 2), ("examples", 4), ("timestamp", 5)]);
+        assert_eq!(
+            current.changes_from(&restored),
+            vec![("examples", Action::Added), ("timestamp", Action::Changed)]
+        );
+        assert_eq!(
+            restored.changes_from(&current),
+            vec![("examples", Action::Removed), ("timestamp", Action::Changed)]
+        );
+        assert!(current.changes_from(&current).is_empty());
+
+        let old_format = CachedFolderInfo { version: 0, ..current };
+        assert!(!old_format.is_current_version());
+    }
+
+    #[wasm_bindgen_test]
+    fn restore_report_contents() {
+        use super::{RestoreOutcome, RestoreReport};
+        use crate::node::path::Path;
+
+        let root = Path::from("/home/runner/.cargo");
+        let report = RestoreReport::from_hits(
+            [
+                (CacheType::Indices, HitKind::Exact),
+                (CacheType::Crates, HitKind::Partial),
+                (CacheType::GitRepos, HitKind::Miss),
+            ],
+            |cache_type| root.join(cache_type.relative_path()),
+        );
+        assert_eq!(report.per_type.len(), 3);
+        assert_eq!(
+            report.per_type.get(&CacheType::Crates),
+            Some(&RestoreOutcome {
+                hit: HitKind::Partial,
+                path: root.join("registry").join("cache"),
+            })
+        );
+        assert_eq!(
+            report.per_type.get(&CacheType::GitRepos).map(|outcome| outcome.hit),
+            Some(HitKind::Miss)
+        );
+        assert!(!report.per_type.contains_key(&CacheType::Bin));
+        let hits = report.hits();
+        assert_eq!(
+            hits.into_iter().collect::<Vec<_>>(),
+            [
+                (CacheType::Indices, HitKind::Exact),
+                (CacheType::Crates, HitKind::Partial),
+                (CacheType::GitRepos, HitKind::Miss),
+            ]
+        );
+        assert_eq!(
+            hit_outputs(&report.hits())[0],
+            ("cache-hit".to_string(), "false".to_string())
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn partial_hits_produce_notice() {
+        use std::collections::BTreeMap;
+
+        let hits: BTreeMap<_, _> = [(CacheType::Indices, HitKind::Exact), (CacheType::Crates, HitKind::Miss)]
+            .into_iter()
+            .collect();
+        assert_eq!(partial_hit_notice(&hits), None);
+
+        let hits: BTreeMap<_, _> = [
+            (CacheType::Indices, HitKind::Partial),
+            (CacheType::Crates, HitKind::Exact),
+            (CacheType::GitRepos, HitKind::Partial),
+        ]
+        .into_iter()
+        .collect();
+        let notice = partial_hit_notice(&hits).expect("Expected a notice");
+        assert!(notice.ends_with(": registry indices, Git repositories"), "{}", notice);
+    }
+
+    #[wasm_bindgen_test]
+    fn overlapping_paths() {
+        use crate::node::path::Path;
+
+        let home = Path::from("home");
+        let cargo_home = home.join(".cargo");
+        let named = |name: &str, path: &Path| (name.to_string(), path.clone());
+        let reserved = [named("registry indices", &cargo_home.join("registry").join("index"))];
+        let extra = [
+            named("parent", &home.join("data")),
+            named("child", &home.join("data").join("nested")),
+            named("registry", &cargo_home.join("registry")),
+            named("duplicate", &home.join("data")),
+            named("other", &home.join("other")),
+        ];
+        assert_eq!(
+            find_overlapping_paths(&reserved, &extra),
+            [
+                None,
+                Some("parent".to_string()),
+                Some("registry indices".to_string()),
+                Some("parent".to_string()),
+                None,
+            ]
+        );
     }
-    Ok(())
 }
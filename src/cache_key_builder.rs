@@ -9,6 +9,7 @@ pub struct CacheKeyBuilder {
     name: String,
     hasher: Blake3Hasher,
     attributes: BTreeMap<&'static str, (String, bool)>,
+    qualifiers: Vec<(&'static str, String)>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, strum::Display, strum::IntoStaticStr, Ord, PartialEq, PartialOrd)]
@@ -49,8 +50,17 @@ pub enum Attribute {
     #[strum(serialize = "toolchain_version")]
     ToolchainVersion,
 
+    #[strum(serialize = "runner_image")]
+    RunnerImage,
+
+    #[strum(serialize = "rustc_version")]
+    RustcVersion,
+
     #[strum(serialize = "entries_hash")]
     EntriesHash,
+
+    #[strum(serialize = "integrity")]
+    Integrity,
 }
 
 impl CacheKeyBuilder {
@@ -59,6 +69,7 @@ impl CacheKeyBuilder {
             name: name.into(),
             hasher: Blake3Hasher::default(),
             attributes: BTreeMap::new(),
+            qualifiers: Vec::new(),
         };
         result.add_key_data(CACHE_ENTRY_VERSION);
         result
@@ -89,6 +100,21 @@ impl CacheKeyBuilder {
         self.attributes.insert(name.into(), (value, false));
     }
 
+    /// Adds an attribute to the key which a restore prefers to match but does
+    /// not require. The restore key is extended with the attribute, and the
+    /// unextended key remains as a fallback.
+    pub fn add_qualifier(&mut self, name: Attribute, value: String) {
+        self.qualifiers.push((name.into(), value));
+    }
+
+    fn qualify_restore_key(restore_key: &str, qualifiers: &[(&str, String)]) -> String {
+        let mut result = restore_key.to_string();
+        for (name, value) in qualifiers {
+            result += &format!("; {}={}", name, value);
+        }
+        result.replace(',', ";")
+    }
+
     fn restore_key_to_save_key(restore_key: &str, attributes: &BTreeMap<&str, (String, bool)>) -> String {
         use itertools::Itertools as _;
         use std::fmt::Write as _;
@@ -107,6 +133,18 @@ impl CacheKeyBuilder {
         save_key.replace(',', ";")
     }
 
+    /// Extracts the value of a non-key attribute from a cache key produced by
+    /// a `CacheKeyBuilder`
+    pub fn get_attribute(key: &str, attribute: Attribute) -> Option<&str> {
+        let attribute: &str = attribute.into();
+        let (_, attributes) = key.split_once("; attributes={")?;
+        let attributes = attributes.strip_suffix('}')?;
+        attributes
+            .split("; ")
+            .filter_map(|a| a.split_once('='))
+            .find_map(|(name, value)| (name == attribute).then_some(value))
+    }
+
     fn build_restore_key(name: &str, mut hasher: Blake3Hasher, attributes: &BTreeMap<&str, (String, bool)>) -> String {
         use std::hash::Hash as _;
 
@@ -126,8 +164,12 @@ impl CacheKeyBuilder {
 
     pub fn into_entry(self) -> CacheEntry {
         let restore_key = Self::build_restore_key(&self.name, self.hasher, &self.attributes);
-        let save_key = Self::restore_key_to_save_key(&restore_key, &self.attributes);
+        let qualified_key = Self::qualify_restore_key(&restore_key, &self.qualifiers);
+        let save_key = Self::restore_key_to_save_key(&qualified_key, &self.attributes);
         let mut result = CacheEntry::new(save_key.as_str());
+        if qualified_key != restore_key {
+            result.restore_key(qualified_key);
+        }
         result.restore_key(restore_key);
         // Since we have the "platform" attribute, turning this on makes no difference
         // unless the user overrides it
@@ -135,3 +177,56 @@ impl CacheKeyBuilder {
         result
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Attribute, CacheKeyBuilder};
+    use std::collections::BTreeMap;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn get_attribute_from_save_key() {
+        let mut attributes = BTreeMap::new();
+        let integrity: &str = Attribute::Integrity.into();
+        let path: &str = Attribute::Path.into();
+        attributes.insert(integrity, ("abc123".to_string(), false));
+        attributes.insert(path, ("index.crates.io-6f17d22bba15001f".to_string(), false));
+        let restore_key = "Ferrous Actions: registry indices (content) - id=AAAA";
+        let save_key = CacheKeyBuilder::restore_key_to_save_key(restore_key, &attributes);
+        assert_eq!(
+            CacheKeyBuilder::get_attribute(&save_key, Attribute::Integrity),
+            Some("abc123")
+        );
+        assert_eq!(
+            CacheKeyBuilder::get_attribute(&save_key, Attribute::Path),
+            Some("index.crates.io-6f17d22bba15001f")
+        );
+        assert_eq!(CacheKeyBuilder::get_attribute(&save_key, Attribute::Nonce), None);
+        assert_eq!(CacheKeyBuilder::get_attribute(restore_key, Attribute::Path), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn qualified_restore_keys() {
+        let mut builder = CacheKeyBuilder::empty("target (extra path)");
+        builder.add_key_data("/ws/target");
+        let unqualified = builder.into_entry().get_restore_keys();
+        assert_eq!(unqualified.len(), 1);
+
+        let entry = |version: &str| {
+            let mut builder = CacheKeyBuilder::empty("target (extra path)");
+            builder.add_key_data("/ws/target");
+            builder.add_qualifier(Attribute::RustcVersion, version.to_string());
+            builder.into_entry()
+        };
+        let old = entry("rustc 1.74.0 (79e9716c9 2023-11-13)");
+        let new = entry("rustc 1.78.0 (9b00956e5 2024-04-29)");
+        let (old_keys, new_keys) = (old.get_restore_keys(), new.get_restore_keys());
+        assert_eq!(old_keys.len(), 2);
+        assert_eq!(old_keys[1], unqualified[0]);
+        assert_eq!(new_keys[1], unqualified[0]);
+        assert_ne!(old_keys[0], new_keys[0]);
+        assert!(old_keys[0].starts_with(&old_keys[1]));
+        assert!(old.get_key().starts_with(&old_keys[0]));
+        assert!(!new.get_key().starts_with(&old_keys[0]));
+    }
+}
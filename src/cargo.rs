@@ -3,12 +3,18 @@ use crate::actions::exec::Command;
 use crate::actions::io;
 use crate::cargo_hooks::{
     Annotation as AnnotationHook, Composite as CompositeHook, Hook as CargoHook, Install as CargoInstallHook,
+    DEFAULT_ANNOTATION_LIMIT,
 };
-use crate::input_manager::{self, Input};
+use crate::input_manager::{self, parse_optional_input, Input};
 use crate::node::path::Path;
 use crate::node::process;
-use crate::{node, nonce, Error};
+use crate::test_summary::TestSummary;
+use crate::{error, info, node, nonce, versions, warning, Error};
+use parking_lot::Mutex;
+use serde::Deserialize;
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
 
 async fn create_empty_dir() -> Result<Path, Error> {
     let nonce = nonce::build(8);
@@ -30,9 +36,184 @@ impl CargoHook for ChangeCwdHook {
     }
 }
 
+/// Collects the results of `cargo test` into a summary while forwarding test
+/// and compiler output to the log
+struct TestSummaryHook {
+    summary: Arc<Mutex<TestSummary>>,
+}
+
+impl CargoHook for TestSummaryHook {
+    fn additional_cargo_options(&self) -> Vec<Cow<str>> {
+        vec!["--message-format=json".into()]
+    }
+
+    fn modify_command(&self, command: &mut Command) {
+        use crate::actions::exec::Stdio;
+
+        let summary = self.summary.clone();
+        command
+            .outline(move |line| {
+                use cargo_metadata::Message;
+
+                if !summary.lock().process_line(line) {
+                    info!("{}", line);
+                } else if let Ok(Message::CompilerMessage(message)) = serde_json::from_str(line) {
+                    if let Some(rendered) = &message.message.rendered {
+                        info!("{}", rendered.trim_end());
+                    }
+                }
+            })
+            .stdout(Stdio::null());
+    }
+}
+
+/// Records the WebAssembly files produced by `cargo build` while forwarding
+/// compiler output to the log
+struct WasmArtifactHook {
+    artifacts: Arc<Mutex<Vec<String>>>,
+}
+
+/// The WebAssembly files in a record of `cargo build` JSON output
+fn wasm_artifact_files(line: &str) -> Vec<String> {
+    use cargo_metadata::Message;
+
+    match serde_json::from_str(line) {
+        Ok(Message::CompilerArtifact(artifact)) => artifact
+            .filenames
+            .iter()
+            .filter(|file| file.extension() == Some("wasm"))
+            .map(ToString::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl CargoHook for WasmArtifactHook {
+    fn additional_cargo_options(&self) -> Vec<Cow<str>> {
+        vec!["--message-format=json".into()]
+    }
+
+    fn modify_command(&self, command: &mut Command) {
+        use crate::actions::exec::Stdio;
+
+        let artifacts = self.artifacts.clone();
+        command
+            .outline(move |line| {
+                use cargo_metadata::Message;
+
+                if let Ok(Message::CompilerMessage(message)) = serde_json::from_str(line) {
+                    if let Some(rendered) = &message.message.rendered {
+                        info!("{}", rendered.trim_end());
+                    }
+                }
+                let mut artifacts = artifacts.lock();
+                for file in wasm_artifact_files(line) {
+                    if !artifacts.contains(&file) {
+                        artifacts.push(file);
+                    }
+                }
+            })
+            .stdout(Stdio::null());
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Cargo {
     path: Path,
+    locked: bool,
+    offline: bool,
+    frozen: bool,
+    workspace: bool,
+    packages: Vec<String>,
+    excluded_packages: Vec<String>,
+    features: Vec<String>,
+    all_features: bool,
+    no_default_features: bool,
+    config: Vec<String>,
+    metadata: Option<cargo_metadata::Metadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    workspace_members: Vec<String>,
+}
+
+fn parse_workspace_member_ids(metadata: &str) -> Result<HashSet<String>, Error> {
+    let metadata: Metadata = serde_json::from_str(metadata)?;
+    Ok(metadata.workspace_members.into_iter().collect())
+}
+
+fn parse_metadata(metadata: &str) -> Result<cargo_metadata::Metadata, Error> {
+    let metadata = serde_json::from_str(metadata)?;
+    Ok(metadata)
+}
+
+fn find_manifest_path(args: &[String]) -> Option<&str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--manifest-path" {
+            return args.next().map(String::as_str);
+        } else if let Some(path) = arg.strip_prefix("--manifest-path=") {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// The target used by `Cargo::build_wasm` when none is specified
+pub const DEFAULT_WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+fn build_wasm_args(target: &str, release: bool) -> Vec<String> {
+    let mut args = vec!["--target".to_string(), target.to_string()];
+    if release {
+        args.push("--release".into());
+    }
+    args
+}
+
+/// If the arguments to `cargo build` only select a WebAssembly target and
+/// optionally a release build, returns the target and whether the build is a
+/// release build
+pub fn parse_wasm_build_args(args: &[String]) -> Option<(&str, bool)> {
+    let mut target = None;
+    let mut release = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--release" | "-r" => {
+                release = true;
+                continue;
+            }
+            "--target" => args.next()?.as_str(),
+            arg => arg.strip_prefix("--target=")?,
+        };
+        if target.replace(value).is_some() {
+            return None;
+        }
+    }
+    target
+        .filter(|target| target.starts_with("wasm"))
+        .map(|target| (target, release))
+}
+
+/// Whether the output of running a tool with `--version` reports a version
+/// satisfying the specified `cargo install` version requirement
+fn version_output_matches(output: &str, version: &str) -> bool {
+    let req = versions::parse_install_requirement(version);
+    let installed = versions::parse_version_output(output);
+    match (installed, req) {
+        (Some(installed), Some(req)) => versions::satisfies(&installed, &req),
+        _ => false,
+    }
+}
+
+/// Whether an argument to `--config` is either a `key=value` pair or the path
+/// of a configuration file
+fn is_valid_config_arg(value: &str) -> bool {
+    match value.split_once('=') {
+        Some((key, _)) => !key.trim().is_empty(),
+        None => value.ends_with(".toml"),
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -51,11 +232,25 @@ impl ToolchainVersion {
 }
 
 impl Cargo {
+    fn with_path(path: Path) -> Cargo {
+        Cargo {
+            path,
+            locked: false,
+            offline: false,
+            frozen: false,
+            workspace: false,
+            packages: Vec::new(),
+            excluded_packages: Vec::new(),
+            features: Vec::new(),
+            all_features: false,
+            no_default_features: false,
+            config: Vec::new(),
+            metadata: None,
+        }
+    }
+
     pub async fn from_environment() -> Result<Cargo, Error> {
-        io::which("cargo", true)
-            .await
-            .map(|path| Cargo { path })
-            .map_err(Error::Js)
+        io::which("cargo", true).await.map(Cargo::with_path).map_err(Error::Js)
     }
 
     pub async fn from_path(path: &Path) -> Result<Cargo, Error> {
@@ -63,14 +258,73 @@ impl Cargo {
         if !full_path.exists().await {
             return Err(Error::PathDoesNotExist(full_path.to_string()));
         }
-        let result = Cargo { path: full_path };
+        let result = Cargo::with_path(full_path);
         Ok(result)
     }
 
-    pub async fn get_installed(&self) -> Result<Vec<String>, Error> {
-        use parking_lot::Mutex;
-        use std::sync::Arc;
+    /// Flags which apply to Cargo as a whole and must precede the subcommand
+    fn global_args(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        if self.frozen {
+            // Implies both `--locked` and `--offline`
+            result.push("--frozen".into());
+        } else {
+            if self.locked {
+                result.push("--locked".into());
+            }
+            if self.offline {
+                result.push("--offline".into());
+            }
+        }
+        for config in &self.config {
+            result.extend(["--config".into(), config.clone()]);
+        }
+        result
+    }
+
+    /// The arguments preceding those specific to the subcommand
+    fn leading_args(&self, toolchain: Option<&str>, subcommand: &str) -> Vec<String> {
+        let mut result = Vec::new();
+        if let Some(toolchain) = toolchain {
+            result.push(format!("+{}", toolchain));
+        }
+        result.extend(self.global_args());
+        result.push(subcommand.into());
+        result
+    }
+
+    fn package_selection_args(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        if self.workspace {
+            result.push("--workspace".into());
+        }
+        for package in &self.packages {
+            result.extend(["-p".into(), package.clone()]);
+        }
+        for package in &self.excluded_packages {
+            result.extend(["--exclude".into(), package.clone()]);
+        }
+        result
+    }
+
+    fn feature_selection_args(&self) -> Result<Vec<String>, Error> {
+        let mut result = Vec::new();
+        if self.all_features {
+            if !self.features.is_empty() {
+                return Err(Error::ConflictingFeatureSelection);
+            }
+            result.push("--all-features".into());
+        }
+        if self.no_default_features {
+            result.push("--no-default-features".into());
+        }
+        if !self.features.is_empty() {
+            result.extend(["--features".into(), self.features.join(",")]);
+        }
+        Ok(result)
+    }
 
+    pub async fn get_installed(&self) -> Result<Vec<String>, Error> {
         // This was added to help remove non-Rustup installed cargo-fmt and rustfmt on
         // the GitHub runners. However the binaries do not appear to be
         // cargo-managed either.
@@ -94,6 +348,51 @@ impl Cargo {
         Ok(installs)
     }
 
+    /// Runs `cargo metadata` without resolving dependencies and returns the
+    /// JSON output
+    async fn read_metadata(&self, toolchain: Option<&str>, manifest_path: Option<&str>) -> Result<String, Error> {
+        use crate::actions::exec::Stdio;
+
+        let output: Arc<Mutex<String>> = Arc::default();
+        let output_captured = output.clone();
+        let mut command = Command::from(&self.path);
+        if let Some(toolchain) = toolchain {
+            command.arg(format!("+{}", toolchain));
+        }
+        command
+            .args(self.global_args())
+            .args(["metadata", "--format-version", "1", "--no-deps"]);
+        if let Some(manifest_path) = manifest_path {
+            command.args(["--manifest-path", manifest_path]);
+        }
+        command
+            .outline(move |line| {
+                let mut out = output_captured.lock();
+                *out += line;
+                *out += "\n";
+            })
+            .stdout(Stdio::null())
+            .exec()
+            .await
+            .map_err(Error::Js)?;
+        let output = output.lock().clone();
+        Ok(output)
+    }
+
+    /// Returns the output of `cargo metadata` for the workspace in the current
+    /// directory, without resolving dependencies. This includes the workspace
+    /// root, target directory and workspace packages. The result is cached
+    /// after the first call.
+    pub async fn metadata(&mut self) -> Result<cargo_metadata::Metadata, Error> {
+        if let Some(metadata) = &self.metadata {
+            return Ok(metadata.clone());
+        }
+        let metadata = self.read_metadata(None, None).await?;
+        let metadata = parse_metadata(&metadata)?;
+        self.metadata = Some(metadata.clone());
+        Ok(metadata)
+    }
+
     async fn get_hooks_for_subcommand(
         &self,
         toolchain: Option<&str>,
@@ -112,7 +411,27 @@ impl Cargo {
                     true
                 };
                 if enabled {
-                    hooks.push(AnnotationHook::new(subcommand));
+                    let mut hook = AnnotationHook::new(subcommand);
+                    hook.limit(
+                        parse_optional_input(input_manager, Input::AnnotationLimit)?
+                            .unwrap_or(DEFAULT_ANNOTATION_LIMIT),
+                    );
+                    if !parse_optional_input(input_manager, Input::AnnotateDependencies)?.unwrap_or(false) {
+                        let member_ids = self
+                            .read_metadata(toolchain, find_manifest_path(args))
+                            .await
+                            .and_then(|metadata| parse_workspace_member_ids(&metadata));
+                        match member_ids {
+                            Ok(member_ids) => {
+                                hook.only_packages(member_ids);
+                            }
+                            Err(e) => warning!(
+                                "Unable to determine workspace members so dependencies will also be annotated: {}",
+                                e
+                            ),
+                        }
+                    }
+                    hooks.push(hook);
                 }
             }
             "install" => {
@@ -132,14 +451,12 @@ impl Cargo {
         Ok(hooks)
     }
 
-    async fn get_toolchain_version(
+    pub async fn get_toolchain_version(
         &self,
         toolchain: Option<&str>,
         cwd: Option<&Path>,
     ) -> Result<ToolchainVersion, Error> {
         use crate::actions::exec::Stdio;
-        use parking_lot::Mutex;
-        use std::sync::Arc;
 
         let rustc_path = io::which("rustc", true).await.map_err(Error::Js)?;
         let mut command = Command::from(&rustc_path);
@@ -175,25 +492,611 @@ impl Cargo {
         I: IntoIterator<Item = &'a str>,
     {
         let args: Vec<String> = args.into_iter().map(Into::into).collect();
-        let mut final_args = Vec::with_capacity(args.len());
-        if let Some(toolchain) = toolchain {
-            final_args.push(format!("+{}", toolchain));
-        }
-        let mut hooks = self
+        let hooks = self
             .get_hooks_for_subcommand(toolchain, subcommand, &args[..], input_manager)
             .await?;
-        final_args.push(subcommand.into());
-        final_args.extend(hooks.additional_cargo_options().into_iter().map(Cow::into_owned));
-        final_args.extend(args);
+        self.execute(toolchain, subcommand, hooks, args).await?;
+        if subcommand == "install" {
+            // A freshly installed binary may shadow a previously located one
+            io::invalidate_which_cache();
+        }
+        Ok(())
+    }
+
+    async fn execute(
+        &self,
+        toolchain: Option<&str>,
+        subcommand: &str,
+        mut hooks: CompositeHook,
+        args: Vec<String>,
+    ) -> Result<(), Error> {
         let mut command = Command::from(&self.path);
-        command.args(final_args);
+        command
+            .args(self.leading_args(toolchain, subcommand))
+            .args(hooks.additional_cargo_options().iter().map(AsRef::<str>::as_ref))
+            .args(self.package_selection_args())
+            .args(self.feature_selection_args()?)
+            .args(args);
         hooks.modify_command(&mut command);
-        if let Err(e) = command.exec().await.map_err(Error::Js) {
-            hooks.failed().await;
-            Err(e)
-        } else {
-            hooks.succeeded().await;
-            Ok(())
+        match command.exec_timed().await.map_err(Error::Js) {
+            Err(e) => {
+                hooks.failed().await;
+                Err(e)
+            }
+            Ok(result) => {
+                info!("cargo {} took {:.1}s", subcommand, result.duration.as_secs_f64());
+                hooks.succeeded().await;
+                Ok(())
+            }
         }
     }
 }
+
+impl Cargo {
+    /// Applies the inputs that select Cargo's global flags, packages and
+    /// features. Each `config` value is on its own line since values may
+    /// contain spaces or commas.
+    pub fn apply_inputs(&mut self, input_manager: &input_manager::Manager) -> Result<&mut Cargo, Error> {
+        self.locked(parse_optional_input(input_manager, Input::Locked)?.unwrap_or(false))
+            .offline(parse_optional_input(input_manager, Input::Offline)?.unwrap_or(false))
+            .frozen(parse_optional_input(input_manager, Input::Frozen)?.unwrap_or(false))
+            .workspace(parse_optional_input(input_manager, Input::Workspace)?.unwrap_or(false))
+            .all_features(parse_optional_input(input_manager, Input::AllFeatures)?.unwrap_or(false))
+            .no_default_features(parse_optional_input(input_manager, Input::NoDefaultFeatures)?.unwrap_or(false));
+        if let Some(packages) = input_manager.get_list(Input::Package) {
+            self.packages(&packages);
+        }
+        if let Some(packages) = input_manager.get_list(Input::Exclude) {
+            self.exclude_packages(&packages);
+        }
+        if let Some(features) = input_manager.get_list(Input::Features) {
+            self.features(&features);
+        }
+        if let Some(configs) = input_manager.get(Input::Config) {
+            for config in configs.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                self.config(config)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Requires `Cargo.lock` to be up to date (`--locked`)
+    pub fn locked(&mut self, enabled: bool) -> &mut Cargo {
+        self.locked = enabled;
+        self
+    }
+
+    /// Overrides a Cargo configuration value (`--config`). The argument must
+    /// either be a `key=value` pair in TOML syntax or the path of a TOML
+    /// configuration file. May be specified more than once.
+    pub fn config(&mut self, key_value: &str) -> Result<&mut Cargo, Error> {
+        if !is_valid_config_arg(key_value) {
+            return Err(Error::InvalidCargoConfig(key_value.to_string()));
+        }
+        self.config.push(key_value.to_string());
+        Ok(self)
+    }
+
+    /// Runs `cargo clean` with the specified arguments
+    pub async fn clean(&mut self, args: &[&str]) -> Result<(), Error> {
+        Command::from(&self.path)
+            .args(self.clean_args(args))
+            .exec()
+            .await
+            .map_err(Error::Js)?;
+        Ok(())
+    }
+
+    fn clean_args(&self, args: &[&str]) -> Vec<String> {
+        let mut result = self.leading_args(None, "clean");
+        result.extend(args.iter().map(ToString::to_string));
+        result
+    }
+
+    /// Prevents Cargo from accessing the network (`--offline`)
+    pub fn offline(&mut self, enabled: bool) -> &mut Cargo {
+        self.offline = enabled;
+        self
+    }
+
+    /// Equivalent to both `locked` and `offline` (`--frozen`)
+    pub fn frozen(&mut self, enabled: bool) -> &mut Cargo {
+        self.frozen = enabled;
+        self
+    }
+
+    /// Selects all packages in the workspace (`--workspace`)
+    pub fn workspace(&mut self, enabled: bool) -> &mut Cargo {
+        self.workspace = enabled;
+        self
+    }
+
+    /// Selects the specified packages (`-p`)
+    pub fn packages(&mut self, names: &[&str]) -> &mut Cargo {
+        self.packages.extend(names.iter().map(ToString::to_string));
+        self
+    }
+
+    /// Excludes the specified packages when the whole workspace is selected
+    /// (`--exclude`)
+    pub fn exclude_packages(&mut self, names: &[&str]) -> &mut Cargo {
+        self.excluded_packages.extend(names.iter().map(ToString::to_string));
+        self
+    }
+
+    /// Enables the specified features (`--features`)
+    pub fn features(&mut self, features: &[&str]) -> &mut Cargo {
+        self.features.extend(features.iter().map(ToString::to_string));
+        self
+    }
+
+    /// Enables all features of the selected packages (`--all-features`). This
+    /// cannot be combined with explicitly listed features.
+    pub fn all_features(&mut self, enabled: bool) -> &mut Cargo {
+        self.all_features = enabled;
+        self
+    }
+
+    /// Disables the default features of the selected packages
+    /// (`--no-default-features`)
+    pub fn no_default_features(&mut self, enabled: bool) -> &mut Cargo {
+        self.no_default_features = enabled;
+        self
+    }
+
+    /// Installs a crate with `cargo install`, unless a binary with the same
+    /// name as the crate is already on the path. If a version is specified,
+    /// the existing binary must also report that version when run with
+    /// `--version`. The build artifacts are cached as for any other install.
+    pub async fn install(
+        &mut self,
+        crate_name: &str,
+        version: Option<&str>,
+        locked: bool,
+        input_manager: &input_manager::Manager,
+    ) -> Result<(), Error> {
+        if self.is_tool_installed(crate_name, version).await? {
+            info!("{} is already installed, skipping installation", crate_name);
+            return Ok(());
+        }
+        let args = self.install_args(crate_name, version, locked);
+        self.run(None, "install", args.iter().map(String::as_str), input_manager)
+            .await
+    }
+
+    /// The arguments following `install`. `--locked` is omitted if it is
+    /// already implied by the global flags.
+    fn install_args(&self, crate_name: &str, version: Option<&str>, locked: bool) -> Vec<String> {
+        let mut args = vec![crate_name.to_string()];
+        if let Some(version) = version {
+            args.extend(["--version".to_string(), version.to_string()]);
+        }
+        if locked && !self.locked && !self.frozen {
+            args.push("--locked".to_string());
+        }
+        args
+    }
+
+    async fn is_tool_installed(&self, name: &str, version: Option<&str>) -> Result<bool, Error> {
+        use crate::actions::exec::Stdio;
+
+        let path = match io::which_optional(name).await? {
+            Some(path) => path,
+            None => return Ok(false),
+        };
+        let version = match version {
+            Some(version) => version,
+            None => return Ok(true),
+        };
+        let output: Arc<Mutex<String>> = Arc::default();
+        let output_captured = output.clone();
+        let result = Command::from(&path)
+            .arg("--version")
+            .outline(move |line| {
+                let mut out = output_captured.lock();
+                *out += line;
+                *out += "\n";
+            })
+            .stdout(Stdio::null())
+            .exec()
+            .await;
+        let output = output.lock();
+        Ok(result.is_ok() && version_output_matches(&output, version))
+    }
+
+    /// Runs `cargo test` and summarises the results. Output from the tests is
+    /// forwarded to the log. An error is returned if compilation failed before
+    /// any tests ran, or if Cargo failed without the failure being reflected
+    /// as a failed test.
+    pub async fn run_tests<'a, I>(
+        &mut self,
+        toolchain: Option<&str>,
+        args: I,
+        input_manager: &input_manager::Manager,
+    ) -> Result<TestSummary, Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let args: Vec<String> = args.into_iter().map(Into::into).collect();
+        let summary: Arc<Mutex<TestSummary>> = Arc::default();
+        let mut hooks = self
+            .get_hooks_for_subcommand(toolchain, "test", &args[..], input_manager)
+            .await?;
+        hooks.push(TestSummaryHook {
+            summary: summary.clone(),
+        });
+        let result = self.execute(toolchain, "test", hooks, args).await;
+        let summary = summary.lock().clone();
+        match result {
+            Err(e) if summary.compile_failed() => {
+                error!("Compilation failed before any tests were run");
+                Err(e)
+            }
+            Err(e) if summary.failed == 0 => Err(e),
+            _ => Ok(summary),
+        }
+    }
+
+    /// Builds the current package for a WebAssembly target, installing the
+    /// target first if necessary, and returns the WebAssembly files produced.
+    /// The target defaults to `wasm32-unknown-unknown`.
+    pub async fn build_wasm(&mut self, target: Option<&str>, release: bool) -> Result<Vec<Path>, Error> {
+        use crate::rustup::Rustup;
+
+        let target = target.unwrap_or(DEFAULT_WASM_TARGET);
+        let rustup = Rustup::get().await?;
+        rustup.add_target(target).await?;
+        let artifacts: Arc<Mutex<Vec<String>>> = Arc::default();
+        let mut hooks = CompositeHook::default();
+        let mut annotation_hook = AnnotationHook::new("build");
+        annotation_hook.limit(DEFAULT_ANNOTATION_LIMIT);
+        hooks.push(annotation_hook);
+        hooks.push(WasmArtifactHook {
+            artifacts: artifacts.clone(),
+        });
+        self.execute(None, "build", hooks, build_wasm_args(target, release))
+            .await?;
+        let artifacts = artifacts.lock().iter().map(Path::from).collect();
+        Ok(artifacts)
+    }
+
+    /// Runs `cargo build` and returns the WebAssembly files it produced.
+    /// Stale files left in the target directory by earlier builds are not
+    /// included.
+    pub async fn run_build<'a, I>(
+        &mut self,
+        toolchain: Option<&str>,
+        args: I,
+        input_manager: &input_manager::Manager,
+    ) -> Result<Vec<Path>, Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let args: Vec<String> = args.into_iter().map(Into::into).collect();
+        let artifacts: Arc<Mutex<Vec<String>>> = Arc::default();
+        let mut hooks = self
+            .get_hooks_for_subcommand(toolchain, "build", &args[..], input_manager)
+            .await?;
+        hooks.push(WasmArtifactHook {
+            artifacts: artifacts.clone(),
+        });
+        self.execute(toolchain, "build", hooks, args).await?;
+        let artifacts = artifacts.lock().iter().map(Path::from).collect();
+        Ok(artifacts)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        build_wasm_args, find_manifest_path, parse_metadata, parse_wasm_build_args, parse_workspace_member_ids,
+        version_output_matches, wasm_artifact_files, Cargo, DEFAULT_WASM_TARGET,
+    };
+    use crate::node::path::Path;
+    use crate::Error;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn package_selection_args() {
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        assert!(cargo.package_selection_args().is_empty());
+        cargo.packages(&["foo", "bar"]);
+        assert_eq!(cargo.package_selection_args(), ["-p", "foo", "-p", "bar"]);
+
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        cargo.workspace(true).exclude_packages(&["baz"]);
+        assert_eq!(cargo.package_selection_args(), ["--workspace", "--exclude", "baz"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn feature_selection_args() {
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        assert!(cargo.feature_selection_args().unwrap().is_empty());
+        cargo.features(&["foo", "bar"]);
+        assert_eq!(cargo.feature_selection_args().unwrap(), ["--features", "foo,bar"]);
+        cargo.no_default_features(true);
+        assert_eq!(
+            cargo.feature_selection_args().unwrap(),
+            ["--no-default-features", "--features", "foo,bar"]
+        );
+
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        cargo.all_features(true);
+        assert_eq!(cargo.feature_selection_args().unwrap(), ["--all-features"]);
+        cargo.features(&["foo"]);
+        assert!(matches!(
+            cargo.feature_selection_args(),
+            Err(Error::ConflictingFeatureSelection)
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn global_args() {
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        assert!(cargo.global_args().is_empty());
+        cargo.locked(true);
+        assert_eq!(cargo.global_args(), ["--locked"]);
+        cargo.offline(true);
+        assert_eq!(cargo.global_args(), ["--locked", "--offline"]);
+        cargo.frozen(true);
+        assert_eq!(cargo.global_args(), ["--frozen"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn options_from_inputs() {
+        use crate::input_manager::{Input, Manager as InputManager};
+
+        let input_manager = InputManager::from_inputs([
+            (Input::Locked, "true"),
+            (Input::Offline, "false"),
+            (Input::Workspace, "true"),
+            (Input::Exclude, "baz qux"),
+            (Input::Features, "foo,bar"),
+            (Input::NoDefaultFeatures, "true"),
+            (
+                Input::Config,
+                "profile.release.opt-level=3\n\nbuild.rustflags = [\"-C\", \"debuginfo=0\"]\n",
+            ),
+        ]);
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        cargo.apply_inputs(&input_manager).unwrap();
+        assert_eq!(
+            cargo.leading_args(None, "build"),
+            [
+                "--locked",
+                "--config",
+                "profile.release.opt-level=3",
+                "--config",
+                "build.rustflags = [\"-C\", \"debuginfo=0\"]",
+                "build"
+            ]
+        );
+        assert_eq!(
+            cargo.package_selection_args(),
+            ["--workspace", "--exclude", "baz", "--exclude", "qux"]
+        );
+        assert_eq!(
+            cargo.feature_selection_args().unwrap(),
+            ["--no-default-features", "--features", "foo,bar"]
+        );
+        assert!(input_manager.unused().is_empty());
+
+        let input_manager = InputManager::from_inputs([(Input::Frozen, "yes")]);
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        assert!(matches!(
+            cargo.apply_inputs(&input_manager),
+            Err(Error::OptionParseError(input, value)) if input == "frozen" && value == "yes"
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn clean_arguments() {
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        assert_eq!(cargo.clean_args(&["-p", "old-dep"]), ["clean", "-p", "old-dep"]);
+        cargo.offline(true);
+        assert_eq!(
+            cargo.clean_args(&["--doc", "--release"]),
+            ["--offline", "clean", "--doc", "--release"]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn config_args() {
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        cargo
+            .config("profile.release.opt-level=3")
+            .unwrap()
+            .config("registries.crates-io.protocol=\"sparse\"")
+            .unwrap()
+            .config("ci/cargo.toml")
+            .unwrap()
+            .locked(true);
+        assert_eq!(
+            cargo.leading_args(Some("nightly"), "build"),
+            [
+                "+nightly",
+                "--locked",
+                "--config",
+                "profile.release.opt-level=3",
+                "--config",
+                "registries.crates-io.protocol=\"sparse\"",
+                "--config",
+                "ci/cargo.toml",
+                "build"
+            ]
+        );
+        for invalid in ["opt-level", "=3", "ci/cargo.json"] {
+            assert!(matches!(cargo.config(invalid), Err(Error::InvalidCargoConfig(value)) if value == invalid));
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn workspace_members_from_metadata() {
+        let metadata = r#"{
+            "packages": [
+                {"name": "foo", "id": "foo 0.1.0 (path+file:///ws/foo)", "version": "0.1.0"},
+                {"name": "bar", "id": "bar 0.2.0 (path+file:///ws/bar)", "version": "0.2.0"}
+            ],
+            "workspace_members": ["bar 0.2.0 (path+file:///ws/bar)", "foo 0.1.0 (path+file:///ws/foo)"],
+            "version": 1
+        }"#;
+        let ids = parse_workspace_member_ids(metadata).expect("Failed to parse metadata");
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains("foo 0.1.0 (path+file:///ws/foo)"));
+    }
+
+    const METADATA_FIXTURE: &str = r#"{
+        "packages": [
+            {
+                "name": "foo",
+                "version": "0.1.0",
+                "id": "foo 0.1.0 (path+file:///ws/foo)",
+                "license": null,
+                "license_file": null,
+                "description": null,
+                "source": null,
+                "dependencies": [],
+                "targets": [
+                    {
+                        "kind": ["lib"],
+                        "crate_types": ["lib"],
+                        "name": "foo",
+                        "src_path": "/ws/foo/src/lib.rs",
+                        "edition": "2021",
+                        "doc": true,
+                        "doctest": true,
+                        "test": true
+                    }
+                ],
+                "features": {},
+                "manifest_path": "/ws/foo/Cargo.toml",
+                "metadata": null,
+                "publish": null,
+                "authors": [],
+                "categories": [],
+                "keywords": [],
+                "readme": null,
+                "repository": null,
+                "homepage": null,
+                "documentation": null,
+                "edition": "2021",
+                "links": null,
+                "default_run": null,
+                "rust_version": null
+            }
+        ],
+        "workspace_members": ["foo 0.1.0 (path+file:///ws/foo)"],
+        "workspace_default_members": ["foo 0.1.0 (path+file:///ws/foo)"],
+        "resolve": null,
+        "target_directory": "/ws/target",
+        "version": 1,
+        "workspace_root": "/ws",
+        "metadata": null
+    }"#;
+
+    #[wasm_bindgen_test]
+    fn metadata_from_fixture() {
+        let metadata = parse_metadata(METADATA_FIXTURE).expect("Failed to parse metadata");
+        assert_eq!(metadata.workspace_root.as_str(), "/ws");
+        assert_eq!(metadata.target_directory.as_str(), "/ws/target");
+        assert_eq!(metadata.packages.len(), 1);
+        assert_eq!(metadata.packages[0].name, "foo");
+        assert_eq!(metadata.workspace_members[0].repr, "foo 0.1.0 (path+file:///ws/foo)");
+    }
+
+    #[wasm_bindgen_test]
+    fn manifest_path_from_args() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(ToString::to_string).collect() };
+        assert_eq!(find_manifest_path(&args(&["--release"])), None);
+        assert_eq!(
+            find_manifest_path(&args(&["--manifest-path", "ws/Cargo.toml", "--release"])),
+            Some("ws/Cargo.toml")
+        );
+        assert_eq!(
+            find_manifest_path(&args(&["--manifest-path=ws/Cargo.toml"])),
+            Some("ws/Cargo.toml")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn install_arguments() {
+        let mut cargo = Cargo::with_path(Path::from("cargo"));
+        assert_eq!(cargo.install_args("cross", None, false), ["cross"]);
+        assert_eq!(cargo.install_args("cross", None, true), ["cross", "--locked"]);
+        assert_eq!(
+            cargo.install_args("cross", Some("0.2.5"), false),
+            ["cross", "--version", "0.2.5"]
+        );
+        assert_eq!(
+            cargo.install_args("cross", Some("0.2.5"), true),
+            ["cross", "--version", "0.2.5", "--locked"]
+        );
+        cargo.locked(true);
+        assert_eq!(cargo.install_args("cross", None, true), ["cross"]);
+        cargo.locked(false).frozen(true);
+        assert_eq!(cargo.install_args("cross", None, true), ["cross"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn wasm_build_arguments() {
+        assert_eq!(
+            build_wasm_args(DEFAULT_WASM_TARGET, false),
+            ["--target", "wasm32-unknown-unknown"]
+        );
+        assert_eq!(
+            build_wasm_args("wasm32-wasi", true),
+            ["--target", "wasm32-wasi", "--release"]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn wasm_build_detection() {
+        fn parse(args: &[&str]) -> Option<(String, bool)> {
+            let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+            parse_wasm_build_args(&args).map(|(target, release)| (target.to_string(), release))
+        }
+
+        let wasm = "wasm32-unknown-unknown".to_string();
+        assert_eq!(
+            parse(&["--target", "wasm32-unknown-unknown"]),
+            Some((wasm.clone(), false))
+        );
+        assert_eq!(
+            parse(&["--release", "--target=wasm32-unknown-unknown"]),
+            Some((wasm.clone(), true))
+        );
+        assert_eq!(parse(&["-r", "--target", "wasm32-unknown-unknown"]), Some((wasm, true)));
+        assert_eq!(parse(&["--target", "x86_64-unknown-linux-gnu"]), None);
+        assert_eq!(parse(&["--target", "wasm32-unknown-unknown", "--bin", "app"]), None);
+        assert_eq!(
+            parse(&["--target", "wasm32-unknown-unknown", "--target", "wasm32-wasi"]),
+            None
+        );
+        assert_eq!(parse(&["--target"]), None);
+        assert_eq!(parse(&["--release"]), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn installed_version_detection() {
+        assert!(version_output_matches("cross 0.2.5\n", "0.2.5"));
+        assert!(version_output_matches(
+            "cargo-nextest v0.9.59 (abc123 2023-10-01)",
+            "=0.9.59"
+        ));
+        assert!(!version_output_matches("cross 0.2.50", "0.2.5"));
+        assert!(version_output_matches("cross 0.2.7", "^0.2"));
+        assert!(!version_output_matches("cross (unknown version)", "0.2.5"));
+    }
+
+    #[wasm_bindgen_test]
+    fn wasm_artifacts_from_build_output() {
+        let artifact = r#"{"reason":"compiler-artifact","package_id":"path+file:///ws#app@0.1.0","manifest_path":"/ws/Cargo.toml","target":{"kind":["cdylib","rlib"],"crate_types":["cdylib","rlib"],"name":"app","src_path":"/ws/src/lib.rs","edition":"2021","doc":true,"doctest":true,"test":true},"profile":{"opt_level":"3","debuginfo":0,"debug_assertions":false,"overflow_checks":false,"test":false},"features":[],"filenames":["/ws/target/wasm32-unknown-unknown/release/app.wasm","/ws/target/wasm32-unknown-unknown/release/libapp.rlib"],"executable":null,"fresh":false}"#;
+        assert_eq!(
+            wasm_artifact_files(artifact),
+            ["/ws/target/wasm32-unknown-unknown/release/app.wasm"]
+        );
+        let finished = r#"{"reason":"build-finished","success":true}"#;
+        assert!(wasm_artifact_files(finished).is_empty());
+        assert!(wasm_artifact_files("not json").is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+use crate::dir_tree::find_up;
+use crate::node::path::Path;
+use crate::{node, Error};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Locations of the project configuration file relative to each folder
+/// searched, in order of precedence. Cargo prefers the legacy name if both
+/// files exist.
+const PROJECT_FILE_NAMES: [&str; 2] = [".cargo/config", ".cargo/config.toml"];
+
+/// Names of the configuration file in the Cargo home, in order of precedence
+const HOME_FILE_NAMES: [&str; 2] = ["config", "config.toml"];
+
+#[derive(Debug, Clone, Error)]
+pub enum ParseError {
+    #[error("Cargo configuration file {0} is not valid TOML: {1}")]
+    Toml(String, String),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TomlFile {
+    build: TomlBuild,
+    registries: BTreeMap<String, TomlRegistry>,
+    source: BTreeMap<String, TomlSource>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct TomlBuild {
+    target_dir: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TomlRegistry {
+    index: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct TomlSource {
+    replace_with: Option<String>,
+}
+
+/// The parts of Cargo's configuration which affect where it stores files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CargoConfig {
+    /// The `build.target-dir` setting, resolved to an absolute path
+    pub target_dir: Option<Path>,
+
+    /// Index URLs of alternate registries, keyed by registry name
+    pub registries: BTreeMap<String, String>,
+
+    /// Source replacements, from the name of the replaced source to the name
+    /// of its replacement
+    pub source_replacements: BTreeMap<String, String>,
+}
+
+impl CargoConfig {
+    /// Parses a configuration file. Relative paths are resolved against
+    /// `base`, which is the folder containing the `.cargo` folder the file was
+    /// found in.
+    pub fn parse(content: &str, base: &Path, file_name: &str) -> Result<CargoConfig, ParseError> {
+        let parsed: TomlFile =
+            basic_toml::from_str(content).map_err(|e| ParseError::Toml(file_name.to_string(), e.to_string()))?;
+        let registries = parsed
+            .registries
+            .into_iter()
+            .filter_map(|(name, registry)| registry.index.map(|index| (name, index)))
+            .collect();
+        let source_replacements = parsed
+            .source
+            .into_iter()
+            .filter_map(|(name, source)| source.replace_with.map(|replacement| (name, replacement)))
+            .collect();
+        Ok(CargoConfig {
+            target_dir: parsed.build.target_dir.map(|dir| base.join(dir.as_str())),
+            registries,
+            source_replacements,
+        })
+    }
+
+    /// Adds settings from a configuration file of lower precedence
+    pub fn merge(&mut self, lower: CargoConfig) {
+        if self.target_dir.is_none() {
+            self.target_dir = lower.target_dir;
+        }
+        for (name, index) in lower.registries {
+            self.registries.entry(name).or_insert(index);
+        }
+        for (name, replacement) in lower.source_replacements {
+            self.source_replacements.entry(name).or_insert(replacement);
+        }
+    }
+
+    /// A description of the configured registries and source replacements,
+    /// which is empty if neither are configured
+    pub fn registry_key_data(&self) -> String {
+        use std::fmt::Write as _;
+
+        let mut result = String::new();
+        for (name, index) in &self.registries {
+            writeln!(result, "registry {}={}", name, index).expect("Unable to write to string");
+        }
+        for (name, replacement) in &self.source_replacements {
+            writeln!(result, "source {}={}", name, replacement).expect("Unable to write to string");
+        }
+        result
+    }
+}
+
+async fn parse_file(path: &Path, base: &Path) -> Result<CargoConfig, Error> {
+    let content = node::fs::read_file(path).await?;
+    let content = String::from_utf8_lossy(&content);
+    Ok(CargoConfig::parse(&content, base, &path.to_string())?)
+}
+
+/// Reads the project configuration file that applies to the specified folder,
+/// merged with the configuration file in the Cargo home. Configuration files
+/// in folders other than the nearest are not read.
+pub async fn load(folder: &Path, cargo_home: &Path) -> Result<CargoConfig, Error> {
+    let mut config = CargoConfig::default();
+    if let Some(path) = find_up(folder, &PROJECT_FILE_NAMES).await {
+        // The base is the folder containing `.cargo`
+        let base = path.parent().parent();
+        config = parse_file(&path, &base).await?;
+    }
+    for name in HOME_FILE_NAMES {
+        let path = cargo_home.join(name);
+        if path.exists().await {
+            config.merge(parse_file(&path, &cargo_home.parent()).await?);
+            break;
+        }
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{load, CargoConfig, ParseError};
+    use crate::node;
+    use crate::node::path::Path;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const PROJECT_CONFIG: &str = r#"
+[build]
+target-dir = "build/target"
+
+[registries.my-registry]
+index = "sparse+https://registry.example.com/index/"
+
+[source.crates-io]
+replace-with = "vendored"
+
+[source.vendored]
+directory = "vendor"
+"#;
+
+    const HOME_CONFIG: &str = r#"
+[build]
+target-dir = "/shared/target"
+jobs = 4
+
+[registries.my-registry]
+index = "https://ignored.example.com/index"
+
+[registries.other]
+index = "https://other.example.com/index"
+"#;
+
+    #[wasm_bindgen_test]
+    fn parse_project_config() {
+        let config = CargoConfig::parse(PROJECT_CONFIG, &Path::from("/ws"), "config.toml").unwrap();
+        assert_eq!(config.target_dir, Some(Path::from("/ws/build/target")));
+        assert_eq!(
+            config.registries.get("my-registry").map(String::as_str),
+            Some("sparse+https://registry.example.com/index/")
+        );
+        assert_eq!(
+            config.source_replacements.get("crates-io").map(String::as_str),
+            Some("vendored")
+        );
+        assert!(!config.source_replacements.contains_key("vendored"));
+    }
+
+    #[wasm_bindgen_test]
+    fn merge_with_home_config() {
+        let mut config = CargoConfig::parse(PROJECT_CONFIG, &Path::from("/ws"), "config.toml").unwrap();
+        config.merge(CargoConfig::parse(HOME_CONFIG, &Path::from("/home/runner"), "config.toml").unwrap());
+        assert_eq!(config.target_dir, Some(Path::from("/ws/build/target")));
+        assert_eq!(config.registries.len(), 2);
+        assert_eq!(
+            config.registries.get("my-registry").map(String::as_str),
+            Some("sparse+https://registry.example.com/index/")
+        );
+        assert_eq!(
+            config.registry_key_data(),
+            concat!(
+                "registry my-registry=sparse+https://registry.example.com/index/\n",
+                "registry other=https://other.example.com/index\n",
+                "source crates-io=vendored\n",
+            )
+        );
+
+        let mut config = CargoConfig::default();
+        assert!(config.registry_key_data().is_empty());
+        config.merge(CargoConfig::parse(HOME_CONFIG, &Path::from("/home/runner"), "config.toml").unwrap());
+        assert_eq!(config.target_dir, Some(Path::from("/shared/target")));
+    }
+
+    #[wasm_bindgen_test]
+    async fn legacy_file_name_preferred() -> Result<(), crate::Error> {
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-cargo-config-")).await?;
+        let project = temp_dir.path().join("ws");
+        let cargo_home = temp_dir.path().join("cargo-home");
+        node::fs::create_dir_all(&project.join(".cargo")).await?;
+        node::fs::create_dir_all(&cargo_home).await?;
+        node::fs::write_file(
+            &project.join(".cargo").join("config"),
+            b"[build]\ntarget-dir = \"legacy\"\n",
+        )
+        .await?;
+        node::fs::write_file(
+            &project.join(".cargo").join("config.toml"),
+            b"[build]\ntarget-dir = \"current\"\n",
+        )
+        .await?;
+        node::fs::write_file(
+            &cargo_home.join("config"),
+            b"[registries.legacy]\nindex = \"https://legacy.example.com/index\"\n",
+        )
+        .await?;
+        node::fs::write_file(
+            &cargo_home.join("config.toml"),
+            b"[registries.current]\nindex = \"https://current.example.com/index\"\n",
+        )
+        .await?;
+        let config = load(&project, &cargo_home).await?;
+        assert_eq!(config.target_dir, Some(project.join("legacy")));
+        assert_eq!(config.registries.keys().collect::<Vec<_>>(), ["legacy"]);
+        temp_dir.close().await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    fn invalid_config() {
+        let result = CargoConfig::parse("[build", &Path::from("/ws"), ".cargo/config.toml");
+        assert!(matches!(result, Err(ParseError::Toml(file, _)) if file == ".cargo/config.toml"));
+    }
+}
@@ -4,21 +4,124 @@ use crate::actions::exec::Command;
 use crate::warning;
 use async_trait::async_trait;
 use cargo_metadata::diagnostic::{DiagnosticLevel, DiagnosticSpan};
+use cargo_metadata::PackageId;
+use parking_lot::Mutex;
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// An annotation built from a compiler message, along with the key used to
+/// detect repeats of the same diagnostic
+struct PendingAnnotation {
+    key: String,
+    level: AnnotationLevel,
+    annotation: crate::actions::core::Annotation,
+}
+
+/// The default maximum number of annotations output for a Cargo invocation.
+/// GitHub discards annotations beyond 10 per step.
+pub const DEFAULT_ANNOTATION_LIMIT: usize = 10;
+
+/// Tracks the annotations output during a single Cargo invocation
+#[derive(Debug)]
+struct Emitter {
+    seen: HashSet<String>,
+    limit: usize,
+    emitted: usize,
+    suppressed: usize,
+}
+
+impl Default for Emitter {
+    fn default() -> Emitter {
+        Emitter::new(DEFAULT_ANNOTATION_LIMIT)
+    }
+}
+
+impl Emitter {
+    fn new(limit: usize) -> Emitter {
+        Emitter {
+            seen: HashSet::new(),
+            limit,
+            emitted: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// Returns whether the annotation with the specified key should be output.
+    /// Repeats of an earlier annotation are always skipped since the same
+    /// diagnostic can be emitted for multiple targets of a crate. One
+    /// annotation of the limit is reserved for the summary, since whether it
+    /// is needed is only known once Cargo has finished.
+    fn accept(&mut self, key: String) -> bool {
+        if !self.seen.insert(key) {
+            false
+        } else if self.emitted + 1 < self.limit {
+            self.emitted += 1;
+            true
+        } else {
+            self.suppressed += 1;
+            false
+        }
+    }
+
+    fn summary(&self) -> Option<String> {
+        (self.suppressed > 0 && self.limit > 0).then(|| {
+            format!(
+                "{} more diagnostics were not annotated as the limit of {} was reached",
+                self.suppressed, self.limit
+            )
+        })
+    }
+}
 
 #[derive(Default)]
 pub struct Annotation {
     subcommand: String,
+    workspace_members: Option<Arc<HashSet<String>>>,
+    emitter: Arc<Mutex<Emitter>>,
 }
 
 impl Annotation {
     pub fn new(subcommand: &str) -> Annotation {
         Annotation {
             subcommand: subcommand.to_string(),
+            workspace_members: None,
+            emitter: Arc::default(),
         }
     }
 
-    fn process_json_record(cargo_subcommand: &str, line: &str) {
+    /// Sets the maximum number of distinct annotations to output
+    pub fn limit(&mut self, limit: usize) -> &mut Annotation {
+        self.emitter = Arc::new(Mutex::new(Emitter::new(limit)));
+        self
+    }
+
+    fn output_summary(&self) {
+        use crate::actions::core::Annotation;
+
+        if let Some(summary) = self.emitter.lock().summary() {
+            let mut annotation = Annotation::from(summary.as_str());
+            annotation.title(&format!("cargo-{}", self.subcommand));
+            annotation.warning();
+        }
+    }
+
+    /// Restricts annotations to messages from the packages with the specified
+    /// IDs
+    pub fn only_packages(&mut self, package_ids: HashSet<String>) -> &mut Annotation {
+        self.workspace_members = Some(Arc::new(package_ids));
+        self
+    }
+
+    fn should_annotate(workspace_members: Option<&HashSet<String>>, package_id: &PackageId) -> bool {
+        workspace_members.map_or(true, |members| members.contains(&package_id.repr))
+    }
+
+    fn parse_json_record(
+        cargo_subcommand: &str,
+        workspace_members: Option<&HashSet<String>>,
+        line: &str,
+    ) -> Option<PendingAnnotation> {
         use crate::actions::core::Annotation;
         use crate::node::path::Path;
         use cargo_metadata::Message;
@@ -26,39 +129,51 @@ impl Annotation {
         // Ignore blank lines
         let line = line.trim();
         if line.is_empty() {
-            return;
+            return None;
         }
 
         let metadata: Message = match serde_json::from_str(line) {
             Ok(metadata) => metadata,
             Err(e) => {
                 warning!("Unable to cargo output line as JSON metadata record: {}", e);
-                return;
+                return None;
             }
         };
-        if let Message::CompilerMessage(compiler_message) = metadata {
-            let diagnostic = &compiler_message.message;
-            let level = Self::annotation_level(diagnostic.level);
-            let mut annotation = if let Some(rendered) = &diagnostic.rendered {
-                let mut annotation = Annotation::from(rendered.as_str());
-                annotation.title(&format!("cargo-{}: {}", cargo_subcommand, diagnostic.message));
-                annotation
-            } else {
-                let mut annotation = Annotation::from(diagnostic.message.as_str());
-                annotation.title(&format!("cargo-{}", cargo_subcommand));
-                annotation
-            };
-            if let Some(span) = Self::get_primary_span(&diagnostic.spans) {
-                let file_name = Path::from(&span.file_name);
-                annotation
-                    .file(&file_name)
-                    .start_line(span.line_start)
-                    .end_line(span.line_end)
-                    .start_column(span.column_start)
-                    .end_column(span.column_end);
-            }
-            annotation.output(level);
+        let compiler_message = if let Message::CompilerMessage(compiler_message) = metadata {
+            compiler_message
+        } else {
+            return None;
+        };
+        if !Self::should_annotate(workspace_members, &compiler_message.package_id) {
+            return None;
         }
+        let diagnostic = &compiler_message.message;
+        let level = Self::annotation_level(diagnostic.level);
+        let mut annotation = if let Some(rendered) = &diagnostic.rendered {
+            let mut annotation = Annotation::from(rendered.as_str());
+            annotation.title(&format!("cargo-{}: {}", cargo_subcommand, diagnostic.message));
+            annotation
+        } else {
+            let mut annotation = Annotation::from(diagnostic.message.as_str());
+            annotation.title(&format!("cargo-{}", cargo_subcommand));
+            annotation
+        };
+        let key = if let Some(span) = Self::get_primary_span(&diagnostic.spans) {
+            let file_name = Path::from(&span.file_name);
+            annotation
+                .file(&file_name)
+                .start_line(span.line_start)
+                .end_line(span.line_end)
+                .start_column(span.column_start)
+                .end_column(span.column_end);
+            format!(
+                "{}:{}:{}:{}",
+                span.file_name, span.line_start, span.column_start, diagnostic.message
+            )
+        } else {
+            diagnostic.message.clone()
+        };
+        Some(PendingAnnotation { key, level, annotation })
     }
 
     fn annotation_level(level: DiagnosticLevel) -> AnnotationLevel {
@@ -86,8 +201,95 @@ impl Hook for Annotation {
         use crate::actions::exec::Stdio;
 
         let subcommand = self.subcommand.clone();
+        let workspace_members = self.workspace_members.clone();
+        let emitter = self.emitter.clone();
         command
-            .outline(move |line| Self::process_json_record(&subcommand, line))
+            .outline(move |line| {
+                if let Some(pending) = Self::parse_json_record(&subcommand, workspace_members.as_deref(), line) {
+                    if emitter.lock().accept(pending.key) {
+                        pending.annotation.output(pending.level);
+                    }
+                }
+            })
             .stdout(Stdio::null());
     }
+
+    async fn succeeded(&mut self) {
+        self.output_summary();
+    }
+
+    async fn failed(&mut self) {
+        self.output_summary();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Annotation, Emitter};
+    use cargo_metadata::Message;
+    use std::collections::HashSet;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn compiler_message(package_id: &str, line: usize) -> String {
+        format!(
+            r#"{{"reason":"compiler-message","package_id":"{}","manifest_path":"/ws/Cargo.toml","target":{{"kind":["lib"],"crate_types":["lib"],"name":"x","src_path":"/ws/src/lib.rs","edition":"2021","doctest":true,"test":true}},"message":{{"message":"unused variable","code":null,"level":"warning","spans":[{{"file_name":"src/lib.rs","byte_start":0,"byte_end":1,"line_start":{line},"line_end":{line},"column_start":5,"column_end":6,"is_primary":true,"text":[],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":null}}],"children":[],"rendered":null}}}}"#,
+            package_id,
+            line = line
+        )
+    }
+
+    fn is_annotated(workspace_members: Option<&HashSet<String>>, package_id: &str) -> bool {
+        match serde_json::from_str(&compiler_message(package_id, 1)).expect("Failed to parse record") {
+            Message::CompilerMessage(message) => Annotation::should_annotate(workspace_members, &message.package_id),
+            _ => panic!("Record was not a compiler message"),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn filter_dependency_messages() {
+        let member = "path+file:///ws/member#0.1.0";
+        let dependency = "path+file:///elsewhere/dependency#0.2.0";
+        let members: HashSet<String> = [member.to_string()].into_iter().collect();
+        assert!(is_annotated(Some(&members), member));
+        assert!(!is_annotated(Some(&members), dependency));
+        assert!(is_annotated(None, member));
+        assert!(is_annotated(None, dependency));
+    }
+
+    #[wasm_bindgen_test]
+    fn deduplicate_messages() {
+        let package_id = "path+file:///ws/member#0.1.0";
+        let lines = [
+            compiler_message(package_id, 1),
+            compiler_message(package_id, 1),
+            compiler_message(package_id, 2),
+        ];
+        let mut emitted = HashSet::new();
+        let count = lines
+            .iter()
+            .filter_map(|line| Annotation::parse_json_record("clippy", None, line))
+            .filter(|pending| emitted.insert(pending.key.clone()))
+            .count();
+        assert_eq!(count, 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn limit_annotations() {
+        let mut emitter = Emitter::new(10);
+        for i in 0..9 {
+            assert!(emitter.accept(format!("warning {}", i)));
+        }
+        assert!(emitter.summary().is_none());
+        assert!(!emitter.accept("warning 0".into()));
+        assert!(emitter.summary().is_none());
+        // The last annotation is left for the summary
+        assert!(!emitter.accept("warning 9".into()));
+        assert!(!emitter.accept("warning 10".into()));
+        let summary = emitter.summary().expect("Missing summary");
+        assert!(summary.starts_with("2 more"));
+
+        let mut emitter = Emitter::new(0);
+        assert!(!emitter.accept("warning 0".into()));
+        assert!(emitter.summary().is_none());
+    }
 }
@@ -30,9 +30,13 @@ impl<'a> Composite<'a> {
 #[async_trait(?Send)]
 impl<'a> Hook for Composite<'a> {
     fn additional_cargo_options(&self) -> Vec<Cow<str>> {
-        let mut result = Vec::new();
-        for hook in &self.hooks {
-            result.extend(hook.additional_cargo_options());
+        // Cargo rejects repeated options such as `--message-format=json` which
+        // more than one hook may require
+        let mut result: Vec<Cow<str>> = Vec::new();
+        for option in self.hooks.iter().flat_map(|hook| hook.additional_cargo_options()) {
+            if !result.contains(&option) {
+                result.push(option);
+            }
         }
         result
     }
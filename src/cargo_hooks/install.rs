@@ -162,13 +162,13 @@ impl Hook for Install {
                 Err(e) => {
                     error!("Failed to save package build artifacts to cache: {}", e);
                 }
-                Ok(r) => {
-                    if r.is_some() {
-                        info!("Saved package build artifacts to cache.");
-                    } else {
+                Ok(r) => match r.map(|outcome| outcome.cache_id()) {
+                    Some(Some(_)) => info!("Saved package build artifacts to cache."),
+                    Some(None) => info!("Package build artifacts were not saved to cache."),
+                    None => {
                         info!("Looks like a concurrent CI job updated the artifacts, not saving back to cache");
                     }
-                }
+                },
             }
         } else {
             info!("Build artifacts unchanged, no need to save back to cache.");
@@ -2,6 +2,11 @@ use crate::dir_tree::{self, Ignores};
 use crate::node::path::Path;
 use crate::{node, Error};
 use async_trait::async_trait;
+use wasm_bindgen::JsValue;
+
+/// Size of the buffer used when hashing files so memory usage is bounded
+/// regardless of file size
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
 
 #[derive(Debug)]
 struct FindFilesVisitor {
@@ -33,6 +38,22 @@ pub struct HashInfo {
     pub bytes: [u8; 32],
 }
 
+async fn update_hasher_from_file(hasher: &mut blake3::Hasher, path: &Path) -> Result<(), JsValue> {
+    let mut file = node::fs::File::open(path).await?;
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    let result = loop {
+        match file.read(&mut buffer).await {
+            Ok(0) => break Ok(()),
+            Ok(bytes_read) => {
+                hasher.update(&buffer[..bytes_read]);
+            }
+            Err(e) => break Err(e),
+        }
+    };
+    file.close().await?;
+    result
+}
+
 pub async fn hash_cargo_lock_files(path: &Path) -> Result<HashInfo, Error> {
     let mut visitor = FindFilesVisitor {
         name: "Cargo.lock".into(),
@@ -40,13 +61,12 @@ pub async fn hash_cargo_lock_files(path: &Path) -> Result<HashInfo, Error> {
     };
     let ignores = Ignores::default();
     dir_tree::apply_visitor(path, &ignores, &mut visitor).await?;
-    let mut paths: Vec<_> = visitor.paths.iter().map(Path::to_string).collect();
+    let mut paths = visitor.paths;
     // We want the paths in a deterministic order
-    paths.sort();
+    paths.sort_by_cached_key(Path::to_string);
     let mut hasher = blake3::Hasher::new();
     for path in &paths {
-        let file_content = node::fs::read_file(path.as_str()).await?;
-        hasher.update(&file_content);
+        update_hasher_from_file(&mut hasher, path).await?;
     }
     let result = HashInfo {
         num_files: paths.len(),
@@ -54,3 +74,28 @@ pub async fn hash_cargo_lock_files(path: &Path) -> Result<HashInfo, Error> {
     };
     Ok(result)
 }
+
+#[cfg(test)]
+mod test {
+    use super::HASH_CHUNK_SIZE;
+    use crate::node;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    async fn chunked_hash_matches_whole_file() -> Result<(), JsValue> {
+        let temp = node::os::temp_dir();
+        let file_name = format!("ferrous-actions-chunked-hash-test - {}", chrono::Local::now());
+        let path = temp.join(&file_name);
+        #[allow(clippy::cast_possible_truncation)]
+        let data: Vec<u8> = (0..(HASH_CHUNK_SIZE * 5 + 123)).map(|i| (i * 7) as u8).collect();
+        node::fs::write_file(&path, &data).await?;
+
+        let mut chunked = blake3::Hasher::new();
+        super::update_hasher_from_file(&mut chunked, &path).await?;
+        let whole = blake3::hash(&node::fs::read_file(&path).await?);
+        assert_eq!(chunked.finalize(), whole);
+        node::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
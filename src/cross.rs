@@ -28,8 +28,7 @@ impl Cross {
 
     async fn install(input_manager: &input_manager::Manager) -> Result<Cross, Error> {
         let mut cargo = Cargo::from_environment().await?;
-        let args = ["cross"];
-        cargo.run(None, "install", args, input_manager).await?;
+        cargo.install("cross", None, false, input_manager).await?;
         Self::get().await
     }
 
@@ -9,9 +9,10 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 pub const ROOT_NAME: &str = ".";
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub struct Ignores {
     map: HashMap<usize, HashSet<String>>,
+    protected: HashMap<usize, HashSet<String>>,
 }
 
 impl Ignores {
@@ -19,9 +20,19 @@ impl Ignores {
         self.map.entry(depth).or_default().insert(name.to_string());
     }
 
+    /// Prevents anything inside a folder with the specified name at the
+    /// specified depth from being ignored
+    pub fn protect(&mut self, depth: usize, name: &str) {
+        self.protected.entry(depth).or_default().insert(name.to_string());
+    }
+
     pub fn should_ignore(&self, name: &str, depth: usize) -> bool {
         self.map.get(&depth).map_or(false, |names| names.contains(name))
     }
+
+    fn is_protected(&self, name: &str, depth: usize) -> bool {
+        self.protected.get(&depth).map_or(false, |names| names.contains(name))
+    }
 }
 
 #[async_trait(?Send)]
@@ -38,7 +49,7 @@ pub async fn apply_visitor<V>(folder_path: &Path, ignores: &Ignores, visitor: &m
 where
     V: Visitor,
 {
-    apply_visitor_impl(0, folder_path, ignores, visitor).await
+    apply_visitor_impl(0, folder_path, ignores, false, visitor).await
 }
 
 #[async_recursion(?Send)]
@@ -46,6 +57,7 @@ async fn apply_visitor_impl(
     depth: usize,
     path: &Path,
     ignores: &Ignores,
+    protected: bool,
     visitor: &mut dyn Visitor,
 ) -> Result<(), Error> {
     let file_name: Cow<str> = if depth == 0 {
@@ -53,18 +65,19 @@ async fn apply_visitor_impl(
     } else {
         path.file_name().into()
     };
-    if ignores.should_ignore(&file_name, depth) {
+    if !protected && ignores.should_ignore(&file_name, depth) {
         return Ok(());
     }
     let metadata = fs::symlink_metadata(path).await?;
     if metadata.is_directory() {
         if visitor.should_enter(path).await? {
             visitor.enter_folder(path).await?;
+            let protected = protected || ignores.is_protected(&file_name, depth);
             let depth = depth + 1;
             let dir = fs::read_dir(path).await?;
             for entry in dir {
                 let path = entry.path();
-                apply_visitor_impl(depth, &path, ignores, visitor).await?;
+                apply_visitor_impl(depth, &path, ignores, protected, visitor).await?;
             }
             visitor.exit_folder(path).await?;
         } else {
@@ -76,6 +89,41 @@ async fn apply_visitor_impl(
     Ok(())
 }
 
+/// Finds the paths within the specified folder which would be skipped by a
+/// visitor using the supplied ignores
+pub async fn find_ignored_paths(folder_path: &Path, ignores: &Ignores) -> Result<Vec<Path>, Error> {
+    let mut result = Vec::new();
+    find_ignored_paths_impl(0, folder_path, ignores, false, &mut result).await?;
+    Ok(result)
+}
+
+#[async_recursion(?Send)]
+async fn find_ignored_paths_impl(
+    depth: usize,
+    path: &Path,
+    ignores: &Ignores,
+    protected: bool,
+    result: &mut Vec<Path>,
+) -> Result<(), Error> {
+    let file_name: Cow<str> = if depth == 0 {
+        ROOT_NAME.into()
+    } else {
+        path.file_name().into()
+    };
+    if !protected && ignores.should_ignore(&file_name, depth) {
+        result.push(path.clone());
+        return Ok(());
+    }
+    let metadata = fs::symlink_metadata(path).await?;
+    if metadata.is_directory() {
+        let protected = protected || ignores.is_protected(&file_name, depth);
+        for entry in fs::read_dir(path).await? {
+            find_ignored_paths_impl(depth + 1, &entry.path(), ignores, protected, result).await?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 struct PathMatchVisitor<'a> {
     matching_paths: Vec<Path>,
@@ -141,3 +189,22 @@ pub async fn match_relative_paths(path: &Path, matcher: &PathMatch, output_relat
     apply_visitor(path, &ignores, &mut visitor).await?;
     Ok(visitor.matching_paths)
 }
+
+/// Searches the specified folder and then each of its ancestors for a file
+/// with one of the supplied names, returning the first found
+pub async fn find_up(folder: &Path, file_names: &[&str]) -> Option<Path> {
+    let mut current = folder.clone();
+    loop {
+        for name in file_names {
+            let candidate = current.join(*name);
+            if candidate.exists().await {
+                return Some(candidate);
+            }
+        }
+        let parent = current.parent();
+        if parent == current {
+            return None;
+        }
+        current = parent;
+    }
+}
@@ -1,4 +1,4 @@
-use crate::package_manifest;
+use crate::{cargo_config, package_manifest, toolchain_file};
 use thiserror::Error;
 use wasm_bindgen::JsValue;
 
@@ -37,6 +37,9 @@ pub enum Error {
     #[error("Unable to parse item to cache: {0}")]
     ParseCacheableItem(String),
 
+    #[error("Unknown profile `{0}`, expected one of `minimal`, `default` or `complete`")]
+    ParseProfile(String),
+
     #[error("Unable to parse cross-plaform-sharing: {0}")]
     ParseCrossPlatformSharing(String),
 
@@ -60,6 +63,80 @@ pub enum Error {
 
     #[error("Postcard serialization/deserialization error: {0}")]
     SerdePostcard(#[from] postcard::Error),
+
+    #[error("No gzip-compressed archive is available for package {0}")]
+    MissingGzipArchive(String),
+
+    #[error("Action state `{0}` is missing or invalid. Did the main step of the action run?")]
+    InvalidState(String),
+
+    #[error("Environment variable `{0}` is not set")]
+    MissingEnvironmentVariable(String),
+
+    #[error("Unable to parse environment variable `{0}`, which has the value `{1}`")]
+    InvalidEnvironmentVariable(String, String),
+
+    #[error("Invalid extra cache path `{0}`: {1}")]
+    InvalidExtraCachePath(String, &'static str),
+
+    #[error("Unable to parse toolchain file: {0}")]
+    ToolchainFile(#[from] toolchain_file::ParseError),
+
+    #[error("Cargo cannot enable all features and also a list of features")]
+    ConflictingFeatureSelection,
+
+    #[error("The following cached items were not restored: {0}")]
+    CacheMiss(String),
+
+    #[error("{0} failed with exit code {1}: {2}")]
+    CommandFailed(String, i32, String),
+
+    #[error("{0} tests failed")]
+    TestsFailed(usize),
+
+    #[error("Invalid rustup installer options: {0}")]
+    InvalidRustupInstallOptions(&'static str),
+
+    #[error("Node.js {0} is not supported, version {1} or newer is required")]
+    UnsupportedNodeVersion(String, String),
+
+    #[error("Invalid wasm-opt optimization level: {0}")]
+    InvalidWasmOptLevel(String),
+
+    #[error("No Binaryen release is available for platform {0} with architecture {1}")]
+    UnsupportedBinaryenPlatform(String, String),
+
+    #[error("Unable to find {0} after installing it")]
+    MissingToolAfterInstall(String),
+
+    #[error("{0} directory {1} cannot be used because {2}. Set {0} to a writable directory.")]
+    UnusableHomeDir(String, String, String),
+
+    #[error("Cargo configuration `{0}` is neither a key=value pair nor a path to a TOML file")]
+    InvalidCargoConfig(String),
+
+    #[error("Unable to parse Cargo configuration: {0}")]
+    CargoConfigFile(#[from] cargo_config::ParseError),
+}
+
+impl Error {
+    /// Whether this error was caused by how the action was configured in the
+    /// workflow file
+    pub fn is_configuration_error(&self) -> bool {
+        matches!(
+            self,
+            Error::OptionParseError(..)
+                | Error::ArgumentsParseError(_)
+                | Error::UnknownCommand(_)
+                | Error::ParseCacheableItem(_)
+                | Error::InvalidExtraCachePath(..)
+                | Error::ParseProfile(_)
+                | Error::ParseCrossPlatformSharing(_)
+                | Error::DurationParse(_)
+                | Error::MissingInput(_)
+                | Error::InvalidWasmOptLevel(_)
+        )
+    }
 }
 
 impl From<JsValue> for Error {
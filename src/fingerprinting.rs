@@ -1,5 +1,6 @@
 use crate::delta::Action as DeltaAction;
 pub use crate::dir_tree::Ignores;
+use crate::hasher::Blake3 as Blake3Hasher;
 use crate::node::fs;
 use crate::node::path::{self, Path};
 use crate::{dir_tree, Error};
@@ -131,6 +132,44 @@ impl Fingerprint {
         hasher.finish()
     }
 
+    /// Content hashes of each top-level entry, keyed by name. If the
+    /// fingerprinted path is a file, it is the only entry.
+    pub fn top_level_hashes(&self) -> BTreeMap<String, u64> {
+        match &self.root {
+            Entry::File(_) => BTreeMap::from([(ROOT_NAME.to_string(), self.content_hash)]),
+            Entry::Dir(sub_tree) => sub_tree
+                .iter()
+                .map(|(name, entry)| (name.clone(), Self::compute_entry_hash(entry)))
+                .collect(),
+        }
+    }
+
+    /// A hash of the names and sizes of all files and folders. Unlike the
+    /// content hash, this is expected to be unchanged when the fingerprinted
+    /// path is archived and restored on another machine.
+    pub fn size_hash(&self) -> u64 {
+        Self::compute_entry_size_hash(&self.root)
+    }
+
+    fn compute_entry_size_hash(entry: &Entry) -> u64 {
+        // We use Blake3 since this hash may be compared against one computed by a
+        // different build of the action
+        let mut hasher = Blake3Hasher::default();
+        match entry {
+            Entry::File(metadata) => {
+                metadata.len.hash(&mut hasher);
+            }
+            Entry::Dir(sub_tree) => {
+                for (name, entry) in sub_tree {
+                    name.hash(&mut hasher);
+                    let hash = Self::compute_entry_size_hash(entry);
+                    hash.hash(&mut hasher);
+                }
+            }
+        }
+        hasher.finish()
+    }
+
     pub fn modified(&self) -> Option<DateTime<Utc>> {
         self.modified
     }
@@ -242,6 +281,24 @@ impl dir_tree::Visitor for BuildFingerprintVisitor {
     }
 }
 
+/// Builds a fingerprint for content identified by a revision (e.g. a Git
+/// commit) rather than by the metadata of every file it contains. File metadata
+/// is taken from the file the revision was read from, so access to that file is
+/// treated as access to the content.
+pub async fn fingerprint_revision(revision_file: &Path, revision: &str) -> Result<Fingerprint, Error> {
+    let stats = fs::symlink_metadata(revision_file).await?;
+    let metadata = Metadata::from(&stats);
+    let mut hasher = DefaultHasher::default();
+    revision.hash(&mut hasher);
+    let result = Fingerprint {
+        content_hash: hasher.finish(),
+        modified: Some(metadata.modified),
+        accessed: Some(metadata.accessed),
+        root: Entry::File(metadata),
+    };
+    Ok(result)
+}
+
 pub async fn fingerprint_path_with_ignores(path: &Path, ignores: &Ignores) -> Result<Fingerprint, Error> {
     let mut visitor = BuildFingerprintVisitor {
         stack: VecDeque::new(),
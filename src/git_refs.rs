@@ -0,0 +1,92 @@
+use crate::node;
+use crate::node::path::Path;
+
+/// The reference Cargo fetches a Git-based registry index into
+const CARGO_FETCHED_REF: &str = "refs/remotes/origin/HEAD";
+
+/// Symbolic references may point to other symbolic references, but we give up
+/// after this many
+const MAX_SYMBOLIC_REF_DEPTH: usize = 5;
+
+fn is_object_id(value: &str) -> bool {
+    // SHA-1 and SHA-256 object IDs respectively
+    matches!(value.len(), 40 | 64) && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn find_packed_ref<'a>(packed_refs: &'a str, reference: &str) -> Option<&'a str> {
+    packed_refs
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+        .filter_map(|line| line.split_once(' '))
+        .find_map(|(id, name)| (name.trim() == reference).then_some(id))
+        .filter(|id| is_object_id(id))
+}
+
+async fn read_to_string(path: &Path) -> Option<String> {
+    let content = node::fs::read_file(path).await.ok()?;
+    String::from_utf8(content).ok()
+}
+
+async fn resolve_ref(git_dir: &Path, reference: &str) -> Option<(Path, String)> {
+    let mut reference = reference.to_string();
+    for _ in 0..MAX_SYMBOLIC_REF_DEPTH {
+        let ref_path = git_dir.join(reference.as_str());
+        if !ref_path.exists().await {
+            let packed_refs_path = git_dir.join("packed-refs");
+            let packed_refs = read_to_string(&packed_refs_path).await?;
+            let id = find_packed_ref(&packed_refs, &reference)?;
+            return Some((packed_refs_path, id.to_string()));
+        }
+        let content = read_to_string(&ref_path).await?;
+        let content = content.trim();
+        if let Some(target) = content.strip_prefix("ref:") {
+            reference = target.trim().to_string();
+        } else {
+            return is_object_id(content).then(|| (ref_path, content.to_string()));
+        }
+    }
+    None
+}
+
+/// Determines the commit a Git repository has been fetched or checked out at.
+///
+/// Returns the commit ID along with the path of the file it was read from, or
+/// `None` if the repository metadata could not be understood.
+pub async fn resolve_head(repo_path: &Path) -> Option<(Path, String)> {
+    let git_dir = repo_path.join(".git");
+    for reference in [CARGO_FETCHED_REF, "HEAD"] {
+        if let Some(resolved) = resolve_ref(&git_dir, reference).await {
+            return Some(resolved);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    #[wasm_bindgen_test]
+    fn object_ids() {
+        assert!(super::is_object_id(COMMIT));
+        assert!(!super::is_object_id(&COMMIT[1..]));
+        assert!(!super::is_object_id("ref: refs/heads/master"));
+    }
+
+    #[wasm_bindgen_test]
+    fn packed_refs() {
+        let packed_refs = format!(
+            "# pack-refs with: peeled fully-peeled sorted\n{} refs/heads/master\n^{}\n{} refs/remotes/origin/HEAD\n",
+            "f".repeat(40),
+            "e".repeat(40),
+            COMMIT
+        );
+        assert_eq!(
+            super::find_packed_ref(&packed_refs, "refs/remotes/origin/HEAD"),
+            Some(COMMIT)
+        );
+        assert_eq!(super::find_packed_ref(&packed_refs, "refs/tags/v1"), None);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::hasher::Blake3 as Blake3Hasher;
+use crate::{node, safe_encoding, Error};
+use std::collections::HashMap;
+use std::hash::Hash as _;
+use std::str::FromStr;
+
+/// Folder within a repository that workflow files live in
+const WORKFLOWS_FOLDER: &str = ".github/workflows/";
+
+/// Information about the workflow run, taken from the `GITHUB_*` and
+/// `RUNNER_*` environment variables set by the runner
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Context {
+    /// Unique number for each workflow run within a repository
+    pub run_id: u64,
+
+    /// Number of times this workflow run has been attempted, starting from 1
+    pub run_attempt: u32,
+
+    /// ID of the current job
+    pub job: String,
+
+    /// Name of the workflow, or its path if it is unnamed
+    pub workflow: String,
+
+    /// Reference to the workflow file, e.g.
+    /// `octocat/hello-world/.github/workflows/ci.yml@refs/heads/main`. Not set
+    /// by older runners.
+    pub workflow_ref: Option<String>,
+
+    /// Short name of the branch or tag that triggered the run
+    pub ref_name: String,
+
+    /// Commit SHA that triggered the run
+    pub sha: String,
+
+    /// Owner and repository name, e.g. `octocat/hello-world`
+    pub repository: String,
+
+    /// Name of the user or app that initiated the run
+    pub actor: String,
+
+    /// Name of the event that triggered the run
+    pub event_name: String,
+
+    /// Name of the runner executing the job
+    pub runner_name: Option<String>,
+}
+
+fn get_var<'a>(env: &'a HashMap<String, String>, name: &str) -> Result<&'a str, Error> {
+    env.get(name)
+        .map(String::as_str)
+        .ok_or_else(|| Error::MissingEnvironmentVariable(name.to_string()))
+}
+
+fn parse_var<T: FromStr>(env: &HashMap<String, String>, name: &str) -> Result<T, Error> {
+    let value = get_var(env, name)?;
+    value
+        .parse()
+        .map_err(|_| Error::InvalidEnvironmentVariable(name.to_string(), value.to_string()))
+}
+
+impl Context {
+    /// Reads the context from the environment of the process
+    pub fn from_env() -> Result<Context, Error> {
+        Self::from_env_map(&node::process::get_env())
+    }
+
+    /// Reads the context from the supplied environment
+    pub fn from_env_map(env: &HashMap<String, String>) -> Result<Context, Error> {
+        let string_var = |name| get_var(env, name).map(String::from);
+        let optional_var = |name| env.get(name).filter(|value| !value.is_empty()).cloned();
+        let context = Context {
+            run_id: parse_var(env, "GITHUB_RUN_ID")?,
+            run_attempt: parse_var(env, "GITHUB_RUN_ATTEMPT")?,
+            job: string_var("GITHUB_JOB")?,
+            workflow: string_var("GITHUB_WORKFLOW")?,
+            workflow_ref: optional_var("GITHUB_WORKFLOW_REF"),
+            ref_name: string_var("GITHUB_REF_NAME")?,
+            sha: string_var("GITHUB_SHA")?,
+            repository: string_var("GITHUB_REPOSITORY")?,
+            actor: string_var("GITHUB_ACTOR")?,
+            event_name: string_var("GITHUB_EVENT_NAME")?,
+            runner_name: optional_var("RUNNER_NAME"),
+        };
+        Ok(context)
+    }
+
+    /// A string identifying the job being run. Concurrent jobs on the same
+    /// machine (e.g. matrix legs on self-hosted runners) have different
+    /// namespaces.
+    pub fn job_namespace(&self) -> String {
+        use std::hash::Hasher as _;
+
+        let mut hasher = Blake3Hasher::default();
+        self.run_id.hash(&mut hasher);
+        self.run_attempt.hash(&mut hasher);
+        self.job.hash(&mut hasher);
+        self.runner_name.hash(&mut hasher);
+        safe_encoding::encode(hasher.finish().to_le_bytes())
+    }
+
+    /// The path of the running workflow's file relative to the root of the
+    /// repository, if it can be determined
+    pub fn workflow_file_path(&self) -> Option<String> {
+        // `GITHUB_WORKFLOW_REF` has the form `owner/repo/path@ref`
+        if let Some(workflow_ref) = &self.workflow_ref {
+            let path = workflow_ref
+                .rsplit_once('@')
+                .map_or(workflow_ref.as_str(), |(path, _)| path);
+            if let Some(path) = path
+                .strip_prefix(self.repository.as_str())
+                .and_then(|p| p.strip_prefix('/'))
+            {
+                return Some(path.to_string());
+            }
+        }
+        // `GITHUB_WORKFLOW` is the path of the workflow file only if the workflow
+        // is unnamed
+        Some(self.workflow.clone()).filter(|workflow| workflow.starts_with(WORKFLOWS_FOLDER))
+    }
+}
+
+impl std::fmt::Display for Context {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            formatter,
+            "job {} of run {} (attempt {}) of workflow {} in {}, triggered by {} on {} of {} at {}",
+            self.job,
+            self.run_id,
+            self.run_attempt,
+            self.workflow,
+            self.repository,
+            self.actor,
+            self.event_name,
+            self.ref_name,
+            self.sha
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Context;
+    use crate::Error;
+    use std::collections::HashMap;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn build_env() -> HashMap<String, String> {
+        [
+            ("GITHUB_RUN_ID", "1658821493"),
+            ("GITHUB_RUN_ATTEMPT", "2"),
+            ("GITHUB_JOB", "build"),
+            ("GITHUB_WORKFLOW", "CI"),
+            ("GITHUB_REF_NAME", "main"),
+            ("GITHUB_SHA", "ffac537e6cbbf934b08745a378932722df287a53"),
+            ("GITHUB_REPOSITORY", "octocat/hello-world"),
+            ("GITHUB_ACTOR", "octocat"),
+            ("GITHUB_EVENT_NAME", "push"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    fn context(env: &HashMap<String, String>) -> Context {
+        Context::from_env_map(env).expect("Failed to read context")
+    }
+
+    #[wasm_bindgen_test]
+    fn context_from_env() {
+        let context = context(&build_env());
+        assert_eq!(context.run_id, 1_658_821_493);
+        assert_eq!(context.run_attempt, 2);
+        assert_eq!(context.job, "build");
+        assert_eq!(context.repository, "octocat/hello-world");
+        assert_eq!(context.event_name, "push");
+        assert_eq!(context.workflow_ref, None);
+        assert_eq!(context.runner_name, None);
+    }
+
+    #[wasm_bindgen_test]
+    fn missing_and_invalid_variables() {
+        let mut env = build_env();
+        env.insert("GITHUB_RUN_ATTEMPT".into(), "first".into());
+        assert!(matches!(
+            Context::from_env_map(&env),
+            Err(Error::InvalidEnvironmentVariable(name, _)) if name == "GITHUB_RUN_ATTEMPT"
+        ));
+        env.remove("GITHUB_SHA");
+        env.insert("GITHUB_RUN_ATTEMPT".into(), "1".into());
+        assert!(matches!(
+            Context::from_env_map(&env),
+            Err(Error::MissingEnvironmentVariable(name)) if name == "GITHUB_SHA"
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn job_namespace_distinguishes_jobs() {
+        let mut env = build_env();
+        let original = context(&env).job_namespace();
+        env.insert("GITHUB_SHA".into(), "0000000000000000000000000000000000000000".into());
+        assert_eq!(context(&env).job_namespace(), original);
+        env.insert("RUNNER_NAME".into(), "runner-2".into());
+        assert_ne!(context(&env).job_namespace(), original);
+        env.remove("RUNNER_NAME");
+        env.insert("GITHUB_RUN_ATTEMPT".into(), "3".into());
+        assert_ne!(context(&env).job_namespace(), original);
+    }
+
+    #[wasm_bindgen_test]
+    fn path_from_workflow_ref() {
+        let mut env = build_env();
+        env.insert(
+            "GITHUB_WORKFLOW_REF".into(),
+            "octocat/hello-world/.github/workflows/ci.yml@refs/heads/main".into(),
+        );
+        assert_eq!(
+            context(&env).workflow_file_path().as_deref(),
+            Some(".github/workflows/ci.yml")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn path_from_unnamed_workflow() {
+        let mut env = build_env();
+        env.insert("GITHUB_WORKFLOW".into(), ".github/workflows/ci.yml".into());
+        assert_eq!(
+            context(&env).workflow_file_path().as_deref(),
+            Some(".github/workflows/ci.yml")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn path_unavailable() {
+        assert_eq!(context(&build_env()).workflow_file_path(), None);
+    }
+}
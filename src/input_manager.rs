@@ -2,31 +2,86 @@ use crate::actions::core;
 use crate::Error;
 use parking_lot::Mutex;
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 use strum::{EnumIter, IntoEnumIterator as _, IntoStaticStr};
 
 #[derive(IntoStaticStr, Clone, Copy, Debug, EnumIter, Eq, Hash, PartialEq, strum::Display)]
 pub enum Input {
+    #[strum(serialize = "all-features")]
+    AllFeatures,
+
+    #[strum(serialize = "annotate-dependencies")]
+    AnnotateDependencies,
+
+    #[strum(serialize = "annotation-limit")]
+    AnnotationLimit,
+
     #[strum(serialize = "annotations")]
     Annotations,
 
     #[strum(serialize = "args")]
     Args,
 
+    #[strum(serialize = "cache-download-concurrency")]
+    CacheDownloadConcurrency,
+
     #[strum(serialize = "cache-only")]
     CacheOnly,
 
+    #[strum(serialize = "cache-rustup")]
+    CacheRustup,
+
+    #[strum(serialize = "cache-target")]
+    CacheTarget,
+
+    #[strum(serialize = "cache-upload-chunk-size")]
+    CacheUploadChunkSize,
+
+    #[strum(serialize = "cache-upload-concurrency")]
+    CacheUploadConcurrency,
+
     #[strum(serialize = "command")]
     Command,
 
     #[strum(serialize = "components")]
     Components,
 
+    #[strum(serialize = "config")]
+    Config,
+
     #[strum(serialize = "cross-platform-sharing")]
     CrossPlatformSharing,
 
     #[strum(serialize = "default")]
     Default,
 
+    #[strum(serialize = "dry-run")]
+    DryRun,
+
+    #[strum(serialize = "exclude")]
+    Exclude,
+
+    #[strum(serialize = "extra-cache-paths")]
+    ExtraCachePaths,
+
+    #[strum(serialize = "fail-on-cache-miss")]
+    FailOnCacheMiss,
+
+    #[strum(serialize = "features")]
+    Features,
+
+    #[strum(serialize = "frozen")]
+    Frozen,
+
+    #[strum(serialize = "job-summary")]
+    JobSummary,
+
+    #[strum(serialize = "locked")]
+    Locked,
+
+    #[strum(serialize = "min-recache-binaries")]
+    MinRecacheBinaries,
+
     #[strum(serialize = "min-recache-crates")]
     MinRecacheCrates,
 
@@ -36,12 +91,42 @@ pub enum Input {
     #[strum(serialize = "min-recache-indices")]
     MinRecacheIndices,
 
+    #[strum(serialize = "no-default-features")]
+    NoDefaultFeatures,
+
+    #[strum(serialize = "offline")]
+    Offline,
+
     #[strum(serialize = "override")]
     Override,
 
+    #[strum(serialize = "package")]
+    Package,
+
+    #[strum(serialize = "prefetch")]
+    Prefetch,
+
+    #[strum(serialize = "preserve-timestamps")]
+    PreserveTimestamps,
+
     #[strum(serialize = "profile")]
     Profile,
 
+    #[strum(serialize = "prune-target")]
+    PruneTarget,
+
+    #[strum(serialize = "restore")]
+    Restore,
+
+    #[strum(serialize = "rustup-self-update")]
+    RustupSelfUpdate,
+
+    #[strum(serialize = "save")]
+    Save,
+
+    #[strum(serialize = "target-cache-exclude")]
+    TargetCacheExclude,
+
     // We name this target instead of targets since actions-rs only has target
     #[strum(serialize = "target")]
     Targets,
@@ -51,6 +136,18 @@ pub enum Input {
 
     #[strum(serialize = "use-cross")]
     UseCross,
+
+    #[strum(serialize = "verify-cache")]
+    VerifyCache,
+
+    #[strum(serialize = "wasm-opt")]
+    WasmOpt,
+
+    #[strum(serialize = "wasm-opt-output-dir")]
+    WasmOptOutputDir,
+
+    #[strum(serialize = "workspace")]
+    Workspace,
 }
 
 #[derive(Debug)]
@@ -74,11 +171,27 @@ impl Manager {
         })
     }
 
+    /// Constructs a manager with the specified input values, rather than
+    /// those passed to the action
+    #[cfg(test)]
+    pub fn from_inputs<'a, I: IntoIterator<Item = (Input, &'a str)>>(inputs: I) -> Manager {
+        Manager {
+            inputs: inputs.into_iter().map(|(k, v)| (k, v.to_string())).collect(),
+            accessed: Mutex::default(),
+        }
+    }
+
     pub fn get(&self, input: Input) -> Option<&str> {
         self.accessed.lock().insert(input);
         self.inputs.get(&input).map(String::as_str)
     }
 
+    /// Gets an input containing a list of items separated by whitespace, commas
+    /// or newlines
+    pub fn get_list(&self, input: Input) -> Option<Vec<&str>> {
+        self.get(input).map(|value| core::split_list(value).collect())
+    }
+
     pub fn get_required(&self, input: Input) -> Result<&str, Error> {
         self.get(input).ok_or_else(|| {
             let input_name: &str = input.into();
@@ -91,3 +204,39 @@ impl Manager {
         &available - &self.accessed.lock()
     }
 }
+
+/// Parses an optional input, failing if it is present but cannot be parsed
+pub fn parse_optional_input<T: FromStr>(input_manager: &Manager, input: Input) -> Result<Option<T>, Error> {
+    input_manager
+        .get(input)
+        .map(|value| {
+            value
+                .parse::<T>()
+                .map_err(|_| Error::OptionParseError(input.to_string(), value.to_string()))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_optional_input, Input, Manager};
+    use crate::Error;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn optional_inputs() {
+        let inputs = Manager::from_inputs([(Input::Save, "false"), (Input::AnnotationLimit, "5")]);
+        assert_eq!(parse_optional_input(&inputs, Input::Save).unwrap(), Some(false));
+        assert_eq!(
+            parse_optional_input(&inputs, Input::AnnotationLimit).unwrap(),
+            Some(5usize)
+        );
+        assert_eq!(parse_optional_input::<bool>(&inputs, Input::Restore).unwrap(), None);
+        assert!(inputs.unused().is_empty());
+        let inputs = Manager::from_inputs([(Input::Save, "no")]);
+        assert!(matches!(
+            parse_optional_input::<bool>(&inputs, Input::Save),
+            Err(Error::OptionParseError(input, value)) if input == "save" && value == "no"
+        ));
+    }
+}
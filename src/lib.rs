@@ -34,6 +34,7 @@ mod agnostic_path;
 mod cache_cargo_home;
 mod cache_key_builder;
 mod cargo;
+mod cargo_config;
 mod cargo_hooks;
 mod cargo_lock_hashing;
 mod cross;
@@ -41,6 +42,8 @@ mod delta;
 mod dir_tree;
 mod error;
 mod fingerprinting;
+mod git_refs;
+mod github;
 mod hasher;
 mod input_manager;
 mod job;
@@ -50,8 +53,15 @@ mod run;
 mod rustup;
 mod safe_encoding;
 mod system;
+mod target_triple;
+mod test_summary;
+mod timestamps;
 mod toolchain;
+mod toolchain_file;
 mod utils;
+mod versions;
+mod wasm_opt;
+mod workflow;
 
 use crate::cargo::Cargo;
 use crate::error::Error;
@@ -72,7 +82,12 @@ pub async fn start() -> Result<(), wasm_bindgen::JsValue> {
     utils::set_panic_hook();
 
     if let Err(e) = run::run().await {
-        core::set_failed(e.to_string());
+        if e.is_configuration_error() {
+            workflow::configuration_error(&e.to_string());
+            node::process::set_exit_code(1);
+        } else {
+            core::set_failed(e.to_string());
+        }
     }
     Ok(())
 }
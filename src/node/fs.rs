@@ -115,6 +115,16 @@ impl DirEntry {
             inner: determine_file_type(&self.inner),
         }
     }
+
+    /// Returns metadata about the entry, following symlinks
+    pub async fn metadata(&self) -> Result<Metadata, JsValue> {
+        metadata(&self.path()).await
+    }
+
+    /// Returns metadata about the entry, without following symlinks
+    pub async fn symlink_metadata(&self) -> Result<Metadata, JsValue> {
+        symlink_metadata(&self.path()).await
+    }
 }
 
 impl Iterator for ReadDir {
@@ -124,10 +134,23 @@ impl Iterator for ReadDir {
         let parent = self.path.clone();
         self.entries.pop_front().map(|inner| DirEntry { parent, inner })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.entries.len();
+        (len, Some(len))
+    }
 }
 
+impl ExactSizeIterator for ReadDir {}
+
 /// Changes the permissions of the specified path to the specified mode
+///
+/// The mode is made up of Unix permission bits, so should normally be written
+/// in octal (e.g. `0o755`).
 pub async fn chmod<P: Into<JsString>>(path: P, mode: u16) -> Result<(), JsValue> {
+    if mode & !0o7777 != 0 {
+        return Err(JsError::new(&format!("Invalid file mode: {:#o}", mode)).into());
+    }
     let path: JsString = path.into();
     ffi::chmod(&path, mode).await.map(|_| ())
 }
@@ -152,6 +175,87 @@ pub async fn write_file<P: Into<JsString>>(path: P, data: &[u8]) -> Result<(), J
     Ok(())
 }
 
+/// Options controlling how `write_file_with_options` opens and writes a file
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    flag: String,
+    mode: u32,
+    flush: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> WriteOptions {
+        WriteOptions {
+            flag: "w".into(),
+            mode: 0o666,
+            flush: false,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// The file system flag used to open the file, such as `a` to append or
+    /// `wx` to fail if the file already exists
+    pub fn flag(&mut self, flag: &str) -> &mut WriteOptions {
+        self.flag = flag.to_string();
+        self
+    }
+
+    /// The permissions given to the file if it is created. These are subject
+    /// to the process umask.
+    pub fn mode(&mut self, mode: u32) -> &mut WriteOptions {
+        self.mode = mode;
+        self
+    }
+
+    /// Flushes the data to storage before the file is closed
+    pub fn flush(&mut self, flush: bool) -> &mut WriteOptions {
+        self.flush = flush;
+        self
+    }
+
+    fn to_js_object(&self) -> Object {
+        let options = js_sys::Map::new();
+        options.set(&"flag".into(), &self.flag.as_str().into());
+        options.set(&"mode".into(), &self.mode.into());
+        options.set(&"flush".into(), &self.flush.into());
+        Object::from_entries(&options).expect("Failed to convert options map to object")
+    }
+}
+
+/// Write the supplied data to a file at the specified path using the
+/// specified options
+pub async fn write_file_with_options<P: Into<JsString>>(
+    path: P,
+    data: &[u8],
+    options: &WriteOptions,
+) -> Result<(), JsValue> {
+    let path: JsString = path.into();
+    ffi::write_file_with_options(&path, data, options.to_js_object()).await?;
+    Ok(())
+}
+
+/// Appends the supplied data to a file at the specified path, creating it if
+/// it does not exist
+pub async fn append_file<P: Into<JsString>>(path: P, data: &[u8]) -> Result<(), JsValue> {
+    let path: JsString = path.into();
+    ffi::append_file(&path, data).await?;
+    Ok(())
+}
+
+/// Write the supplied data to a file at the specified path such that readers
+/// will never observe a partially written file
+///
+/// The data is first written to a temporary file in the same folder which is
+/// then renamed over the destination.
+pub async fn write_file_atomic<P: Into<Path>>(path: P, data: &[u8]) -> Result<(), JsValue> {
+    let path: Path = path.into();
+    let temp_path = path.with_extension("tmp");
+    write_file(&temp_path, data).await?;
+    rename(&temp_path, &path).await?;
+    Ok(())
+}
+
 /// Reads all entries in the specified folder and returns an iterator
 pub async fn read_dir<P: Into<JsString>>(path: P) -> Result<ReadDir, JsValue> {
     let path: JsString = path.into();
@@ -171,6 +275,63 @@ pub async fn read_dir<P: Into<JsString>>(path: P) -> Result<ReadDir, JsValue> {
     Ok(entries)
 }
 
+/// Reads the names of all entries in the specified folder
+///
+/// This is faster than `read_dir` on filesystems where determining file types
+/// requires an additional `stat` per entry, but file types are not available.
+pub async fn read_dir_names<P: Into<JsString>>(path: P) -> Result<Vec<String>, JsValue> {
+    let path: JsString = path.into();
+    let options = js_sys::Map::new();
+    options.set(&"encoding".into(), &"utf8".into());
+    let options = Object::from_entries(&options).expect("Failed to convert options map to object");
+    let names = ffi::read_dir(&path, Some(options)).await?;
+    let names = names
+        .dyn_into::<js_sys::Array>()
+        .map_err(|_| JsError::new("read_dir didn't return an array"))?
+        .iter()
+        .map(|name| String::from(JsString::from(name)))
+        .collect();
+    Ok(names)
+}
+
+/// Reads entries in the specified folder lazily as a stream
+///
+/// Unlike `read_dir`, entries are fetched from node.js one at a time, so memory
+/// usage does not grow with the size of the folder. The cost is a round trip
+/// to the JavaScript event loop per entry, so `read_dir` is faster for small
+/// folders.
+pub async fn read_dir_stream<P: Into<JsString>>(
+    path: P,
+) -> Result<impl futures::Stream<Item = Result<DirEntry, JsValue>>, JsValue> {
+    let path: JsString = path.into();
+    let options = js_sys::Map::new();
+    options.set(&"encoding".into(), &"utf8".into());
+    let options = Object::from_entries(&options).expect("Failed to convert options map to object");
+    let dir: ffi::Dir = ffi::opendir(&path, Some(options)).await?.into();
+    let path = Path::from(path);
+    let stream = futures::stream::unfold(Some(dir), move |dir| {
+        let parent = path.clone();
+        async move {
+            let dir = dir?;
+            match dir.read().await {
+                Ok(entry) if entry.is_null() => {
+                    drop(dir.close().await);
+                    None
+                }
+                Ok(entry) => {
+                    let inner = entry.into();
+                    Some((Ok(DirEntry { parent, inner }), Some(dir)))
+                }
+                Err(e) => {
+                    drop(dir.close().await);
+                    Some((Err(e), None))
+                }
+            }
+        }
+    });
+    Ok(stream)
+}
+
 /// Creates a folder and any required parent folders at the specified path
 pub async fn create_dir_all<P: Into<JsString>>(path: P) -> Result<(), JsValue> {
     let options = js_sys::Map::new();
@@ -181,6 +342,22 @@ pub async fn create_dir_all<P: Into<JsString>>(path: P) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Creates each of the specified folders and any required parent folders
+///
+/// The folders are created concurrently. All creations are allowed to complete
+/// and the first error encountered, if any, is returned.
+pub async fn ensure_dirs<I, P>(paths: I) -> Result<(), JsValue>
+where
+    I: IntoIterator<Item = P>,
+    P: Into<Path>,
+{
+    let creations = paths.into_iter().map(|path| {
+        let path: Path = path.into();
+        create_dir_all(path)
+    });
+    futures::future::join_all(creations).await.into_iter().collect()
+}
+
 /// Creates a folder at the specified path
 ///
 /// This function will error if any required parent folders do not exist.
@@ -190,6 +367,59 @@ pub async fn create_dir<P: Into<JsString>>(path: P) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Creates a uniquely named folder whose name is the specified prefix
+/// followed by random characters, and returns its path
+///
+/// The folder is not removed automatically. The caller is responsible for
+/// deleting it, e.g. with `remove_dir_all_safe`.
+pub async fn mkdtemp<P: Into<JsString>>(prefix: P) -> Result<Path, JsValue> {
+    let prefix: JsString = prefix.into();
+    let path = ffi::mkdtemp(&prefix).await?;
+    let path: JsString = path.dyn_into()?;
+    Ok(path.into())
+}
+
+/// A uniquely named temporary folder which is deleted when closed
+///
+/// Drop cannot be asynchronous, so `close` should be called once the folder is
+/// no longer needed. If a `TempDir` is dropped without being closed, deletion
+/// is scheduled in the background on a best-effort basis and any failure is
+/// ignored.
+#[derive(Debug)]
+pub struct TempDir {
+    path: Option<Path>,
+}
+
+impl TempDir {
+    /// Creates a temporary folder whose path starts with the specified prefix.
+    /// See `mkdtemp`.
+    pub async fn new<P: Into<JsString>>(prefix: P) -> Result<TempDir, JsValue> {
+        let path = mkdtemp(prefix).await?;
+        Ok(TempDir { path: Some(path) })
+    }
+
+    /// The path of the temporary folder
+    pub fn path(&self) -> &Path {
+        self.path.as_ref().expect("Temporary folder path missing")
+    }
+
+    /// Deletes the temporary folder and its contents
+    pub async fn close(mut self) -> Result<(), JsValue> {
+        let path = self.path.take().expect("Temporary folder path missing");
+        remove_dir_all_safe(&path).await
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            wasm_bindgen_futures::spawn_local(async move {
+                drop(remove_dir_all_safe(&path).await);
+            });
+        }
+    }
+}
+
 /// Deletes an empty folder at the specified path
 pub async fn remove_dir<P: Into<JsString>>(path: P) -> Result<(), JsValue> {
     let path: JsString = path.into();
@@ -204,6 +434,123 @@ pub async fn remove_file<P: Into<JsString>>(path: P) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Deletes the file or folder at the specified path
+///
+/// Non-empty folders can only be deleted if `recursive` is set. If `force` is
+/// set, a missing path is not an error. Symlinks are removed rather than
+/// followed.
+pub async fn rm<P: Into<JsString>>(path: P, recursive: bool, force: bool) -> Result<(), JsValue> {
+    let options = js_sys::Map::new();
+    options.set(&"recursive".into(), &recursive.into());
+    options.set(&"force".into(), &force.into());
+    let options = Object::from_entries(&options).expect("Failed to convert options map to object");
+    let path: JsString = path.into();
+    ffi::rm(&path, Some(options)).await?;
+    Ok(())
+}
+
+/// Options controlling how `cp` copies files and folders
+#[derive(Clone, Copy, Debug)]
+pub struct CpOptions {
+    recursive: bool,
+    force: bool,
+    preserve_timestamps: bool,
+    dereference: bool,
+}
+
+impl Default for CpOptions {
+    fn default() -> CpOptions {
+        CpOptions {
+            recursive: false,
+            force: true,
+            preserve_timestamps: false,
+            dereference: false,
+        }
+    }
+}
+
+impl CpOptions {
+    /// Copies folders recursively. Without this, copying a folder is an error.
+    pub fn recursive(&mut self, recursive: bool) -> &mut CpOptions {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Overwrites existing files at the destination
+    pub fn force(&mut self, force: bool) -> &mut CpOptions {
+        self.force = force;
+        self
+    }
+
+    /// Gives copied files the access and modification times of the originals
+    pub fn preserve_timestamps(&mut self, preserve_timestamps: bool) -> &mut CpOptions {
+        self.preserve_timestamps = preserve_timestamps;
+        self
+    }
+
+    /// Copies the targets of symlinks rather than the links themselves
+    pub fn dereference(&mut self, dereference: bool) -> &mut CpOptions {
+        self.dereference = dereference;
+        self
+    }
+
+    fn to_js_object(self) -> Object {
+        let options = js_sys::Map::new();
+        options.set(&"recursive".into(), &self.recursive.into());
+        options.set(&"force".into(), &self.force.into());
+        options.set(&"preserveTimestamps".into(), &self.preserve_timestamps.into());
+        options.set(&"dereference".into(), &self.dereference.into());
+        Object::from_entries(&options).expect("Failed to convert options map to object")
+    }
+}
+
+/// Copies the file or folder at `src` to `dst`
+pub async fn cp<P: Into<JsString>>(src: P, dst: P, options: CpOptions) -> Result<(), JsValue> {
+    let src: JsString = src.into();
+    let dst: JsString = dst.into();
+    ffi::cp(&src, &dst, Some(options.to_js_object())).await?;
+    Ok(())
+}
+
+/// Recursively deletes the folder at the specified path without following
+/// symlinks
+///
+/// Symlinks are unlinked rather than traversed, so the targets of any links
+/// inside the folder are left untouched. If the path itself is a symlink, only
+/// the link is removed.
+pub async fn remove_dir_all_safe<P: Into<Path>>(path: P) -> Result<(), JsValue> {
+    remove_path_without_following(&path.into()).await
+}
+
+#[async_recursion::async_recursion(?Send)]
+async fn remove_path_without_following(path: &Path) -> Result<(), JsValue> {
+    let file_type = symlink_metadata(path).await?.file_type();
+    if !file_type.is_dir() {
+        return remove_file(path).await;
+    }
+    for name in read_dir_names(path).await? {
+        remove_path_without_following(&path.join(name.as_str())).await?;
+    }
+    remove_dir(path).await
+}
+
+/// Creates a symlink at `path` which points to `target`
+pub async fn symlink<P: Into<JsString>>(target: P, path: P) -> Result<(), JsValue> {
+    let target: JsString = target.into();
+    let path: JsString = path.into();
+    ffi::symlink(&target, &path, None).await?;
+    Ok(())
+}
+
+/// Returns the absolute path of an existing file or folder with all symlinks
+/// resolved. Unlike `Path::resolve`, this accesses the filesystem.
+pub async fn canonicalize<P: Into<JsString>>(path: P) -> Result<Path, JsValue> {
+    let path: JsString = path.into();
+    let result = ffi::realpath(&path).await?;
+    let result: JsString = result.into();
+    Ok(Path::from(result))
+}
+
 /// Renames a file from one path to another
 pub async fn rename<P: Into<JsString>>(from: P, to: P) -> Result<(), JsValue> {
     let from: JsString = from.into();
@@ -212,6 +559,137 @@ pub async fn rename<P: Into<JsString>>(from: P, to: P) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// An open file which can be read incrementally
+///
+/// The underlying handle is not closed on drop since closing is asynchronous,
+/// so `close` should always be called once the file is no longer needed.
+#[derive(Debug)]
+pub struct File {
+    inner: ffi::FileHandle,
+    position: u64,
+}
+
+impl File {
+    /// Opens the file at the specified path for reading
+    pub async fn open<P: Into<JsString>>(path: P) -> Result<File, JsValue> {
+        let path: JsString = path.into();
+        let inner = ffi::open(&path, "r").await?.into();
+        Ok(File { inner, position: 0 })
+    }
+
+    /// Reads up to `buf.len()` bytes from the current position into `buf`,
+    /// returning the number of bytes read. A return value of zero indicates
+    /// the end of the file.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, JsValue> {
+        let length: u32 = buf
+            .len()
+            .try_into()
+            .map_err(|_| JsError::new("Read buffer too large"))?;
+        let array = Uint8Array::new_with_length(length);
+        #[allow(clippy::cast_precision_loss)]
+        let position = self.position as f64;
+        let result = self.inner.read(&array, 0, length, position).await?;
+        let bytes_read = js_sys::Reflect::get(&result, &"bytesRead".into())?
+            .as_f64()
+            .ok_or_else(|| JsError::new("read didn't return the number of bytes read"))?;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let bytes_read = bytes_read as u32;
+        array.subarray(0, bytes_read).copy_to(&mut buf[..bytes_read as usize]);
+        self.position += u64::from(bytes_read);
+        Ok(bytes_read as usize)
+    }
+
+    /// Moves the position that subsequent reads will start from, returning
+    /// the new position from the start of the file
+    pub async fn seek(&mut self, pos: std::io::SeekFrom) -> Result<u64, JsValue> {
+        use std::io::SeekFrom;
+
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::Current(offset) => (self.position, offset),
+            SeekFrom::End(offset) => (self.metadata().await?.len(), offset),
+        };
+        let position = base
+            .checked_add_signed(offset)
+            .ok_or_else(|| JsError::new("Invalid seek to a negative or overflowing position"))?;
+        self.position = position;
+        Ok(position)
+    }
+
+    /// Returns metadata about the open file
+    pub async fn metadata(&self) -> Result<Metadata, JsValue> {
+        let options = js_sys::Map::new();
+        options.set(&"bigint".into(), &true.into());
+        let options = Object::from_entries(&options).expect("Failed to convert options map to object");
+        let stats = self.inner.stat(Some(options)).await.map(Into::<ffi::Stats>::into)?;
+        Ok(Metadata { inner: stats })
+    }
+
+    /// Closes the file
+    pub async fn close(self) -> Result<(), JsValue> {
+        self.inner.close().await?;
+        Ok(())
+    }
+}
+
+/// The permission bits of a file
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Permissions {
+    mode: u32,
+}
+
+impl Permissions {
+    const EXECUTE_BITS: u32 = 0o111;
+    const PERMISSION_BITS: u32 = 0o7777;
+    const WRITE_BITS: u32 = 0o222;
+
+    /// Constructs permissions from Unix mode bits. Anything other than the
+    /// permission bits (e.g. the file type) is discarded.
+    pub fn from_mode(mode: u32) -> Permissions {
+        Permissions {
+            mode: mode & Self::PERMISSION_BITS,
+        }
+    }
+
+    /// The Unix permission bits
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// Replaces the Unix permission bits
+    pub fn set_mode(&mut self, mode: u32) {
+        *self = Self::from_mode(mode);
+    }
+
+    /// Returns `true` if no-one can write to the file
+    pub fn readonly(&self) -> bool {
+        self.mode & Self::WRITE_BITS == 0
+    }
+
+    /// Removes all write bits if `readonly` is `true`, otherwise sets them all
+    pub fn set_readonly(&mut self, readonly: bool) {
+        if readonly {
+            self.mode &= !Self::WRITE_BITS;
+        } else {
+            self.mode |= Self::WRITE_BITS;
+        }
+    }
+
+    /// Returns `true` if any of the execute bits are set
+    pub fn is_executable(&self) -> bool {
+        self.mode & Self::EXECUTE_BITS != 0
+    }
+}
+
+/// Changes the permissions of the specified path
+pub async fn set_permissions<P: Into<JsString>>(path: P, permissions: Permissions) -> Result<(), JsValue> {
+    let mode = u16::try_from(permissions.mode()).expect("Permission bits should fit in 16 bits");
+    chmod(path, mode).await
+}
+
 /// File metadata
 #[derive(Debug)]
 pub struct Metadata {
@@ -240,6 +718,12 @@ impl Metadata {
         self.inner.mode().try_into().expect("File mode too large")
     }
 
+    /// The permissions of the file
+    pub fn permissions(&self) -> Permissions {
+        #[allow(clippy::cast_possible_truncation)]
+        Permissions::from_mode(self.mode() as u32)
+    }
+
     fn utc_ns_to_time(ns: BigInt) -> DateTime<Utc> {
         const NS_IN_S: i128 = 1000 * 1000 * 1000;
         let ns = i128::try_from(ns).expect("Timestamp out of range");
@@ -289,6 +773,16 @@ impl Metadata {
     }
 }
 
+/// Returns metadata about the specified path, dereferencing symlinks
+pub async fn metadata<P: Into<JsString>>(path: P) -> Result<Metadata, JsValue> {
+    let path = path.into();
+    let options = js_sys::Map::new();
+    options.set(&"bigint".into(), &true.into());
+    let options = Object::from_entries(&options).expect("Failed to convert options map to object");
+    let stats = ffi::stat(&path, Some(options)).await.map(Into::<ffi::Stats>::into)?;
+    Ok(Metadata { inner: stats })
+}
+
 /// Returns metadata about the specified path, without dereferencing symlinks
 pub async fn symlink_metadata<P: Into<JsString>>(path: P) -> Result<Metadata, JsValue> {
     let path = path.into();
@@ -308,7 +802,20 @@ fn timestamp_to_seconds(timestamp: &DateTime<Utc>) -> f64 {
     whole + fractional
 }
 
-/// Sets the access and modification times of the file at the specified path
+/// Sets the access and modification times of the file at the specified path,
+/// dereferencing symlinks
+pub async fn utimes<P: Into<JsString>>(path: P, a_time: &DateTime<Utc>, m_time: &DateTime<Utc>) -> Result<(), JsValue> {
+    use js_sys::Number;
+
+    let path = path.into();
+    let a_time: Number = timestamp_to_seconds(a_time).into();
+    let m_time: Number = timestamp_to_seconds(m_time).into();
+    ffi::utimes(&path, a_time.as_ref(), m_time.as_ref()).await?;
+    Ok(())
+}
+
+/// Sets the access and modification times of the file at the specified path,
+/// without dereferencing symlinks
 pub async fn lutimes<P: Into<JsString>>(
     path: P,
     a_time: &DateTime<Utc>,
@@ -323,9 +830,101 @@ pub async fn lutimes<P: Into<JsString>>(
     Ok(())
 }
 
+/// Modes used to check the accessibility of a path. These can be combined
+/// with `|`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct AccessMode(u32);
+
+impl AccessMode {
+    /// The path can be executed (`X_OK`)
+    pub const EXECUTE: AccessMode = AccessMode(1);
+    /// The path is visible (`F_OK`)
+    pub const EXISTS: AccessMode = AccessMode(0);
+    /// The path can be read (`R_OK`)
+    pub const READ: AccessMode = AccessMode(4);
+    /// The path can be written (`W_OK`)
+    pub const WRITE: AccessMode = AccessMode(2);
+
+    /// The numeric value of the mode
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl std::ops::BitOr for AccessMode {
+    type Output = AccessMode;
+
+    fn bitor(self, rhs: AccessMode) -> AccessMode {
+        AccessMode(self.0 | rhs.0)
+    }
+}
+
+/// Checks that the path is accessible with the specified mode, returning an
+/// error if it is not
+pub async fn access<P: Into<JsString>>(path: P, mode: AccessMode) -> Result<(), JsValue> {
+    let path: JsString = path.into();
+    ffi::access(&path, Some(mode.bits())).await?;
+    Ok(())
+}
+
+/// Flags modifying how a file is copied. These can be combined with `|`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CopyFileFlags(u32);
+
+impl CopyFileFlags {
+    /// Attempt to create a copy-on-write reflink, falling back to a copy
+    /// (`COPYFILE_FICLONE`)
+    pub const CLONE: CopyFileFlags = CopyFileFlags(2);
+    /// Create a copy-on-write reflink or fail (`COPYFILE_FICLONE_FORCE`)
+    pub const CLONE_FORCE: CopyFileFlags = CopyFileFlags(4);
+    /// Fail if the destination already exists (`COPYFILE_EXCL`)
+    pub const EXCLUSIVE: CopyFileFlags = CopyFileFlags(1);
+
+    /// The numeric value of the flags
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl std::ops::BitOr for CopyFileFlags {
+    type Output = CopyFileFlags;
+
+    fn bitor(self, rhs: CopyFileFlags) -> CopyFileFlags {
+        CopyFileFlags(self.0 | rhs.0)
+    }
+}
+
+/// Values from `fs.constants`, read on first use
+///
+/// The values used by `AccessMode` and `CopyFileFlags` are the same on all
+/// platforms supported by node.js, so those types should normally be preferred.
+pub mod constants {
+    use lazy_static::lazy_static;
+
+    fn read(name: &str) -> u32 {
+        let value = js_sys::Reflect::get(&super::ffi::CONSTANTS, &name.into())
+            .ok()
+            .and_then(|value| value.as_f64())
+            .unwrap_or_else(|| panic!("fs.constants.{} was not a number", name));
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let value = value as u32;
+        value
+    }
+
+    lazy_static! {
+        pub static ref F_OK: u32 = read("F_OK");
+        pub static ref R_OK: u32 = read("R_OK");
+        pub static ref W_OK: u32 = read("W_OK");
+        pub static ref X_OK: u32 = read("X_OK");
+        pub static ref COPYFILE_EXCL: u32 = read("COPYFILE_EXCL");
+        pub static ref COPYFILE_FICLONE: u32 = read("COPYFILE_FICLONE");
+        pub static ref COPYFILE_FICLONE_FORCE: u32 = read("COPYFILE_FICLONE_FORCE");
+    }
+}
+
 /// Low-level bindings for node.js filesystem functions
 pub mod ffi {
-    use js_sys::{BigInt, JsString, Object};
+    use js_sys::{BigInt, JsString, Object, Uint8Array};
     use wasm_bindgen::prelude::*;
     use wasm_bindgen::JsValue;
 
@@ -354,10 +953,40 @@ pub mod ffi {
 
         #[wasm_bindgen(method, js_name = "isSymbolicLink")]
         pub fn is_symbolic_link(this: &FileType) -> bool;
+
+        #[derive(Debug)]
+        pub type FileHandle;
+
+        #[wasm_bindgen(catch, method)]
+        pub async fn read(
+            this: &FileHandle,
+            buffer: &Uint8Array,
+            offset: u32,
+            length: u32,
+            position: f64,
+        ) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch, method)]
+        pub async fn stat(this: &FileHandle, options: Option<Object>) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch, method)]
+        pub async fn close(this: &FileHandle) -> Result<JsValue, JsValue>;
+
+        #[derive(Debug)]
+        pub type Dir;
+
+        #[wasm_bindgen(catch, method)]
+        pub async fn read(this: &Dir) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch, method)]
+        pub async fn close(this: &Dir) -> Result<JsValue, JsValue>;
     }
 
     #[wasm_bindgen(module = "fs")]
     extern "C" {
+        #[wasm_bindgen(js_name = "constants")]
+        pub static CONSTANTS: Object;
+
         #[derive(Debug)]
         #[wasm_bindgen(js_name = "DirEnt", extends = FileType)]
         pub type DirEnt;
@@ -396,21 +1025,43 @@ pub mod ffi {
         #[wasm_bindgen(catch)]
         pub async fn chmod(path: &JsString, mode: u16) -> Result<JsValue, JsValue>;
 
+        #[wasm_bindgen(catch)]
+        pub async fn open(path: &JsString, flags: &str) -> Result<JsValue, JsValue>;
+
         #[wasm_bindgen(catch, js_name = "readFile")]
         pub async fn read_file(path: &JsString) -> Result<JsValue, JsValue>;
 
         #[wasm_bindgen(catch, js_name = "writeFile")]
         pub async fn write_file(path: &JsString, data: &[u8]) -> Result<JsValue, JsValue>;
 
+        #[wasm_bindgen(catch, js_name = "writeFile")]
+        pub async fn write_file_with_options(path: &JsString, data: &[u8], options: Object)
+            -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch, js_name = "appendFile")]
+        pub async fn append_file(path: &JsString, data: &[u8]) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch)]
+        pub async fn opendir(path: &JsString, options: Option<Object>) -> Result<JsValue, JsValue>;
+
         #[wasm_bindgen(catch, js_name = "readdir")]
         pub async fn read_dir(path: &JsString, options: Option<Object>) -> Result<JsValue, JsValue>;
 
         #[wasm_bindgen(catch)]
         pub async fn mkdir(path: &JsString, options: Option<Object>) -> Result<JsValue, JsValue>;
 
+        #[wasm_bindgen(catch)]
+        pub async fn mkdtemp(prefix: &JsString) -> Result<JsValue, JsValue>;
+
         #[wasm_bindgen(catch)]
         pub async fn rename(old: &JsString, new: &JsString) -> Result<JsValue, JsValue>;
 
+        #[wasm_bindgen(catch)]
+        pub async fn rm(path: &JsString, options: Option<Object>) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch)]
+        pub async fn cp(src: &JsString, dst: &JsString, options: Option<Object>) -> Result<JsValue, JsValue>;
+
         #[wasm_bindgen(catch)]
         pub async fn rmdir(path: &JsString, options: Option<Object>) -> Result<JsValue, JsValue>;
 
@@ -420,11 +1071,23 @@ pub mod ffi {
         #[wasm_bindgen(catch)]
         pub async fn lstat(path: &JsString, options: Option<Object>) -> Result<JsValue, JsValue>;
 
+        #[wasm_bindgen(catch)]
+        pub async fn stat(path: &JsString, options: Option<Object>) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch)]
+        pub async fn utimes(path: &JsString, atime: &JsValue, mtime: &JsValue) -> Result<JsValue, JsValue>;
+
         #[wasm_bindgen(catch)]
         pub async fn lutimes(path: &JsString, atime: &JsValue, mtime: &JsValue) -> Result<JsValue, JsValue>;
 
         #[wasm_bindgen(catch)]
         pub async fn unlink(path: &JsString) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch)]
+        pub async fn symlink(target: &JsString, path: &JsString, kind: Option<&str>) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(catch)]
+        pub async fn realpath(path: &JsString) -> Result<JsValue, JsValue>;
     }
 }
 
@@ -518,6 +1181,150 @@ mod test {
         Ok(())
     }
 
+    #[wasm_bindgen_test]
+    async fn write_file_atomically() -> Result<(), JsValue> {
+        let path = temp_path().with_extension("dat");
+        let temp = path.with_extension("tmp");
+        let good = b"complete".to_vec();
+        node::fs::write_file(&path, &good).await?;
+
+        // A partial temporary file left behind by a crash must not affect the target
+        node::fs::write_file(&temp, b"parti").await?;
+        assert_eq!(node::fs::read_file(&path).await?, good);
+
+        let updated = format!("{}", chrono::Local::now()).into_bytes();
+        node::fs::write_file_atomic(&path, &updated).await?;
+        assert_eq!(node::fs::read_file(&path).await?, updated);
+        assert!(!temp.exists().await);
+        node::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn open_read_seek_file() -> Result<(), JsValue> {
+        use std::io::SeekFrom;
+
+        let path = temp_path();
+        let data: Vec<u8> = (0..=255).collect();
+        node::fs::write_file(&path, &data).await?;
+        let mut file = node::fs::File::open(&path).await?;
+        let mut buffer = [0u8; 100];
+        let mut read_data = Vec::new();
+        loop {
+            let bytes_read = file.read(&mut buffer).await?;
+            if bytes_read == 0 {
+                break;
+            }
+            read_data.extend_from_slice(&buffer[..bytes_read]);
+        }
+        assert_eq!(read_data, data);
+        assert_eq!(file.seek(SeekFrom::Start(10)).await?, 10);
+        assert_eq!(file.read(&mut buffer[..1]).await?, 1);
+        assert_eq!(buffer[0], 10);
+        assert_eq!(file.seek(SeekFrom::Current(-6)).await?, 5);
+        assert_eq!(file.seek(SeekFrom::End(-1)).await?, 255);
+        assert_eq!(file.read(&mut buffer).await?, 1);
+        assert_eq!(buffer[0], 255);
+        assert_eq!(file.metadata().await?.len(), 256);
+        file.close().await?;
+        node::fs::remove_file(&path).await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    fn permissions() {
+        use super::Permissions;
+
+        let rw = Permissions::from_mode(0o100_644);
+        assert_eq!(rw.mode(), 0o644);
+        assert!(!rw.readonly());
+        assert!(!rw.is_executable());
+
+        let executable = Permissions::from_mode(0o755);
+        assert!(!executable.readonly());
+        assert!(executable.is_executable());
+
+        let mut readonly = Permissions::from_mode(0o444);
+        assert!(readonly.readonly());
+        assert!(!readonly.is_executable());
+        readonly.set_readonly(false);
+        assert_eq!(readonly.mode(), 0o666);
+        readonly.set_readonly(true);
+        assert_eq!(readonly.mode(), 0o444);
+        readonly.set_mode(0o40_755);
+        assert_eq!(readonly.mode(), 0o755);
+    }
+
+    #[wasm_bindgen_test]
+    async fn read_dir_as_stream() -> Result<(), JsValue> {
+        use futures::TryStreamExt as _;
+
+        const NUM_ENTRIES: usize = 64;
+
+        let root = temp_path();
+        node::fs::create_dir(&root).await?;
+        for index in 0..NUM_ENTRIES {
+            node::fs::write_file(&root.join(&format!("{}", index)), &[]).await?;
+        }
+        let stream = node::fs::read_dir_stream(&root).await?;
+        let entries: Vec<_> = stream.try_collect().await?;
+        let mut names: Vec<usize> = entries
+            .iter()
+            .map(|e| e.file_name().parse().expect("Unexpected file name"))
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, (0..NUM_ENTRIES).collect::<Vec<_>>());
+        for entry in entries {
+            assert!(entry.file_type().is_file());
+            node::fs::remove_file(&entry.path()).await?;
+        }
+        node::fs::remove_dir(&root).await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn read_dir_names_matches_read_dir() -> Result<(), JsValue> {
+        let root = temp_path();
+        node::fs::create_dir(&root).await?;
+        node::fs::create_dir(&root.join("folder")).await?;
+        for name in ["a", "b.txt", "with space"] {
+            node::fs::write_file(&root.join(name), &[]).await?;
+        }
+        let mut names = node::fs::read_dir_names(&root).await?;
+        names.sort();
+        let mut typed_names: Vec<String> = node::fs::read_dir(&root).await?.map(|e| e.file_name()).collect();
+        typed_names.sort();
+        assert_eq!(names, typed_names);
+        assert_eq!(names, ["a", "b.txt", "folder", "with space"]);
+        node::fs::remove_dir(&root.join("folder")).await?;
+        for name in ["a", "b.txt", "with space"] {
+            node::fs::remove_file(&root.join(name)).await?;
+        }
+        node::fs::remove_dir(&root).await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn dir_entry_metadata() -> Result<(), JsValue> {
+        let root = temp_path();
+        node::fs::create_dir(&root).await?;
+        let sizes = [0usize, 10, 100];
+        for (index, size) in sizes.iter().enumerate() {
+            node::fs::write_file(&root.join(&format!("{}", index)), &vec![0u8; *size]).await?;
+        }
+        let entries = node::fs::read_dir(&root).await?;
+        assert_eq!(entries.size_hint(), (sizes.len(), Some(sizes.len())));
+        for entry in entries {
+            let index: usize = entry.file_name().parse().expect("Unexpected file name");
+            let expected = sizes[index] as u64;
+            assert_eq!(entry.metadata().await?.len(), expected);
+            assert_eq!(entry.symlink_metadata().await?.len(), expected);
+            node::fs::remove_file(&entry.path()).await?;
+        }
+        node::fs::remove_dir(&root).await?;
+        Ok(())
+    }
+
     #[wasm_bindgen_test]
     async fn read_dir_and_lstat() -> Result<(), JsValue> {
         const NUM_ENTRIES: usize = 256;
@@ -603,6 +1410,33 @@ mod test {
 
     #[wasm_bindgen_test]
     async fn utimes() -> Result<(), JsValue> {
+        use chrono::TimeZone as _;
+
+        let max_delta = chrono::Duration::milliseconds(1);
+        let path = temp_path();
+        node::fs::write_file(&path, &[]).await?;
+
+        let new_atime = chrono::Utc
+            .timestamp_millis_opt(1_600_000_000_123)
+            .single()
+            .expect("Invalid timestamp");
+        let new_mtime = new_atime + chrono::Duration::milliseconds(4567);
+        node::fs::utimes(&path, &new_atime, &new_mtime).await?;
+
+        let new_metadata = node::fs::metadata(&path).await?;
+        for (expected, actual) in [
+            (new_atime, new_metadata.accessed()),
+            (new_mtime, new_metadata.modified()),
+        ] {
+            let delta = duration_abs(expected - actual);
+            assert!(delta < max_delta);
+        }
+        drop(node::fs::remove_file(&path).await);
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn lutimes() -> Result<(), JsValue> {
         let max_delta = chrono::Duration::seconds(2);
         let atime_change = chrono::Duration::seconds(64);
         let mtime_change = chrono::Duration::seconds(64);
@@ -627,4 +1461,188 @@ mod test {
         drop(node::fs::remove_file(&path).await);
         Ok(())
     }
+
+    #[wasm_bindgen_test]
+    async fn remove_dir_all_safe_does_not_follow_symlinks() -> Result<(), JsValue> {
+        let temp = node::os::temp_dir();
+        let suffix = get_random();
+        let outside = temp.join(&format!("ferrous-actions-safe-delete-target-{}", suffix));
+        let root = temp.join(&format!("ferrous-actions-safe-delete-{}", suffix));
+        node::fs::write_file(&outside, b"keep me").await?;
+        node::fs::create_dir_all(&root.join("nested")).await?;
+        node::fs::write_file(&root.join("nested").join("file"), b"delete me").await?;
+        node::fs::symlink(&outside, &root.join("nested").join("link")).await?;
+
+        node::fs::remove_dir_all_safe(&root).await?;
+        assert!(!root.exists().await);
+        assert_eq!(node::fs::read_file(&outside).await?, b"keep me");
+        node::fs::remove_file(&outside).await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn rm_files_and_folders() -> Result<(), JsValue> {
+        let root = node::os::temp_dir().join(&format!("ferrous-actions-rm-{}", get_random()));
+        let file = root.join("file");
+        node::fs::create_dir_all(&root.join("nested")).await?;
+        node::fs::write_file(&file, b"data").await?;
+        node::fs::write_file(&root.join("nested").join("file"), b"data").await?;
+
+        node::fs::rm(&file, false, false).await?;
+        assert!(!file.exists().await);
+        assert!(node::fs::rm(&root, false, false).await.is_err());
+        node::fs::rm(&root, true, false).await?;
+        assert!(!root.exists().await);
+
+        assert!(node::fs::rm(&root, true, false).await.is_err());
+        node::fs::rm(&root, true, true).await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn write_with_options() -> Result<(), JsValue> {
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-write-options-")).await?;
+        let path = temp_dir.path().join("credentials");
+
+        let mut options = node::fs::WriteOptions::default();
+        options.flag("wx").mode(0o600);
+        node::fs::write_file_with_options(&path, b"secret", &options).await?;
+        assert_eq!(node::fs::read_file(&path).await?, b"secret");
+        if node::os::platform() != "win32" {
+            let metadata = node::fs::metadata(&path).await?;
+            assert_eq!(metadata.mode() & 0o777, 0o600);
+        }
+
+        // The exclusive flag refuses to overwrite an existing file
+        assert!(node::fs::write_file_with_options(&path, b"other", &options)
+            .await
+            .is_err());
+        options.flag("a");
+        node::fs::write_file_with_options(&path, b"!", &options).await?;
+        assert_eq!(node::fs::read_file(&path).await?, b"secret!");
+        temp_dir.close().await
+    }
+
+    #[wasm_bindgen_test]
+    async fn cp_preserves_timestamps() -> Result<(), JsValue> {
+        use chrono::TimeZone as _;
+
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-cp-")).await?;
+        let src = temp_dir.path().join("src");
+        let dst = temp_dir.path().join("dst");
+        let nested = Path::from("nested").join("file");
+        node::fs::create_dir_all(&src.join("nested")).await?;
+        node::fs::write_file(&src.join(&nested), b"data").await?;
+        let mtime = chrono::Utc
+            .timestamp_millis_opt(1_600_000_000_000)
+            .single()
+            .expect("Invalid timestamp");
+        node::fs::utimes(&src.join(&nested), &mtime, &mtime).await?;
+
+        let mut options = node::fs::CpOptions::default();
+        assert!(node::fs::cp(&src, &dst, options).await.is_err());
+        options.recursive(true).preserve_timestamps(true);
+        node::fs::cp(&src, &dst, options).await?;
+        assert_eq!(node::fs::read_file(&dst.join(&nested)).await?, b"data");
+        let copied = node::fs::metadata(&dst.join(&nested)).await?;
+        assert!(duration_abs(copied.modified() - mtime) < chrono::Duration::milliseconds(1));
+        temp_dir.close().await
+    }
+
+    #[wasm_bindgen_test]
+    async fn join_resolve_and_canonicalize() -> Result<(), JsValue> {
+        let temp_dir = node::fs::TempDir::new(
+            node::fs::canonicalize(node::os::temp_dir())
+                .await?
+                .join("ferrous-actions-canonicalize-"),
+        )
+        .await?;
+        let root = temp_dir.path().clone();
+        let target = root.join("target");
+        let link = root.join("link");
+        node::fs::create_dir_all(&target).await?;
+        node::fs::symlink(&target, &link).await?;
+
+        let relative = link.relative_to(node::process::cwd());
+        assert!(!relative.is_absolute());
+        // Joining does not make a path absolute
+        assert!(!relative.join("file").is_absolute());
+        // Resolving makes a path absolute without following symlinks
+        assert_eq!(Path::resolve([&relative]).to_string(), link.to_string());
+        // Canonicalizing follows symlinks and requires the path to exist
+        assert_eq!(node::fs::canonicalize(&relative).await?.to_string(), target.to_string());
+        assert!(node::fs::canonicalize(&root.join("missing")).await.is_err());
+        temp_dir.close().await
+    }
+
+    #[wasm_bindgen_test]
+    async fn ensure_dirs_creates_nested_folders() -> Result<(), JsValue> {
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-ensure-dirs-")).await?;
+        let root = temp_dir.path().clone();
+        let paths = [
+            root.join("a").join("b"),
+            root.join("a").join("c"),
+            root.join("d").join("e"),
+        ];
+        node::fs::ensure_dirs(paths.iter()).await?;
+        for path in &paths {
+            assert!(node::fs::metadata(path).await?.is_directory());
+        }
+        // Creating folders that already exist is not an error
+        node::fs::ensure_dirs(paths.iter()).await?;
+        temp_dir.close().await
+    }
+
+    #[wasm_bindgen_test]
+    async fn mkdtemp_unique() -> Result<(), JsValue> {
+        let prefix = node::os::temp_dir().join("ferrous-actions-mkdtemp-");
+        let first = node::fs::mkdtemp(&prefix).await?;
+        let second = node::fs::mkdtemp(&prefix).await?;
+        assert_ne!(first.to_string(), second.to_string());
+        for path in [first, second] {
+            assert!(node::fs::metadata(&path).await?.is_directory());
+            assert!(path.file_name().starts_with("ferrous-actions-mkdtemp-"));
+            node::fs::remove_dir(&path).await?;
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn temp_dir_close_removes_contents() -> Result<(), JsValue> {
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-temp-dir-")).await?;
+        let path = temp_dir.path().clone();
+        node::fs::create_dir_all(&path.join("nested")).await?;
+        node::fs::write_file(&path.join("nested").join("file"), b"data").await?;
+        temp_dir.close().await?;
+        assert!(!path.exists().await);
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    fn constants_match_typed_values() {
+        use node::fs::{constants, AccessMode, CopyFileFlags};
+
+        assert_eq!(*constants::F_OK, 0);
+        assert_eq!(AccessMode::EXISTS.bits(), *constants::F_OK);
+        assert_eq!(AccessMode::READ.bits(), *constants::R_OK);
+        assert_eq!(AccessMode::WRITE.bits(), *constants::W_OK);
+        assert_eq!(AccessMode::EXECUTE.bits(), *constants::X_OK);
+        assert_eq!(CopyFileFlags::EXCLUSIVE.bits(), *constants::COPYFILE_EXCL);
+        assert_eq!(CopyFileFlags::CLONE.bits(), *constants::COPYFILE_FICLONE);
+        assert_eq!(CopyFileFlags::CLONE_FORCE.bits(), *constants::COPYFILE_FICLONE_FORCE);
+
+        let access_bits = [*constants::R_OK, *constants::W_OK, *constants::X_OK];
+        let copy_bits = [
+            *constants::COPYFILE_EXCL,
+            *constants::COPYFILE_FICLONE,
+            *constants::COPYFILE_FICLONE_FORCE,
+        ];
+        for bits in [access_bits, copy_bits] {
+            assert!(bits.iter().all(|b| b.count_ones() == 1));
+            assert_eq!(bits[0] & bits[1], 0);
+            assert_eq!(bits[0] & bits[2], 0);
+            assert_eq!(bits[1] & bits[2], 0);
+        }
+        assert_eq!((AccessMode::READ | AccessMode::WRITE).bits(), 6);
+    }
 }
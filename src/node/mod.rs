@@ -12,3 +12,6 @@ pub mod process;
 
 /// Streams
 pub mod stream;
+
+/// Time sources
+pub mod time;
@@ -2,8 +2,14 @@ use super::path;
 use js_sys::JsString;
 use lazy_static::lazy_static;
 use std::borrow::Cow;
+use std::collections::HashMap;
+use wasm_bindgen::JsValue;
 
 lazy_static! {
+    /// Generating a diagnostic report is slow, so the C library is only
+    /// detected once
+    static ref IS_MUSL: bool = detect_musl();
+
     static ref EOL: String = {
         use wasm_bindgen::JsCast as _;
         ffi::EOL
@@ -34,11 +40,128 @@ pub fn arch() -> String {
     ffi::arch().into()
 }
 
+/// Maps a node.js platform and architecture to the Rust target triple of the
+/// corresponding host
+fn host_triple(platform: &str, arch: &str, musl: bool) -> Option<&'static str> {
+    let triple = match (platform, arch, musl) {
+        ("darwin", "arm64", _) => "aarch64-apple-darwin",
+        ("darwin", "x64", _) => "x86_64-apple-darwin",
+        ("linux", "arm64", false) => "aarch64-unknown-linux-gnu",
+        ("linux", "arm64", true) => "aarch64-unknown-linux-musl",
+        ("linux", "ia32", false) => "i686-unknown-linux-gnu",
+        ("linux", "ia32", true) => "i686-unknown-linux-musl",
+        ("linux", "x64", false) => "x86_64-unknown-linux-gnu",
+        ("linux", "x64", true) => "x86_64-unknown-linux-musl",
+        ("win32", "arm64", _) => "aarch64-pc-windows-msvc",
+        ("win32", "ia32", _) => "i686-pc-windows-msvc",
+        ("win32", "x64", _) => "x86_64-pc-windows-msvc",
+        _ => return None,
+    };
+    Some(triple)
+}
+
+/// Determines whether the C library is musl rather than glibc. This uses the
+/// same heuristic as the `detect-libc` package: node.js only reports a glibc
+/// version when running against glibc.
+fn detect_musl() -> bool {
+    use js_sys::{Function, Reflect};
+    use wasm_bindgen::JsCast as _;
+
+    let uses_glibc = || -> Option<bool> {
+        let process = Reflect::get(&js_sys::global(), &"process".into()).ok()?;
+        let report = Reflect::get(&process, &"report".into()).ok()?;
+        let get_report: Function = Reflect::get(&report, &"getReport".into()).ok()?.dyn_into().ok()?;
+        let report = get_report.call0(&report).ok()?;
+        let header = Reflect::get(&report, &"header".into()).ok()?;
+        let glibc_version = Reflect::get(&header, &"glibcVersionRuntime".into()).ok()?;
+        Some(!glibc_version.is_undefined())
+    };
+    platform() == "linux" && uses_glibc() == Some(false)
+}
+
+/// The Rust target triple of the host, derived from the platform and
+/// architecture, or `None` if there is no known triple. On Linux, musl is
+/// detected heuristically.
+pub fn detect_host_triple() -> Option<&'static str> {
+    host_triple(&platform(), &arch(), *IS_MUSL)
+}
+
 /// Path to the current user's home directory
 pub fn homedir() -> path::Path {
     path::Path::from(ffi::homedir())
 }
 
+/// Path to the current user's home directory, returning an error if it cannot
+/// be determined
+pub fn try_homedir() -> Result<path::Path, JsValue> {
+    ffi::try_homedir().map(path::Path::from)
+}
+
+/// The address family of a network interface address
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AddressFamily {
+    IPv4,
+    IPv6,
+}
+
+/// An address assigned to a network interface
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NetworkInterface {
+    /// The assigned IP address
+    pub address: String,
+
+    /// The address family, or `None` if node.js reported one that is not
+    /// recognised
+    pub family: Option<AddressFamily>,
+
+    /// Whether the interface is a loopback or similar interface which is not
+    /// remotely accessible
+    pub internal: bool,
+
+    /// The MAC address of the interface
+    pub mac: String,
+}
+
+impl From<&ffi::NetworkInterfaceInfo> for NetworkInterface {
+    fn from(info: &ffi::NetworkInterfaceInfo) -> NetworkInterface {
+        // Node.js 18.0 to 18.3 reported the family as a number
+        let family = info.family();
+        let family = family
+            .as_string()
+            .or_else(|| family.as_f64().map(|version| format!("IPv{}", version)));
+        let family = match family.as_deref() {
+            Some("IPv4") => Some(AddressFamily::IPv4),
+            Some("IPv6") => Some(AddressFamily::IPv6),
+            _ => None,
+        };
+        NetworkInterface {
+            address: info.address().into(),
+            family,
+            internal: info.internal(),
+            mac: info.mac().into(),
+        }
+    }
+}
+
+/// The addresses assigned to each network interface, keyed by interface name
+pub fn network_interfaces() -> HashMap<String, Vec<NetworkInterface>> {
+    use wasm_bindgen::JsCast as _;
+
+    js_sys::Object::entries(&ffi::network_interfaces())
+        .iter()
+        .filter_map(|entry| {
+            let entry: js_sys::Array = entry.into();
+            let name = entry.get(0).as_string()?;
+            let addresses: js_sys::Array = entry.get(1).dyn_into().ok()?;
+            let addresses = addresses
+                .iter()
+                .map(|address| NetworkInterface::from(&ffi::NetworkInterfaceInfo::from(address)))
+                .collect();
+            Some((name, addresses))
+        })
+        .collect()
+}
+
 /// Path to the temporary directory
 pub fn temp_dir() -> path::Path {
     path::Path::from(ffi::tmpdir())
@@ -56,9 +179,33 @@ pub mod ffi {
 
         pub fn arch() -> JsString;
         pub fn homedir() -> JsString;
+
+        #[wasm_bindgen(catch, js_name = "homedir")]
+        pub fn try_homedir() -> Result<JsString, JsValue>;
         pub fn machine() -> JsString;
         pub fn platform() -> JsString;
         pub fn tmpdir() -> JsString;
+
+        #[wasm_bindgen(js_name = "networkInterfaces")]
+        pub fn network_interfaces() -> Object;
+    }
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[derive(Debug)]
+        pub type NetworkInterfaceInfo;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn address(this: &NetworkInterfaceInfo) -> JsString;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn family(this: &NetworkInterfaceInfo) -> JsValue;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn internal(this: &NetworkInterfaceInfo) -> bool;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn mac(this: &NetworkInterfaceInfo) -> JsString;
     }
 }
 
@@ -86,8 +233,39 @@ mod test {
         super::platform();
     }
 
+    #[wasm_bindgen_test]
+    fn host_triples() {
+        for (platform, arch, musl, expected) in [
+            ("darwin", "x64", false, Some("x86_64-apple-darwin")),
+            ("darwin", "arm64", false, Some("aarch64-apple-darwin")),
+            ("linux", "x64", false, Some("x86_64-unknown-linux-gnu")),
+            ("linux", "x64", true, Some("x86_64-unknown-linux-musl")),
+            ("linux", "arm64", false, Some("aarch64-unknown-linux-gnu")),
+            ("linux", "arm64", true, Some("aarch64-unknown-linux-musl")),
+            ("win32", "x64", false, Some("x86_64-pc-windows-msvc")),
+            ("win32", "arm64", false, Some("aarch64-pc-windows-msvc")),
+            ("aix", "ppc64", false, None),
+            ("windows", "x64", false, None),
+        ] {
+            assert_eq!(super::host_triple(platform, arch, musl), expected);
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn invoke_detect_host_triple() {
+        assert_eq!(super::detect_host_triple(), super::detect_host_triple());
+    }
+
     #[wasm_bindgen_test]
     fn invoke_temp_dir() {
         super::temp_dir();
     }
+
+    #[wasm_bindgen_test]
+    fn loopback_interface_present() {
+        let interfaces = super::network_interfaces();
+        assert!(interfaces.values().flatten().any(|interface| interface.internal
+            && (interface.address == "127.0.0.1" || interface.address == "::1")
+            && interface.family.is_some()));
+    }
 }
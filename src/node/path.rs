@@ -63,10 +63,37 @@ impl Path {
     }
 
     /// Returns the path as a JavaScript string
+    ///
+    /// This clones the handle to the underlying string. Use `as_js_string` to
+    /// borrow it instead.
     pub fn to_js_string(&self) -> JsString {
         self.inner.clone()
     }
 
+    /// Borrows the path as a JavaScript string without cloning
+    pub fn as_js_string(&self) -> &JsString {
+        &self.inner
+    }
+
+    /// Converts the path to a Rust string, replacing any unpaired UTF-16
+    /// surrogates with U+FFFD.
+    ///
+    /// The path is stored as a JavaScript string, so this always allocates, as
+    /// does formatting the path with `Display`.
+    pub fn to_string_lossy(&self) -> String {
+        char::decode_utf16(self.inner.iter())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    /// Returns the path with forward slashes as separators and a lowercase
+    /// drive letter, so that the same logical path has the same form on every
+    /// platform. This is intended for cache keys and serialized metadata, not
+    /// for filesystem calls.
+    pub fn normalize_separators(&self) -> String {
+        normalize_separators(&self.to_string_lossy())
+    }
+
     /// Returns the parent path
     #[must_use]
     pub fn parent(&self) -> Path {
@@ -85,6 +112,23 @@ impl Path {
         result.into()
     }
 
+    /// Returns a copy of this path with the extension of the final component
+    /// replaced by the supplied one (or added if there was none)
+    #[must_use]
+    pub fn with_extension(&self, extension: &str) -> Path {
+        let file_name = self.file_name();
+        let stem = match file_name.rfind('.') {
+            Some(0) | None => file_name.as_str(),
+            Some(idx) => &file_name[..idx],
+        };
+        let file_name = if extension.is_empty() {
+            stem.to_string()
+        } else {
+            format!("{}.{}", stem, extension)
+        };
+        self.parent().join(&file_name)
+    }
+
     /// Returns `true` if the path can be determined to exist
     pub async fn exists(&self) -> bool {
         super::fs::ffi::access(&self.inner, None).await.is_ok()
@@ -98,6 +142,81 @@ impl Path {
         result
     }
 
+    /// Combines this path with each of the supplied parts in turn. See
+    /// `join_all`.
+    ///
+    /// As with `push`, an absolute part replaces everything before it, whereas
+    /// node.js's `path.join` would simply append it. Unlike `path.resolve`, a
+    /// relative result is not made absolute using the current directory.
+    #[must_use]
+    pub fn join_iter<I, P>(&self, parts: I) -> Path
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Path>,
+    {
+        join_all(self, parts)
+    }
+
+    /// Resolves a sequence of paths into an absolute path, as node.js's
+    /// `path.resolve` does. Parts are processed from right to left until an
+    /// absolute path is formed, and the current directory is used if none is.
+    ///
+    /// This does not access the filesystem, so symlinks are not followed and
+    /// the path need not exist. In contrast, `join` leaves relative paths
+    /// relative, and `node::fs::canonicalize` follows symlinks but requires the
+    /// path to exist.
+    pub fn resolve<I, P>(parts: I) -> Path
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Path>,
+    {
+        let parts = parts.into_iter().map(|part| part.into().inner).collect();
+        Path {
+            inner: ffi::resolve(parts),
+        }
+    }
+
+    /// Resolves the supplied path against this one. See `resolve`.
+    #[must_use]
+    pub fn resolve_from<P: Into<Path>>(&self, part: P) -> Path {
+        Self::resolve([self.clone(), part.into()])
+    }
+
+    /// Returns the normalized components of this path. Empty and `.`
+    /// components are omitted.
+    pub fn components(&self) -> impl Iterator<Item = String> {
+        let normalized = String::from(ffi::normalize(&self.inner));
+        normalized
+            .split(SEPARATOR.as_str())
+            .filter(|c| !c.is_empty() && *c != ".")
+            .map(String::from)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Returns `true` if the leading components of this path are the
+    /// components of `prefix`. Matching is by whole component, so `foobar/baz`
+    /// does not start with `foo`.
+    pub fn starts_with<P: Into<Path>>(&self, prefix: P) -> bool {
+        let components: Vec<String> = self.components().collect();
+        let prefix: Vec<String> = prefix.into().components().collect();
+        components.starts_with(&prefix)
+    }
+
+    /// Returns `true` if the trailing components of this path are the
+    /// components of `suffix`. Matching is by whole component, so `foo/barbaz`
+    /// does not end with `baz`. An absolute suffix only matches the whole
+    /// path.
+    pub fn ends_with<P: Into<Path>>(&self, suffix: P) -> bool {
+        let suffix = suffix.into();
+        if suffix.is_absolute() {
+            return *self == suffix;
+        }
+        let components: Vec<String> = self.components().collect();
+        let suffix: Vec<String> = suffix.components().collect();
+        components.ends_with(&suffix)
+    }
+
     /// Returns this path relative to the supplied path
     #[must_use]
     pub fn relative_to<P: Into<Path>>(&self, path: P) -> Path {
@@ -162,6 +281,38 @@ impl From<&Path> for JsString {
     }
 }
 
+/// Converts backslashes to forward slashes and lowercases a leading drive
+/// letter
+fn normalize_separators(path: &str) -> String {
+    let mut result = path.replace('\\', "/");
+    let bytes = result.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        result[..1].make_ascii_lowercase();
+    }
+    result
+}
+
+/// Joins a sequence of paths onto a base path using a single call to the
+/// underlying join function. As with `push`, an absolute path replaces
+/// everything before it.
+pub fn join_all<I, P>(base: &Path, parts: I) -> Path
+where
+    I: IntoIterator<Item = P>,
+    P: Into<Path>,
+{
+    let mut components = vec![base.inner.clone()];
+    for part in parts {
+        let part = part.into();
+        if part.is_absolute() {
+            components.clear();
+        }
+        components.push(part.inner);
+    }
+    Path {
+        inner: ffi::join(components),
+    }
+}
+
 /// Returns the delimiter used to combined paths into a list
 pub fn delimiter() -> Cow<'static, str> {
     DELIMITER.as_str().into()
@@ -208,6 +359,38 @@ mod test {
     use wasm_bindgen::JsValue;
     use wasm_bindgen_test::wasm_bindgen_test;
 
+    #[wasm_bindgen_test]
+    fn string_conversions() {
+        use js_sys::JsString;
+
+        let path = Path::from("foo").join("bar");
+        assert_eq!(path.to_string_lossy(), path.to_string());
+        assert_eq!(path.as_js_string(), &path.to_js_string());
+
+        let unpaired = JsString::from_char_code(&[u16::from(b'a'), 0xd800]);
+        let path = Path::from(unpaired);
+        assert_eq!(path.to_string_lossy(), "a\u{fffd}");
+    }
+
+    #[wasm_bindgen_test]
+    fn separator_normalization() {
+        use super::normalize_separators;
+
+        assert_eq!(
+            normalize_separators("C:\\Users\\runner\\.cargo"),
+            "c:/Users/runner/.cargo"
+        );
+        assert_eq!(normalize_separators("c:/Users/runner/.cargo"), "c:/Users/runner/.cargo");
+        assert_eq!(normalize_separators("/home/runner/.cargo"), "/home/runner/.cargo");
+        assert_eq!(
+            normalize_separators("target\\debug"),
+            normalize_separators("target/debug")
+        );
+
+        let path = Path::from("foo").join("bar");
+        assert_eq!(path.normalize_separators(), "foo/bar");
+    }
+
     #[wasm_bindgen_test]
     fn check_absolute() {
         let cwd = node::process::cwd();
@@ -249,18 +432,112 @@ mod test {
         assert_eq!(child_name, path.file_name());
         assert_eq!(
             child_name,
-            String::from(super::ffi::basename(&path.to_js_string(), None))
+            String::from(super::ffi::basename(path.as_js_string(), None))
         );
         assert_eq!(
             child_name,
-            String::from(super::ffi::basename(&path.to_js_string(), Some(".nomatch".into())))
+            String::from(super::ffi::basename(path.as_js_string(), Some(".nomatch".into())))
         );
         assert_eq!(
             child_base,
-            String::from(super::ffi::basename(&path.to_js_string(), Some(child_ext.into())))
+            String::from(super::ffi::basename(path.as_js_string(), Some(child_ext.into())))
         );
     }
 
+    #[wasm_bindgen_test]
+    fn check_with_extension() {
+        let parent = Path::from("parent");
+        assert_eq!(parent.join("a.b").with_extension("tmp"), parent.join("a.tmp"));
+        assert_eq!(parent.join("a").with_extension("tmp"), parent.join("a.tmp"));
+        assert_eq!(parent.join(".a").with_extension("tmp"), parent.join(".a.tmp"));
+        assert_eq!(parent.join("a.b.c").with_extension(""), parent.join("a.b"));
+    }
+
+    #[wasm_bindgen_test]
+    fn check_join_all() {
+        let base = Path::from("base");
+        let mut expected = base.clone();
+        for part in ["a", "b", "c"] {
+            expected.push(part);
+        }
+        let joined = super::join_all(&base, ["a", "b", "c"]);
+        assert_eq!(joined.to_string(), expected.to_string());
+
+        let absolute = node::process::cwd();
+        let joined = super::join_all(&base, [absolute.clone(), Path::from("d")]);
+        assert_eq!(joined, absolute.join("d"));
+    }
+
+    #[wasm_bindgen_test]
+    fn check_components() {
+        let path = Path::from("a").join("b").join(".").join("c");
+        assert_eq!(path.components().collect::<Vec<_>>(), ["a", "b", "c"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn check_join_iter() {
+        let base = Path::from("base");
+        let joined = base.join_iter(["registry", "index", "github.com-1ecc6299db9ec823"]);
+        assert_eq!(
+            joined,
+            base.join("registry").join("index").join("github.com-1ecc6299db9ec823")
+        );
+        assert!(!joined.is_absolute());
+
+        // An absolute part replaces the preceding ones, as it would with
+        // `resolve`, but a relative result stays relative
+        let absolute = node::process::cwd().join("absolute");
+        let joined = base.join_iter([absolute.clone(), Path::from("a")]);
+        assert_eq!(joined, absolute.join("a"));
+        let resolved = base.resolve_from("a");
+        assert!(resolved.is_absolute());
+        assert_eq!(resolved, node::process::cwd().join(base.join_iter(["a"])));
+    }
+
+    #[wasm_bindgen_test]
+    fn check_resolve() {
+        let cwd = node::process::cwd();
+        let relative = Path::from("a").join("b");
+        assert_eq!(Path::resolve([&relative]), cwd.join(&relative));
+        assert_eq!(Path::resolve(["a", "b"]), cwd.join(&relative));
+        assert_eq!(Path::from("a").resolve_from("b"), cwd.join(&relative));
+        let absolute = cwd.join("x");
+        assert_eq!(Path::from("a").resolve_from(&absolute), absolute);
+    }
+
+    #[wasm_bindgen_test]
+    fn check_starts_with() {
+        let path = Path::from("foo").join("bar").join("baz");
+        assert!(path.starts_with("foo"));
+        assert!(path.starts_with(Path::from("foo").join("bar")));
+        assert!(path.starts_with(&path));
+        assert!(!path.starts_with("fo"));
+        assert!(!path.starts_with("bar"));
+    }
+
+    #[wasm_bindgen_test]
+    fn check_ends_with() {
+        let path = Path::from("home").join("registry").join("cache");
+        assert!(path.ends_with("cache"));
+        assert!(path.ends_with(Path::from("registry").join("cache")));
+        assert!(path.ends_with(&path));
+        assert!(!path.ends_with("registry"));
+        assert!(!path.ends_with(Path::from("other").join("registry").join("cache")));
+
+        let absolute = Path::from("/home/registry/cache");
+        assert!(absolute.ends_with(&absolute));
+        assert!(absolute.ends_with(Path::from("registry").join("cache")));
+        assert!(!absolute.ends_with(Path::from("/registry/cache")));
+        assert!(!path.ends_with(Path::from("/home/registry/cache")));
+    }
+
+    #[wasm_bindgen_test]
+    fn check_ends_with_whole_components() {
+        let path = Path::from("foo").join("barbaz");
+        assert!(!path.ends_with("baz"));
+        assert!(!path.ends_with(Path::from("o").join("barbaz")));
+    }
+
     #[wasm_bindgen_test]
     fn check_push() {
         let parent_name = "a";
@@ -1,4 +1,5 @@
 use super::path::{self, Path};
+use js_sys::Object;
 use std::collections::HashMap;
 use wasm_bindgen::JsValue;
 
@@ -8,6 +9,9 @@ pub fn cwd() -> path::Path {
 }
 
 /// Returns a map of all environment variables defined for the process
+///
+/// The environment may contain secrets, so it should only ever be logged at
+/// debug level.
 pub fn get_env() -> HashMap<String, String> {
     use js_sys::JsString;
     use wasm_bindgen::JsCast as _;
@@ -45,13 +49,124 @@ pub fn remove_var(name: &str) {
     js_sys::Reflect::delete_property(&ffi::ENV, &name.into()).expect("process.env wasn't an object");
 }
 
+/// Sets the exit code the process will use when it exits normally
+pub fn set_exit_code(code: i32) {
+    let process = js_sys::Reflect::get(&js_sys::global(), &"process".into()).expect("Unable to find process object");
+    js_sys::Reflect::set(&process, &"exitCode".into(), &code.into()).expect("Failed to set process.exitCode");
+}
+
 /// Changes the current working directory to the specified path
 pub fn chdir<P: Into<Path>>(path: P) -> Result<(), JsValue> {
     let path = path.into();
-    ffi::chdir(&path.to_js_string())?;
+    ffi::chdir(path.as_js_string())?;
     Ok(())
 }
 
+/// The name of the underlying platform. This is the same as
+/// `node::os::platform`.
+pub fn platform() -> String {
+    String::from(&*ffi::PLATFORM)
+}
+
+/// The architecture the process is running on. This is the same as
+/// `node::os::arch`.
+pub fn arch() -> String {
+    String::from(&*ffi::ARCH)
+}
+
+/// The version of node.js the process is running on, including a leading `v`
+pub fn node_version() -> String {
+    String::from(&*ffi::VERSION)
+}
+
+/// The versions of node.js and its dependencies (e.g. `v8`), keyed by
+/// component name. Versions do not have a leading `v`.
+pub fn versions() -> HashMap<String, String> {
+    Object::entries(&ffi::VERSIONS)
+        .iter()
+        .filter_map(|entry| {
+            let entry: js_sys::Array = entry.into();
+            let name = entry.get(0).as_string()?;
+            let version = entry.get(1).as_string()?;
+            Some((name, version))
+        })
+        .collect()
+}
+
+/// Memory usage of the process in bytes
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MemoryUsage {
+    /// Resident set size
+    pub rss: u64,
+
+    /// Total size of the V8 heap
+    pub heap_total: u64,
+
+    /// Portion of the V8 heap in use
+    pub heap_used: u64,
+
+    /// Memory used by C++ objects bound to JavaScript objects
+    pub external: u64,
+}
+
+/// Returns the memory usage of the process
+pub fn memory_usage() -> MemoryUsage {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let to_bytes = |value: f64| value as u64;
+
+    let usage = ffi::memory_usage();
+    MemoryUsage {
+        rss: to_bytes(usage.rss()),
+        heap_total: to_bytes(usage.heap_total()),
+        heap_used: to_bytes(usage.heap_used()),
+        external: to_bytes(usage.external()),
+    }
+}
+
+/// Sets environment variables and restores their original values when it is
+/// dropped, so a test which fails part way through does not leak them
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct ScopedEnv {
+    original: Vec<(String, Option<String>)>,
+}
+
+#[cfg(test)]
+impl ScopedEnv {
+    /// Sets `name` to `value` until the `ScopedEnv` is dropped
+    pub fn set(&mut self, name: &str, value: &str) -> &mut ScopedEnv {
+        self.remember(name);
+        set_var(name, value);
+        self
+    }
+
+    /// Removes `name` until the `ScopedEnv` is dropped
+    pub fn remove(&mut self, name: &str) -> &mut ScopedEnv {
+        self.remember(name);
+        remove_var(name);
+        self
+    }
+
+    fn remember(&mut self, name: &str) {
+        if self.original.iter().all(|(existing, _)| existing != name) {
+            let value = get_env().get(name).cloned();
+            self.original.push((name.to_string(), value));
+        }
+    }
+}
+
+#[cfg(test)]
+impl Drop for ScopedEnv {
+    fn drop(&mut self) {
+        for (name, value) in self.original.drain(..).rev() {
+            match value {
+                Some(value) => set_var(&name, &value),
+                None => remove_var(&name),
+            }
+        }
+    }
+}
+
 /// Low-level bindings for node.js process functions and variables
 pub mod ffi {
     use js_sys::{JsString, Object};
@@ -62,10 +177,43 @@ pub mod ffi {
         #[wasm_bindgen(js_name = "env")]
         pub static ENV: Object;
 
+        #[wasm_bindgen(js_name = "platform")]
+        pub static PLATFORM: JsString;
+
+        #[wasm_bindgen(js_name = "arch")]
+        pub static ARCH: JsString;
+
+        #[wasm_bindgen(js_name = "version")]
+        pub static VERSION: JsString;
+
+        #[wasm_bindgen(js_name = "versions")]
+        pub static VERSIONS: Object;
+
         pub fn cwd() -> JsString;
 
         #[wasm_bindgen(catch)]
         pub fn chdir(path: &JsString) -> Result<JsValue, JsValue>;
+
+        #[wasm_bindgen(js_name = "memoryUsage")]
+        pub fn memory_usage() -> MemoryUsage;
+    }
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[derive(Debug)]
+        pub type MemoryUsage;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn rss(this: &MemoryUsage) -> f64;
+
+        #[wasm_bindgen(method, getter, js_name = "heapTotal")]
+        pub fn heap_total(this: &MemoryUsage) -> f64;
+
+        #[wasm_bindgen(method, getter, js_name = "heapUsed")]
+        pub fn heap_used(this: &MemoryUsage) -> f64;
+
+        #[wasm_bindgen(method, getter)]
+        pub fn external(this: &MemoryUsage) -> f64;
     }
 }
 
@@ -83,4 +231,41 @@ mod test {
         let cwd = super::cwd();
         assert!(cwd.exists().await);
     }
+
+    #[wasm_bindgen_test]
+    fn platform_and_arch_match_os() {
+        use crate::node;
+
+        assert_eq!(super::platform(), node::os::platform());
+        assert_eq!(super::arch(), node::os::arch());
+    }
+
+    #[wasm_bindgen_test]
+    fn memory_usage_nonzero() {
+        let usage = super::memory_usage();
+        assert!(usage.rss > 0);
+        assert!(usage.heap_total > 0);
+        assert!(usage.heap_used > 0);
+        assert!(usage.external > 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn scoped_env_restores_variables() {
+        use crate::nonce;
+
+        let set = format!("FERROUS_ACTIONS_TEST_{}", nonce::build(8).to_string().to_uppercase());
+        let removed = format!("{}_REMOVED", set);
+        super::set_var(&removed, "original");
+        {
+            let mut env = super::ScopedEnv::default();
+            env.set(&set, "first").set(&set, "second").remove(&removed);
+            let vars = super::get_env();
+            assert_eq!(vars.get(&set).map(String::as_str), Some("second"));
+            assert!(!vars.contains_key(&removed));
+        }
+        let vars = super::get_env();
+        super::remove_var(&removed);
+        assert!(!vars.contains_key(&set));
+        assert_eq!(vars.get(&removed).map(String::as_str), Some("original"));
+    }
 }
@@ -0,0 +1,56 @@
+/// The current wall-clock time in milliseconds since the Unix epoch, as
+/// returned by `Date.now()`.
+///
+/// This is suitable for timestamps but not for measuring durations since the
+/// system clock may be adjusted. Use `monotonic_ms` for durations.
+pub fn now_ms() -> f64 {
+    ffi::date_now()
+}
+
+/// A monotonic time in milliseconds relative to an arbitrary origin, as
+/// returned by `performance.now()`.
+///
+/// This is unaffected by adjustments to the system clock so the difference of
+/// two values is suitable for measuring durations. The values themselves have
+/// no meaning as timestamps.
+pub fn monotonic_ms() -> f64 {
+    ffi::performance_now()
+}
+
+/// Low-level bindings for node.js time functions
+pub mod ffi {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = Date, js_name = "now")]
+        pub fn date_now() -> f64;
+    }
+
+    #[wasm_bindgen(module = "perf_hooks")]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance, js_name = "now")]
+        pub fn performance_now() -> f64;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{monotonic_ms, now_ms};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn monotonic_time_is_non_decreasing() {
+        let first = monotonic_ms();
+        let second = monotonic_ms();
+        assert!(second >= first);
+    }
+
+    #[wasm_bindgen_test]
+    fn wall_clock_time() {
+        // 2020-01-01T00:00:00Z
+        const START_OF_2020_MS: f64 = 1_577_836_800_000.0;
+
+        assert!(now_ms() > START_OF_2020_MS);
+    }
+}
@@ -1,22 +1,45 @@
-use crate::cache_cargo_home::{restore_cargo_cache, save_cargo_cache};
+use crate::cache_cargo_home::{remove_cached_folder_info, restore_cargo_cache, save_cargo_cache};
 use crate::cross::Cross;
-use crate::input_manager::{Input, Manager as InputManager};
-use crate::rustup::{self, ToolchainConfig};
-use crate::{node, toolchain, warning, Cargo, Error};
+use crate::input_manager::{parse_optional_input, Input, Manager as InputManager};
+use crate::node::path::Path;
+use crate::rustup::{self, Profile, ToolchainConfig};
+use crate::{
+    action_paths, cargo, debug, error, github, info, node, toolchain, toolchain_file, versions, warning, wasm_opt,
+    workflow, Cargo, Error,
+};
+use std::collections::HashMap;
+use std::str::FromStr as _;
 
-fn get_toolchain_config(input_manager: &InputManager) -> Result<ToolchainConfig, Error> {
+fn parse_list(value: &str) -> Vec<String> {
+    value.split_whitespace().map(String::from).collect()
+}
+
+fn parse_profile(value: &str) -> Result<Profile, Error> {
+    Profile::from_str(value).map_err(|_| Error::ParseProfile(value.to_string()))
+}
+
+async fn get_toolchain_config(input_manager: &InputManager) -> Result<ToolchainConfig, Error> {
     let mut toolchain_config = ToolchainConfig::default();
     if let Some(toolchain) = input_manager.get(Input::Toolchain) {
         toolchain_config.name = toolchain.into();
+    } else if let Some((path, toolchain_file)) = toolchain_file::find_and_parse(&node::process::cwd()).await? {
+        // Explicitly specified inputs below take precedence over the file
+        info!("Using toolchain specified in {}", path);
+        toolchain_config.name = toolchain_file.channel;
+        if let Some(profile) = toolchain_file.profile {
+            toolchain_config.profile = parse_profile(&profile)?;
+        }
+        toolchain_config.components = toolchain_file.components;
+        toolchain_config.targets = toolchain_file.targets;
     }
     if let Some(profile) = input_manager.get(Input::Profile) {
-        toolchain_config.profile = profile.into();
+        toolchain_config.profile = parse_profile(profile)?;
     }
     if let Some(components) = input_manager.get(Input::Components) {
-        toolchain_config.components = components.split_whitespace().map(String::from).collect();
+        toolchain_config.components = parse_list(components);
     }
     if let Some(targets) = input_manager.get(Input::Targets) {
-        toolchain_config.targets = targets.split_whitespace().map(String::from).collect();
+        toolchain_config.targets = parse_list(targets);
     }
     if let Some(set_default) = input_manager.get(Input::Default) {
         let set_default = set_default
@@ -33,23 +56,69 @@ fn get_toolchain_config(input_manager: &InputManager) -> Result<ToolchainConfig,
     Ok(toolchain_config)
 }
 
+/// Which phases of the `cache` command run. Both are enabled by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct CachePhases {
+    restore: bool,
+    save: bool,
+}
+
+impl CachePhases {
+    /// Reads the phases from the `restore` and `save` inputs. Both are read in
+    /// each phase so a bad value is reported before the post step.
+    fn from_inputs(input_manager: &InputManager) -> Result<CachePhases, Error> {
+        Ok(CachePhases {
+            restore: parse_optional_input(input_manager, Input::Restore)?.unwrap_or(true),
+            save: parse_optional_input(input_manager, Input::Save)?.unwrap_or(true),
+        })
+    }
+}
+
+/// Environment variable set by the phase wrapper scripts
+const PHASE_ENV_VAR: &str = "GITHUB_RUST_ACTION_PHASE";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    Main,
+    Post,
+}
+
+/// Determines which phase the action has been invoked for. Returns `None` if
+/// no phase was specified and the phase name if it was not recognised.
+fn determine_phase(env: &HashMap<String, String>) -> Option<Result<Phase, &str>> {
+    env.get(PHASE_ENV_VAR).map(|phase| match phase.as_str() {
+        "main" => Ok(Phase::Main),
+        "post" => Ok(Phase::Post),
+        other => Err(other),
+    })
+}
+
 pub async fn run() -> Result<(), Error> {
     use wasm_bindgen::JsError;
 
+    let node_version = node::process::node_version();
+    if !versions::is_supported_node_version(&node_version) {
+        return Err(Error::UnsupportedNodeVersion(
+            node_version,
+            versions::MIN_NODE_VERSION.to_string(),
+        ));
+    }
+    action_paths::check_home_dir().await?;
+    match github::Context::from_env() {
+        Ok(context) => debug!("Running as {}", context),
+        Err(e) => debug!("Unable to determine the workflow run context: {}", e),
+    }
     let environment = node::process::get_env();
-    if let Some(phase) = environment.get("GITHUB_RUST_ACTION_PHASE") {
-        match phase.as_str() {
-            "main" => main().await,
-            "post" => post().await,
-            _ => {
-                warning!("Unexpectedly invoked with phase {}. Doing nothing.", phase);
-                Ok(())
-            }
+    match determine_phase(&environment) {
+        Some(Ok(Phase::Main)) => main().await,
+        Some(Ok(Phase::Post)) => post().await,
+        Some(Err(phase)) => {
+            warning!("Unexpectedly invoked with phase {}. Doing nothing.", phase);
+            Ok(())
         }
-    } else {
-        Err(Error::Js(
+        None => Err(Error::Js(
             JsError::new("Action was invoked in an unexpected way. Could not determine phase.").into(),
-        ))
+        )),
     }
 }
 
@@ -59,11 +128,13 @@ pub async fn main() -> Result<(), Error> {
     let split: Vec<&str> = command.split_whitespace().collect();
     match split[..] {
         ["install-rustup"] => {
-            let toolchain_config = get_toolchain_config(&input_manager)?;
-            rustup::install(&toolchain_config).await?;
+            let toolchain_config = get_toolchain_config(&input_manager).await?;
+            let self_update = parse_optional_input(&input_manager, Input::RustupSelfUpdate)?.unwrap_or(true);
+            let dry_run = parse_optional_input(&input_manager, Input::DryRun)?.unwrap_or(false);
+            rustup::install(&toolchain_config, self_update, dry_run).await?;
         }
         ["install-toolchain"] => {
-            let toolchain_config = get_toolchain_config(&input_manager)?;
+            let toolchain_config = get_toolchain_config(&input_manager).await?;
             toolchain::install(&toolchain_config).await?;
         }
         ["cargo", cargo_subcommand] => {
@@ -80,25 +151,73 @@ pub async fn main() -> Result<(), Error> {
             } else {
                 Cargo::from_environment().await?
             };
+            cargo.apply_inputs(&input_manager)?;
             let cargo_args = input_manager.get(Input::Args).unwrap_or_default();
             let cargo_args =
                 shlex::split(cargo_args).ok_or_else(|| Error::ArgumentsParseError(cargo_args.to_string()))?;
             let toolchain = input_manager.get(Input::Toolchain);
-            cargo
-                .run(
-                    toolchain,
-                    cargo_subcommand,
-                    cargo_args.iter().map(String::as_str),
-                    &input_manager,
-                )
-                .await?;
+            let wasm_opt_level = match input_manager.get(Input::WasmOpt) {
+                Some(level) if cargo_subcommand == "build" => Some(wasm_opt::parse_level(level)?),
+                _ => None,
+            };
+            // The target is added to the active toolchain, which is not the one used by
+            // Cross or an explicitly specified toolchain
+            let wasm_build = if cargo_subcommand == "build" && !use_cross && toolchain.is_none() {
+                cargo::parse_wasm_build_args(&cargo_args)
+            } else {
+                None
+            };
+            if cargo_subcommand == "test" {
+                let summary = cargo
+                    .run_tests(toolchain, cargo_args.iter().map(String::as_str), &input_manager)
+                    .await?;
+                info!(
+                    "Tests: {} passed, {} failed, {} ignored",
+                    summary.passed, summary.failed, summary.ignored
+                );
+                if !summary.failures.is_empty() {
+                    error!("Failed tests:\n{}", summary.failures.join("\n"));
+                }
+                if !summary.is_success() {
+                    return Err(Error::TestsFailed(summary.failed));
+                }
+            } else if wasm_build.is_some() || wasm_opt_level.is_some() {
+                let artifacts = if let Some((target, release)) = wasm_build {
+                    cargo.build_wasm(Some(target), release).await?
+                } else {
+                    cargo
+                        .run_build(toolchain, cargo_args.iter().map(String::as_str), &input_manager)
+                        .await?
+                };
+                if let Some(level) = wasm_opt_level {
+                    let output_dir = input_manager.get(Input::WasmOptOutputDir).map(Path::from);
+                    let metadata = cargo.metadata().await?;
+                    let target_dir = Path::from(metadata.target_directory.as_str());
+                    wasm_opt::optimize_outputs(&target_dir, &artifacts, level, output_dir.as_ref()).await?;
+                }
+            } else {
+                cargo
+                    .run(
+                        toolchain,
+                        cargo_subcommand,
+                        cargo_args.iter().map(String::as_str),
+                        &input_manager,
+                    )
+                    .await?;
+            }
+        }
+        ["cache"] => {
+            let phases = CachePhases::from_inputs(&input_manager)?;
+            if !phases.restore {
+                info!("Not restoring the cache since the restore input is false");
+            }
+            restore_cargo_cache(&input_manager, phases.restore).await?;
         }
-        ["cache"] => restore_cargo_cache(&input_manager).await?,
         _ => return Err(Error::UnknownCommand(command.to_string())),
     }
 
     for input in input_manager.unused() {
-        warning!("Recognised but unused input {} was passed to action", input);
+        workflow::configuration_warning(&format!("Recognised but unused input {} was passed to action", input));
     }
     Ok(())
 }
@@ -109,8 +228,88 @@ pub async fn post() -> Result<(), Error> {
     let split: Vec<&str> = command.split_whitespace().collect();
     #[allow(clippy::single_match)]
     match split[..] {
-        ["cache"] => save_cargo_cache(&input_manager).await?,
+        ["cache"] => {
+            let result = if CachePhases::from_inputs(&input_manager)?.save {
+                save_cargo_cache(&input_manager).await
+            } else {
+                info!("Not saving the cache since the save input is false");
+                Ok(())
+            };
+            remove_cached_folder_info().await;
+            result?;
+        }
         _ => {}
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::{determine_phase, parse_list, CachePhases, Phase, PHASE_ENV_VAR};
+    use crate::input_manager::{Input, Manager as InputManager};
+    use crate::Error;
+    use std::collections::HashMap;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn parse_empty_list() {
+        assert!(parse_list("").is_empty());
+        assert!(parse_list(" \t\n ").is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_list_with_extra_whitespace() {
+        let expected = vec!["clippy".to_string(), "rustfmt".to_string()];
+        assert_eq!(parse_list("clippy rustfmt"), expected);
+        assert_eq!(parse_list("  clippy \t rustfmt\n"), expected);
+        assert_eq!(parse_list("clippy\nrustfmt"), expected);
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_single_target() {
+        assert_eq!(
+            parse_list(" wasm32-unknown-unknown "),
+            vec!["wasm32-unknown-unknown".to_string()]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn phase_from_env() {
+        let env_with_phase = |phase: &str| HashMap::from([(PHASE_ENV_VAR.to_string(), phase.to_string())]);
+        assert_eq!(determine_phase(&env_with_phase("main")), Some(Ok(Phase::Main)));
+        assert_eq!(determine_phase(&env_with_phase("post")), Some(Ok(Phase::Post)));
+        assert_eq!(determine_phase(&env_with_phase("pre")), Some(Err("pre")));
+        assert_eq!(determine_phase(&HashMap::new()), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn cache_phases_from_inputs() {
+        let phases =
+            |inputs: &[(Input, &str)]| CachePhases::from_inputs(&InputManager::from_inputs(inputs.iter().copied()));
+        assert_eq!(
+            phases(&[]).unwrap(),
+            CachePhases {
+                restore: true,
+                save: true
+            }
+        );
+        assert_eq!(
+            phases(&[(Input::Save, "false")]).unwrap(),
+            CachePhases {
+                restore: true,
+                save: false
+            }
+        );
+        assert_eq!(
+            phases(&[(Input::Restore, "false"), (Input::Save, "true")]).unwrap(),
+            CachePhases {
+                restore: false,
+                save: true
+            }
+        );
+        assert!(matches!(
+            phases(&[(Input::Save, "no")]),
+            Err(Error::OptionParseError(..))
+        ));
+    }
+}
@@ -1,24 +1,168 @@
 use crate::actions::exec::Command;
 use crate::actions::{core, io, tool_cache};
 use crate::node::path::Path;
-use crate::{debug, info, node, Error};
+use crate::{action_paths, debug, info, node, target_triple, warning, Error};
+use js_sys::JsString;
 use parking_lot::Mutex;
 use std::sync::Arc;
+use strum::{Display, EnumString};
 
 const NO_DEFAULT_TOOLCHAIN_NAME: &str = "none";
+const SELF_UPDATE_ARGS: [&str; 2] = ["self", "update"];
+const INSTALLER_MODE: u16 = 0o755;
 
-pub async fn install(toolchain_config: &ToolchainConfig) -> Result<(), Error> {
-    let rustup = Rustup::get_or_install().await?;
-    debug!("Rustup installed at: {}", rustup.get_path());
-    rustup.update().await?;
-    rustup.install_toolchain(toolchain_config).await?;
+/// The URL of the `rustup-init` installer for the specified host
+fn installer_url(host: &str) -> String {
+    let extension = if host.contains("windows") { ".exe" } else { "" };
+    format!(
+        "https://static.rust-lang.org/rustup/dist/{}/rustup-init{}",
+        host, extension
+    )
+}
+
+/// Makes the downloaded installer executable
+async fn make_installer_executable(installer: &Path) -> Result<(), Error> {
+    node::fs::chmod(installer, INSTALLER_MODE).await.map_err(Error::Js)
+}
+
+/// Installs Rustup if necessary and the configured toolchain. If `dry_run` is
+/// set, the steps that would be taken are logged but nothing is modified.
+pub async fn install(toolchain_config: &ToolchainConfig, self_update: bool, dry_run: bool) -> Result<(), Error> {
+    let rustup = Rustup::get().await.ok();
+    let installed = match &rustup {
+        Some(rustup) => {
+            debug!("Rustup installed at: {}", rustup.get_path());
+            Some(rustup.installed_items(&toolchain_config.name).await?)
+        }
+        None => None,
+    };
+    let plan = plan_install(toolchain_config, self_update, installed.as_ref());
+    info!("Rustup setup plan:");
+    for step in &plan {
+        info!("- {}", step);
+    }
+    if dry_run {
+        info!("Dry run requested, so no changes were made");
+        return Ok(());
+    }
+    let mut rustup = rustup;
+    for step in &plan {
+        rustup = Some(match rustup {
+            None if *step == PlanStep::InstallRustup => Rustup::install(&InstallOptions::default()).await?,
+            None => panic!("Rustup setup plan did not start by installing Rustup"),
+            Some(rustup) => {
+                rustup.execute(step, toolchain_config).await?;
+                rustup
+            }
+        });
+    }
     Ok(())
 }
 
+/// The toolchain, components and targets already installed by Rustup
+#[derive(Clone, Debug, Default)]
+struct InstalledItems {
+    host: Option<String>,
+    toolchains: Vec<String>,
+    components: Vec<String>,
+    targets: Vec<String>,
+}
+
+/// A single modification made when setting up Rustup and a toolchain
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PlanStep {
+    InstallRustup,
+    SelfUpdate,
+    Update,
+    InstallToolchain(String),
+    AddComponent(String, String),
+    AddTarget(String, String),
+    SetDefault(String),
+    SetOverride(String),
+}
+
+impl std::fmt::Display for PlanStep {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            PlanStep::InstallRustup => write!(formatter, "Install Rustup"),
+            PlanStep::SelfUpdate => write!(formatter, "Update Rustup"),
+            PlanStep::Update => write!(formatter, "Update installed toolchains"),
+            PlanStep::InstallToolchain(toolchain) => write!(formatter, "Install toolchain {}", toolchain),
+            PlanStep::AddComponent(toolchain, component) => {
+                write!(formatter, "Add component {} to toolchain {}", component, toolchain)
+            }
+            PlanStep::AddTarget(toolchain, target) => {
+                write!(formatter, "Add target {} to toolchain {}", target, toolchain)
+            }
+            PlanStep::SetDefault(toolchain) => write!(formatter, "Set toolchain {} as the default", toolchain),
+            PlanStep::SetOverride(toolchain) => {
+                write!(
+                    formatter,
+                    "Set toolchain {} as the override for this directory",
+                    toolchain
+                )
+            }
+        }
+    }
+}
+
+/// Determines the steps needed to install the configured toolchain. If
+/// `installed` is `None`, Rustup itself is not installed.
+fn plan_install(config: &ToolchainConfig, self_update: bool, installed: Option<&InstalledItems>) -> Vec<PlanStep> {
+    let mut plan = Vec::new();
+    if installed.is_none() {
+        plan.push(PlanStep::InstallRustup);
+    } else if self_update {
+        plan.push(PlanStep::SelfUpdate);
+    }
+    plan.push(PlanStep::Update);
+    if config.name == NO_DEFAULT_TOOLCHAIN_NAME {
+        return plan;
+    }
+    let empty = InstalledItems::default();
+    let installed = installed.unwrap_or(&empty);
+    let host = installed.host.as_deref();
+    if is_installed(&installed.toolchains, &config.name, host) {
+        for component in &config.components {
+            if !is_installed(&installed.components, component, host) {
+                plan.push(PlanStep::AddComponent(config.name.clone(), component.clone()));
+            }
+        }
+        for target in &config.targets {
+            if !installed.targets.contains(target) {
+                plan.push(PlanStep::AddTarget(config.name.clone(), target.clone()));
+            }
+        }
+    } else {
+        plan.push(PlanStep::InstallToolchain(config.name.clone()));
+    }
+    if config.set_default {
+        plan.push(PlanStep::SetDefault(config.name.clone()));
+    }
+    if config.set_override {
+        plan.push(PlanStep::SetOverride(config.name.clone()));
+    }
+    plan
+}
+
+/// A Rustup profile, which determines the default set of components installed
+#[derive(Clone, Copy, Debug, Default, Display, EnumString, Eq, PartialEq)]
+pub enum Profile {
+    #[default]
+    #[strum(serialize = "minimal")]
+    Minimal,
+
+    #[strum(serialize = "default")]
+    Default,
+
+    #[strum(serialize = "complete")]
+    Complete,
+}
+
 #[derive(Clone, Debug)]
 pub struct ToolchainConfig {
     pub name: String,
-    pub profile: String,
+    pub profile: Profile,
     pub components: Vec<String>,
     pub targets: Vec<String>,
     pub set_default: bool,
@@ -29,7 +173,7 @@ impl Default for ToolchainConfig {
     fn default() -> ToolchainConfig {
         ToolchainConfig {
             name: "stable".into(),
-            profile: "default".into(),
+            profile: Profile::default(),
             components: Vec::new(),
             targets: Vec::new(),
             set_default: true,
@@ -38,23 +182,71 @@ impl Default for ToolchainConfig {
     }
 }
 
+/// Options passed to the `rustup-init` installer
 #[derive(Clone, Debug)]
-pub struct Rustup {
-    path: Path,
+pub struct InstallOptions {
+    /// The toolchain to install as the default. If `None`, no toolchain is
+    /// installed.
+    pub default_toolchain: Option<String>,
+
+    /// The profile used when installing toolchains
+    pub profile: Option<Profile>,
+
+    /// The default host triple, if not the detected one
+    pub default_host: Option<String>,
+
+    /// Whether the installer should add Cargo's bin folder to the `PATH` in
+    /// shell profiles
+    pub modify_path: bool,
 }
 
-impl Rustup {
-    pub async fn get_or_install() -> Result<Rustup, Error> {
-        match Self::get().await {
-            Ok(rustup) => Ok(rustup),
-            Err(e) => {
-                info!("Unable to find rustup, Installing it now...");
-                debug!("Attempting to locate rustup returned this error: {}", e);
-                Self::install().await
+impl Default for InstallOptions {
+    fn default() -> InstallOptions {
+        InstallOptions {
+            default_toolchain: None,
+            profile: None,
+            default_host: None,
+            modify_path: true,
+        }
+    }
+}
+
+impl InstallOptions {
+    /// The arguments to pass to `rustup-init`
+    fn args(&self) -> Result<Vec<String>, Error> {
+        let mut args = vec!["-y".to_string()];
+        let default_toolchain = match self.default_toolchain.as_deref() {
+            None => NO_DEFAULT_TOOLCHAIN_NAME,
+            Some(NO_DEFAULT_TOOLCHAIN_NAME) => {
+                return Err(Error::InvalidRustupInstallOptions(
+                    "the toolchain `none` should be requested by not specifying a default toolchain",
+                ))
             }
+            Some("") => return Err(Error::InvalidRustupInstallOptions("the default toolchain is empty")),
+            Some(toolchain) => toolchain,
+        };
+        args.extend(["--default-toolchain".into(), default_toolchain.into()]);
+        if let Some(profile) = self.profile {
+            args.extend(["--profile".into(), profile.to_string()]);
+        }
+        match self.default_host.as_deref() {
+            None => {}
+            Some("") => return Err(Error::InvalidRustupInstallOptions("the default host is empty")),
+            Some(host) => args.extend(["--default-host".into(), host.into()]),
         }
+        if !self.modify_path {
+            args.push("--no-modify-path".into());
+        }
+        Ok(args)
     }
+}
 
+#[derive(Clone, Debug)]
+pub struct Rustup {
+    path: Path,
+}
+
+impl Rustup {
     pub async fn get() -> Result<Rustup, Error> {
         io::which("rustup", true)
             .await
@@ -62,104 +254,187 @@ impl Rustup {
             .map_err(Error::Js)
     }
 
-    pub async fn install() -> Result<Rustup, Error> {
-        let args = ["--default-toolchain", NO_DEFAULT_TOOLCHAIN_NAME, "-y"];
-        let platform = node::os::platform();
-        info!("Getting rustup for platform: {:?}", platform);
-        match platform.as_str() {
-            "darwin" | "linux" => {
-                let rustup_script = tool_cache::download_tool("https://sh.rustup.rs")
-                    .await
-                    .map_err(Error::Js)?;
-                info!("Downloaded to: {:?}", rustup_script);
-                node::fs::chmod(&rustup_script, 0x755).await.map_err(Error::Js)?;
-                Command::from(&rustup_script)
-                    .args(args)
-                    .exec()
-                    .await
-                    .map_err(Error::Js)?;
-            }
-            "windows" => {
-                let rustup_exe = tool_cache::download_tool("https://win.rustup.rs")
-                    .await
-                    .map_err(Error::Js)?;
-                info!("Downloaded to: {:?}", rustup_exe);
-                Command::from(&rustup_exe).args(args).exec().await.map_err(Error::Js)?;
-            }
-            _ => return Err(Error::UnsupportedPlatform(platform)),
+    pub async fn install(options: &InstallOptions) -> Result<Rustup, Error> {
+        let args = options.args()?;
+        let host = target_triple::host()?;
+        info!("Getting rustup for host: {}", host);
+        let installer = tool_cache::download_tool(installer_url(host).as_str())
+            .await
+            .map_err(Error::Js)?;
+        info!("Downloaded to: {:?}", installer);
+        make_installer_executable(&installer).await?;
+        let output = Command::from(&installer)
+            .args(args.iter().map(String::as_str))
+            .exec_output()
+            .await
+            .map_err(Error::Js)?;
+        if output.exit_code != 0 {
+            return Err(Error::CommandFailed(
+                "rustup-init".into(),
+                output.exit_code,
+                output.stderr.trim().to_string(),
+            ));
         }
-        let cargo_bin_path = node::os::homedir().join(".cargo").join("bin");
+        let cargo_home = action_paths::cargo_home_from_env(&node::process::get_env(), &node::os::homedir());
+        let cargo_bin_path = cargo_home.join("bin");
         info!("Adding {:?} to path", cargo_bin_path);
         core::add_path(&cargo_bin_path);
         Self::get().await
     }
 
     pub async fn update(&self) -> Result<(), Error> {
+        // Self-updating is handled separately by `self_update`
         Command::from(&self.path)
             .arg("update")
+            .arg("--no-self-update")
             .exec()
             .await
             .map_err(Error::Js)?;
         Ok(())
     }
 
-    pub async fn install_toolchain(&self, config: &ToolchainConfig) -> Result<(), Error> {
-        if config.name == NO_DEFAULT_TOOLCHAIN_NAME {
-            return Ok(());
-        }
-        let mut args: Vec<_> = ["toolchain", "install"].into_iter().map(String::from).collect();
-        args.push(config.name.clone());
-        args.extend(["--profile".into(), config.profile.clone()]);
-        for target in &config.targets {
-            args.extend(["-t".into(), target.clone()]);
-        }
-        // It seems that components can take multiple arguments so the toolchain name
-        // must be present before this
-        for component in &config.components {
-            args.extend(["-c".into(), component.clone()]);
-        }
-        Command::from(&self.path).args(args).exec().await.map_err(Error::Js)?;
-        for (flag, option_name) in [(config.set_default, "default"), (config.set_override, "override")] {
-            if flag {
-                Command::from(&self.path)
-                    .arg(option_name)
-                    .arg(config.name.clone())
-                    .exec()
-                    .await
-                    .map_err(Error::Js)?;
+    pub async fn self_update(&self) -> Result<(), Error> {
+        let disabled = Arc::new(Mutex::new(false));
+        let result = {
+            let disabled = Arc::clone(&disabled);
+            Command::from(&self.path)
+                .args(SELF_UPDATE_ARGS)
+                .errline(move |line| {
+                    if is_self_update_disabled_message(line) {
+                        *disabled.lock() = true;
+                    }
+                })
+                .exec()
+                .await
+        };
+        match result {
+            Ok(_) => Ok(()),
+            Err(_) if *disabled.lock() => {
+                warning!("Rustup self-update is disabled in this environment so Rustup was not updated");
+                Ok(())
             }
+            Err(e) => Err(Error::Js(e)),
         }
+    }
+
+    /// Lists what is installed, including the components and targets of the
+    /// specified toolchain if it is installed
+    async fn installed_items(&self, toolchain: &str) -> Result<InstalledItems, Error> {
+        let host = self.default_host().await?;
+        let toolchains = self.installed_toolchains().await?;
+        let (components, targets) =
+            if toolchain != NO_DEFAULT_TOOLCHAIN_NAME && is_installed(&toolchains, toolchain, host.as_deref()) {
+                (
+                    self.installed_components(toolchain).await?,
+                    self.installed_targets(toolchain).await?,
+                )
+            } else {
+                (Vec::new(), Vec::new())
+            };
+        Ok(InstalledItems {
+            host,
+            toolchains,
+            components,
+            targets,
+        })
+    }
+
+    async fn execute(&self, step: &PlanStep, config: &ToolchainConfig) -> Result<(), Error> {
+        match step {
+            PlanStep::InstallRustup => panic!("Rustup is already installed"),
+            PlanStep::SelfUpdate => self.self_update().await,
+            PlanStep::Update => self.update().await,
+            PlanStep::InstallToolchain(_) => self.install_new_toolchain(config).await,
+            PlanStep::AddComponent(toolchain, component) => self.install_component(toolchain, component).await,
+            PlanStep::AddTarget(toolchain, target) => self.install_target(toolchain, target).await,
+            PlanStep::SetDefault(toolchain) => self.set_toolchain_option("default", toolchain).await,
+            PlanStep::SetOverride(toolchain) => self.set_toolchain_option("override", toolchain).await,
+        }
+    }
+
+    async fn set_toolchain_option(&self, option_name: &str, toolchain: &str) -> Result<(), Error> {
+        Command::from(&self.path)
+            .arg(option_name)
+            .arg(toolchain)
+            .exec()
+            .await
+            .map_err(Error::Js)?;
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub async fn installed_toolchains(&self) -> Result<Vec<String>, Error> {
-        let args: Vec<_> = ["toolchain", "list"].into_iter().map(String::from).collect();
+    async fn install_new_toolchain(&self, config: &ToolchainConfig) -> Result<(), Error> {
+        let args = toolchain_install_args(config);
+        Command::from(&self.path).args(args).exec().await.map_err(Error::Js)?;
+        Ok(())
+    }
 
-        let toolchains: Arc<Mutex<Vec<String>>> = Arc::default();
+    async fn output_lines<I, S>(&self, args: I) -> Result<Vec<String>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<JsString>,
+    {
+        let lines: Arc<Mutex<Vec<String>>> = Arc::default();
         {
-            let match_default = regex::Regex::new(r" *\(default\) *$").expect("Regex compilation failed");
-            let toolchains = Arc::clone(&toolchains);
+            let lines = Arc::clone(&lines);
             Command::from(&self.path)
                 .args(args)
-                .outline(move |line| {
-                    let toolchain = match_default.replace(line, "");
-                    toolchains.lock().push(toolchain.to_string());
-                })
+                .outline(move |line| lines.lock().push(line.to_string()))
                 .exec()
                 .await
                 .map_err(Error::Js)?;
         }
-        let toolchains = toolchains.lock().drain(..).collect();
+        let lines = lines.lock().drain(..).collect();
+        Ok(lines)
+    }
+
+    pub async fn default_host(&self) -> Result<Option<String>, Error> {
+        let lines = self.output_lines(["show"]).await?;
+        Ok(parse_default_host(&lines))
+    }
+
+    pub async fn installed_toolchains(&self) -> Result<Vec<String>, Error> {
+        let lines = self.output_lines(["toolchain", "list"]).await?;
+        let match_annotation = regex::Regex::new(r" *\(.*\) *$").expect("Regex compilation failed");
+        let toolchains = lines
+            .iter()
+            .map(|line| match_annotation.replace(line, "").to_string())
+            .collect();
         Ok(toolchains)
     }
 
-    #[allow(dead_code)]
-    pub async fn install_component(&self, name: &str) -> Result<(), Error> {
+    pub async fn installed_components(&self, toolchain: &str) -> Result<Vec<String>, Error> {
+        self.output_lines(["component", "list", "--installed", "--toolchain", toolchain])
+            .await
+    }
+
+    pub async fn installed_targets(&self, toolchain: &str) -> Result<Vec<String>, Error> {
+        self.output_lines(["target", "list", "--installed", "--toolchain", toolchain])
+            .await
+    }
+
+    pub async fn install_component(&self, toolchain: &str, name: &str) -> Result<(), Error> {
+        Command::from(&self.path)
+            .args(["component", "add", "--toolchain", toolchain, name])
+            .exec()
+            .await
+            .map_err(Error::Js)?;
+        Ok(())
+    }
+
+    pub async fn install_target(&self, toolchain: &str, name: &str) -> Result<(), Error> {
         Command::from(&self.path)
-            .arg("component")
-            .arg("add")
-            .arg(name)
+            .args(["target", "add", "--toolchain", toolchain, name])
+            .exec()
+            .await
+            .map_err(Error::Js)?;
+        Ok(())
+    }
+
+    /// Adds a target to the active toolchain. This is a no-op if the target is
+    /// already installed.
+    pub async fn add_target(&self, name: &str) -> Result<(), Error> {
+        Command::from(&self.path)
+            .args(["target", "add", name])
             .exec()
             .await
             .map_err(Error::Js)?;
@@ -170,3 +445,292 @@ impl Rustup {
         &self.path
     }
 }
+
+fn toolchain_install_args(config: &ToolchainConfig) -> Vec<String> {
+    let mut args: Vec<_> = ["toolchain", "install"].into_iter().map(String::from).collect();
+    args.push(config.name.clone());
+    args.extend(["--profile".into(), config.profile.to_string()]);
+    for target in &config.targets {
+        args.extend(["-t".into(), target.clone()]);
+    }
+    // It seems that components can take multiple arguments so the toolchain name
+    // must be present before this
+    for component in &config.components {
+        args.extend(["-c".into(), component.clone()]);
+    }
+    args
+}
+
+fn parse_default_host(show_output: &[String]) -> Option<String> {
+    show_output
+        .iter()
+        .find_map(|line| line.strip_prefix("Default host:"))
+        .map(|host| host.trim().to_string())
+}
+
+/// Checks if a toolchain or component is in the list reported by Rustup, which
+/// may qualify the name with the host triple
+fn is_installed(installed: &[String], name: &str, host: Option<&str>) -> bool {
+    installed.iter().any(|entry| {
+        entry == name
+            || host.map_or(false, |host| {
+                entry
+                    .strip_prefix(name)
+                    .and_then(|rest| rest.strip_prefix('-'))
+                    .map_or(false, |rest| rest == host)
+            })
+    })
+}
+
+fn is_self_update_disabled_message(line: &str) -> bool {
+    line.contains("self-update is disabled")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{plan_install, InstallOptions, InstalledItems, PlanStep, Profile, ToolchainConfig};
+    use std::str::FromStr as _;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn to_lines(output: &str) -> Vec<String> {
+        output.lines().map(String::from).collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn install_plan() {
+        let config = ToolchainConfig {
+            name: "nightly".into(),
+            components: vec!["rustfmt".into(), "clippy".into()],
+            targets: vec!["wasm32-unknown-unknown".into()],
+            ..ToolchainConfig::default()
+        };
+        assert_eq!(
+            plan_install(&config, true, None),
+            [
+                PlanStep::InstallRustup,
+                PlanStep::Update,
+                PlanStep::InstallToolchain("nightly".into()),
+                PlanStep::SetDefault("nightly".into()),
+            ]
+        );
+
+        let installed = InstalledItems {
+            host: Some("x86_64-unknown-linux-gnu".into()),
+            toolchains: vec!["nightly-x86_64-unknown-linux-gnu".into()],
+            components: vec!["rustfmt-x86_64-unknown-linux-gnu".into()],
+            targets: vec!["x86_64-unknown-linux-gnu".into()],
+        };
+        let config = ToolchainConfig {
+            set_default: false,
+            set_override: true,
+            ..config
+        };
+        assert_eq!(
+            plan_install(&config, false, Some(&installed)),
+            [
+                PlanStep::Update,
+                PlanStep::AddComponent("nightly".into(), "clippy".into()),
+                PlanStep::AddTarget("nightly".into(), "wasm32-unknown-unknown".into()),
+                PlanStep::SetOverride("nightly".into()),
+            ]
+        );
+
+        let config = ToolchainConfig {
+            name: "none".into(),
+            ..ToolchainConfig::default()
+        };
+        assert_eq!(
+            plan_install(&config, true, Some(&installed)),
+            [PlanStep::SelfUpdate, PlanStep::Update]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn installer_args() {
+        assert_eq!(
+            InstallOptions::default()
+                .args()
+                .expect("Default options should be valid"),
+            ["-y", "--default-toolchain", "none"]
+        );
+
+        let options = InstallOptions {
+            profile: Some(Profile::Minimal),
+            modify_path: false,
+            ..InstallOptions::default()
+        };
+        assert_eq!(
+            options.args().expect("Options should be valid"),
+            [
+                "-y",
+                "--default-toolchain",
+                "none",
+                "--profile",
+                "minimal",
+                "--no-modify-path"
+            ]
+        );
+
+        let options = InstallOptions {
+            default_toolchain: Some("stable".into()),
+            default_host: Some("x86_64-pc-windows-gnu".into()),
+            ..InstallOptions::default()
+        };
+        assert_eq!(
+            options.args().expect("Options should be valid"),
+            [
+                "-y",
+                "--default-toolchain",
+                "stable",
+                "--default-host",
+                "x86_64-pc-windows-gnu"
+            ]
+        );
+
+        let options = InstallOptions {
+            default_toolchain: Some("none".into()),
+            ..InstallOptions::default()
+        };
+        assert!(options.args().is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_default_host_from_show() {
+        let output = to_lines(
+            "Default host: x86_64-unknown-linux-gnu
+rustup home:  /home/runner/.rustup
+
+installed toolchains
+--------------------
+
+stable-x86_64-unknown-linux-gnu (default)
+nightly-x86_64-unknown-linux-gnu
+
+active toolchain
+----------------
+
+stable-x86_64-unknown-linux-gnu (default)
+rustc 1.70.0 (90c541806 2023-05-31)
+",
+        );
+        assert_eq!(
+            super::parse_default_host(&output).as_deref(),
+            Some("x86_64-unknown-linux-gnu")
+        );
+        assert_eq!(super::parse_default_host(&[]), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn detect_installed_toolchain() {
+        let host = Some("x86_64-unknown-linux-gnu");
+        let installed = to_lines("stable-x86_64-unknown-linux-gnu\nnightly-2023-06-01-x86_64-unknown-linux-gnu");
+        assert!(super::is_installed(&installed, "stable", host));
+        assert!(super::is_installed(&installed, "stable-x86_64-unknown-linux-gnu", host));
+        assert!(super::is_installed(&installed, "nightly-2023-06-01", host));
+        assert!(!super::is_installed(&installed, "nightly", host));
+        assert!(!super::is_installed(&installed, "beta", host));
+        assert!(!super::is_installed(&installed, "stable", None));
+    }
+
+    #[wasm_bindgen_test]
+    fn detect_installed_component() {
+        let host = Some("x86_64-unknown-linux-gnu");
+        let installed = to_lines("rust-src\nrustfmt-x86_64-unknown-linux-gnu\nrust-std-x86_64-unknown-linux-gnu");
+        assert!(super::is_installed(&installed, "rustfmt", host));
+        assert!(super::is_installed(&installed, "rust-src", host));
+        assert!(!super::is_installed(&installed, "clippy", host));
+    }
+
+    #[wasm_bindgen_test]
+    fn profile_install_args() {
+        for (input, profile) in [
+            ("minimal", Profile::Minimal),
+            ("default", Profile::Default),
+            ("complete", Profile::Complete),
+        ] {
+            assert_eq!(Profile::from_str(input).ok(), Some(profile));
+            let config = ToolchainConfig {
+                profile,
+                ..ToolchainConfig::default()
+            };
+            let args = super::toolchain_install_args(&config);
+            let position = args
+                .iter()
+                .position(|a| a == "--profile")
+                .expect("Missing profile flag");
+            assert_eq!(args[position + 1], input);
+        }
+        assert!(Profile::from_str("tiny").is_err());
+        assert_eq!(ToolchainConfig::default().profile, Profile::Minimal);
+    }
+
+    #[wasm_bindgen_test]
+    fn installer_urls() {
+        assert_eq!(
+            super::installer_url("aarch64-apple-darwin"),
+            "https://static.rust-lang.org/rustup/dist/aarch64-apple-darwin/rustup-init"
+        );
+        assert_eq!(
+            super::installer_url("x86_64-pc-windows-msvc"),
+            "https://static.rust-lang.org/rustup/dist/x86_64-pc-windows-msvc/rustup-init.exe"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn self_update_disabled_is_not_an_error() -> Result<(), crate::Error> {
+        use super::Rustup;
+        use crate::node;
+
+        // The fake Rustup is a shell script
+        if node::os::platform() == "win32" {
+            return Ok(());
+        }
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-self-update-")).await?;
+        let rustup = Rustup {
+            path: temp_dir.path().join("rustup"),
+        };
+        let disabled = "#!/bin/sh\necho 'error: self-update is disabled for this build of rustup' >&2\nexit 1\n";
+        node::fs::write_file(&rustup.path, disabled.as_bytes()).await?;
+        node::fs::chmod(&rustup.path, 0o755).await?;
+        let disabled_result = rustup.self_update().await;
+
+        let failed = "#!/bin/sh\necho 'error: could not download file' >&2\nexit 1\n";
+        node::fs::write_file(&rustup.path, failed.as_bytes()).await?;
+        let failed_result = rustup.self_update().await;
+
+        assert!(disabled_result.is_ok());
+        assert!(failed_result.is_err());
+        temp_dir.close().await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    async fn installer_made_executable() -> Result<(), crate::Error> {
+        use super::INSTALLER_MODE;
+        use crate::node;
+
+        // The mode must be octal rather than e.g. `0x755`
+        assert_eq!(INSTALLER_MODE, 0o755);
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-installer-")).await?;
+        let installer = temp_dir.path().join("rustup-init");
+        node::fs::write_file(&installer, b"").await?;
+        node::fs::chmod(&installer, 0o600).await?;
+        super::make_installer_executable(&installer).await?;
+        let metadata = node::fs::metadata(&installer).await?;
+        if node::os::platform() != "win32" {
+            assert_eq!(metadata.mode() & 0o777, u64::from(INSTALLER_MODE));
+        }
+        temp_dir.close().await?;
+        Ok(())
+    }
+
+    #[wasm_bindgen_test]
+    fn detect_self_update_disabled() {
+        assert!(super::is_self_update_disabled_message(
+            "error: self-update is disabled for this build of rustup"
+        ));
+        assert!(!super::is_self_update_disabled_message(
+            "info: checking for self-update"
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::info;
+use std::time::Duration;
+
+/// How many times to attempt an operation and how long to wait between
+/// attempts. The delay doubles after each failed attempt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Policy {
+    pub attempts: u32,
+    pub initial_delay: Duration,
+}
+
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy {
+            attempts: 3,
+            initial_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl Policy {
+    /// The delay after the specified failed attempt, counting from one
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay * 2u32.saturating_pow(attempt.saturating_sub(1))
+    }
+}
+
+/// Runs `f` until it succeeds, it fails with an error that `should_retry`
+/// rejects or the policy's attempts are exhausted. The last error is returned
+/// on failure.
+pub async fn retry<T, E, F, Fut, R>(policy: &Policy, operation: &str, should_retry: R, mut f: F) -> Result<T, E>
+where
+    E: std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+    R: Fn(&E) -> bool,
+{
+    use crate::system::sleep::sleep;
+
+    let mut attempt = 1;
+    loop {
+        match f().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.attempts && should_retry(&e) => {
+                let delay = policy.delay(attempt);
+                info!(
+                    "Attempt {} of {} to {} failed, retrying in {:?}: {:?}",
+                    attempt, policy.attempts, operation, delay, e
+                );
+                sleep(&delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{retry, Policy};
+    use std::cell::Cell;
+    use std::time::Duration;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const IMMEDIATE: Policy = Policy {
+        attempts: 3,
+        initial_delay: Duration::ZERO,
+    };
+
+    #[wasm_bindgen_test]
+    fn exponential_delay() {
+        let policy = Policy::default();
+        assert_eq!(policy.delay(1), Duration::from_secs(2));
+        assert_eq!(policy.delay(2), Duration::from_secs(4));
+        assert_eq!(policy.delay(3), Duration::from_secs(8));
+    }
+
+    #[wasm_bindgen_test]
+    async fn succeeds_after_transient_failures() {
+        let calls = Cell::new(0);
+        let result = retry(
+            &IMMEDIATE,
+            "test",
+            |_| true,
+            || {
+                calls.set(calls.get() + 1);
+                let result = if calls.get() <= 2 {
+                    Err("transient")
+                } else {
+                    Ok(calls.get())
+                };
+                async move { result }
+            },
+        )
+        .await;
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[wasm_bindgen_test]
+    async fn stops_on_permanent_failure_or_exhaustion() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = retry(
+            &IMMEDIATE,
+            "test",
+            |e| *e != "conflict",
+            || {
+                calls.set(calls.get() + 1);
+                async { Err("conflict") }
+            },
+        )
+        .await;
+        assert_eq!(result, Err("conflict"));
+        assert_eq!(calls.get(), 1);
+
+        calls.set(0);
+        let result: Result<(), _> = retry(
+            &IMMEDIATE,
+            "test",
+            |_| true,
+            || {
+                calls.set(calls.get() + 1);
+                async { Err("transient") }
+            },
+        )
+        .await;
+        assert_eq!(result, Err("transient"));
+        assert_eq!(calls.get(), 3);
+    }
+}
@@ -0,0 +1,7 @@
+use crate::{node, Error};
+
+/// Returns the Rust target triple of the machine the action is running on
+pub fn host() -> Result<&'static str, Error> {
+    node::os::detect_host_triple()
+        .ok_or_else(|| Error::UnsupportedPlatform(format!("{}-{}", node::os::platform(), node::os::arch())))
+}
@@ -0,0 +1,130 @@
+use serde::Deserialize;
+
+/// An event emitted by the test harness when run with `--format json`
+#[derive(Debug, Deserialize)]
+struct HarnessEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    name: Option<String>,
+}
+
+/// Results of running `cargo test`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub failures: Vec<String>,
+    build_failed: bool,
+}
+
+impl TestSummary {
+    /// Whether compilation failed before any tests were run
+    pub fn compile_failed(&self) -> bool {
+        self.build_failed && self.passed + self.failed + self.ignored == 0
+    }
+
+    /// Whether all tests that ran passed and compilation succeeded
+    pub fn is_success(&self) -> bool {
+        !self.build_failed && self.failed == 0
+    }
+
+    fn record(&mut self, name: &str, outcome: &str) {
+        match outcome {
+            "ok" => self.passed += 1,
+            "failed" | "FAILED" => {
+                self.failed += 1;
+                self.failures.push(name.to_string());
+            }
+            _ if outcome.starts_with("ignored") => self.ignored += 1,
+            _ => {}
+        }
+    }
+
+    fn process_json(&mut self, line: &str) -> bool {
+        use cargo_metadata::Message;
+
+        if let Ok(event) = serde_json::from_str::<HarnessEvent>(line) {
+            if event.kind == "test" {
+                if let Some(name) = event.name {
+                    self.record(&name, &event.event);
+                }
+            }
+            true
+        } else if let Ok(message) = serde_json::from_str::<Message>(line) {
+            if let Message::BuildFinished(finished) = message {
+                self.build_failed |= !finished.success;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Updates the summary from a line of output from `cargo test
+    /// --message-format=json`. Test results may be in either the human
+    /// readable or JSON test harness format. Returns whether the line was a
+    /// JSON record.
+    pub fn process_line(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        if line.starts_with('{') && self.process_json(line) {
+            return true;
+        }
+        // Human readable results have the form `test <name> ... <outcome>`
+        if let Some((name, outcome)) = line.strip_prefix("test ").and_then(|l| l.split_once(" ... ")) {
+            self.record(name, outcome);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TestSummary;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn human_readable_output() {
+        let output = "\
+running 4 tests
+test tests::a ... ok
+test tests::b ... FAILED
+test tests::c ... ignored
+test tests::d ... ignored, requires network
+test result: FAILED. 1 passed; 1 failed; 2 ignored; 0 measured; 0 filtered out";
+        let mut summary = TestSummary::default();
+        for line in output.lines() {
+            summary.process_line(line);
+        }
+        assert_eq!(summary.passed, 1);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.ignored, 2);
+        assert_eq!(summary.failures, ["tests::b"]);
+        assert!(!summary.is_success());
+        assert!(!summary.compile_failed());
+    }
+
+    #[wasm_bindgen_test]
+    fn json_harness_output() {
+        let output = r#"{ "type": "suite", "event": "started", "test_count": 2 }
+{ "type": "test", "event": "started", "name": "a" }
+{ "type": "test", "name": "a", "event": "ok" }
+{ "type": "test", "name": "b", "event": "failed", "stdout": "panicked" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 0 }"#;
+        let mut summary = TestSummary::default();
+        for line in output.lines() {
+            assert!(summary.process_line(line));
+        }
+        assert_eq!(summary.passed, 1);
+        assert_eq!(summary.failures, ["b"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn compile_failure() {
+        let mut summary = TestSummary::default();
+        assert!(summary.process_line(r#"{"reason":"build-finished","success":false}"#));
+        assert!(summary.compile_failed());
+        assert!(!summary.is_success());
+    }
+}
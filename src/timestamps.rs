@@ -0,0 +1,128 @@
+use crate::agnostic_path::AgnosticPath;
+use crate::node::path::Path;
+use crate::{dir_tree, node, Error};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+/// Name of the file within a cached folder that records modification times
+const RECORD_FILE_NAME: &str = ".ferrous-actions-timestamps";
+
+type Record = BTreeMap<AgnosticPath, DateTime<Utc>>;
+
+struct RecordVisitor<'a> {
+    root: &'a Path,
+    record: Record,
+}
+
+#[async_trait(?Send)]
+impl dir_tree::Visitor for RecordVisitor<'_> {
+    async fn enter_folder(&mut self, _: &Path) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn exit_folder(&mut self, _: &Path) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn visit_entry(&mut self, path: &Path, is_file: bool) -> Result<(), Error> {
+        if is_file {
+            let metadata = node::fs::symlink_metadata(path).await?;
+            let relative = path.relative_to(self.root);
+            self.record.insert(AgnosticPath::from(&relative), metadata.modified());
+        } else {
+            panic!("Expected to descend into all directories");
+        }
+        Ok(())
+    }
+}
+
+fn record_path(folder: &Path) -> Path {
+    folder.join(RECORD_FILE_NAME)
+}
+
+/// Writes the modification times of all files in the folder to a file within
+/// it, so that they can be restored by `apply` if lost
+pub async fn record(folder: &Path) -> Result<(), Error> {
+    let mut visitor = RecordVisitor {
+        root: folder,
+        record: Record::new(),
+    };
+    let mut ignores = dir_tree::Ignores::default();
+    ignores.add(1, RECORD_FILE_NAME);
+    dir_tree::apply_visitor(folder, &ignores, &mut visitor).await?;
+    let serialized = postcard::to_stdvec(&visitor.record)?;
+    node::fs::write_file(&record_path(folder), &serialized).await?;
+    Ok(())
+}
+
+/// Deletes the record written by `record`, if present
+pub async fn remove_record(folder: &Path) -> Result<(), Error> {
+    let path = record_path(folder);
+    if path.exists().await {
+        node::fs::remove_file(&path).await?;
+    }
+    Ok(())
+}
+
+/// Restores modification times recorded by `record` where they differ from
+/// those on disk, then deletes the record. Returns the number of files
+/// updated.
+pub async fn apply(folder: &Path) -> Result<usize, Error> {
+    let path = record_path(folder);
+    if !path.exists().await {
+        return Ok(0);
+    }
+    let serialized = node::fs::read_file(&path).await?;
+    node::fs::remove_file(&path).await?;
+    let record: Record = postcard::from_bytes(&serialized)?;
+    let mut num_updated = 0;
+    for (relative, modified) in record {
+        let file_path = folder.join(&Path::from(&relative));
+        if !file_path.exists().await {
+            continue;
+        }
+        let metadata = node::fs::symlink_metadata(&file_path).await?;
+        if metadata.modified() != modified {
+            node::fs::lutimes(&file_path, &metadata.accessed(), &modified).await?;
+            num_updated += 1;
+        }
+    }
+    Ok(num_updated)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::node;
+    use crate::node::path::Path;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    async fn record_and_apply() -> Result<(), JsValue> {
+        let temp_dir = node::fs::TempDir::new(node::os::temp_dir().join("ferrous-actions-timestamps-")).await?;
+        let folder = temp_dir.path().clone();
+        let sub_folder = folder.join("sub");
+        node::fs::create_dir_all(&sub_folder).await?;
+        let files: Vec<Path> = vec![folder.join("a"), sub_folder.join("b")];
+        let original = chrono::Utc::now() - chrono::Duration::days(10);
+        for file in &files {
+            node::fs::write_file(file, &[]).await?;
+            node::fs::lutimes(file, &original, &original).await?;
+        }
+        super::record(&folder).await.map_err(|e| JsValue::from(e.to_string()))?;
+
+        let changed = chrono::Utc::now();
+        for file in &files {
+            node::fs::lutimes(file, &changed, &changed).await?;
+        }
+        let num_updated = super::apply(&folder).await.map_err(|e| JsValue::from(e.to_string()))?;
+        assert_eq!(num_updated, files.len());
+        for file in &files {
+            let modified = node::fs::symlink_metadata(file).await?.modified();
+            assert!((modified - original).num_milliseconds().abs() < 1);
+        }
+        assert!(!super::record_path(&folder).exists().await);
+        temp_dir.close().await
+    }
+}
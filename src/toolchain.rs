@@ -38,16 +38,7 @@ fn compute_package_cache_key(package: &ManifestPackage) -> CacheEntry {
 }
 
 fn default_target_for_platform() -> Result<Triple, Error> {
-    let target = Triple::from_str(match (node::os::arch().as_str(), node::os::platform().as_str()) {
-        ("arm64", "linux") => "aarch64-unknown-linux-gnu",
-        ("ia32", "linux") => "i686-unknown-linux-gnu",
-        ("ia32", "win32") => "i686-pc-windows-msvc",
-        ("x64", "darwin") => "x86_64-apple-darwin",
-        ("x64", "linux") => "x86_64-unknown-linux-gnu",
-        ("x64", "win32") => "x86_64-pc-windows-msvc",
-        (arch, platform) => return Err(Error::UnsupportedPlatform(format!("{}-{}", platform, arch))),
-    })
-    .expect("Failed to parse hardcoded platform triple");
+    let target = Triple::from_str(crate::target_triple::host()?).expect("Failed to parse hardcoded platform triple");
     Ok(target)
 }
 
@@ -129,7 +120,7 @@ async fn fetch_and_decompress_package(package: &ManifestPackage) -> Result<(), E
             .tarballs
             .iter()
             .find(|(c, _)| *c == Compression::Gzip)
-            .expect("Unable to find tar.gz")
+            .ok_or_else(|| Error::MissingGzipArchive(package.name.clone()))?
             .1
             .clone();
         info!("Will need to download the following: {:#?}", remote_binary);
@@ -140,8 +131,10 @@ async fn fetch_and_decompress_package(package: &ManifestPackage) -> Result<(), E
         info!("Will extract to {}", extract_path);
         tool_cache::extract_tar(&tarball_path, StreamCompression::Gzip, Some(&extract_path)).await?;
         info!("Extracted to {}", extract_path);
-        let cache_id = cache_entry.save().await?;
-        info!("Saved as {}", cache_id);
+        match cache_entry.save().await?.cache_id() {
+            Some(cache_id) => info!("Saved as {}", cache_id),
+            None => info!("Package was not saved to the cache"),
+        }
     }
     Ok(())
 }
@@ -151,14 +144,12 @@ pub async fn install(toolchain_config: &ToolchainConfig) -> Result<(), Error> {
     use futures::{StreamExt as _, TryStreamExt as _};
     use rustup_toolchain_manifest::{InstallSpec, Manifest};
 
-    let toolchain = {
-        let mut toolchain = Toolchain::from_str(&toolchain_config.name)?;
-        toolchain.host = Some(match toolchain.host {
-            Some(host) => host,
-            None => default_target_for_platform()?,
-        });
-        toolchain
+    let mut toolchain = Toolchain::from_str(&toolchain_config.name)?;
+    let target = match toolchain.host.clone() {
+        Some(host) => host,
+        None => default_target_for_platform()?,
     };
+    toolchain.host = Some(target.clone());
     let manifest_url = toolchain.manifest_url();
     info!(
         "Will download manifest for toolchain {} from {}",
@@ -171,10 +162,9 @@ pub async fn install(toolchain_config: &ToolchainConfig) -> Result<(), Error> {
     let manifest = node::fs::read_file(&manifest_path).await?;
     let manifest = String::from_utf8(manifest).map_err(|_| Error::ManifestNotUtf8)?;
     let manifest = Manifest::try_from(manifest.as_str())?;
-    let target = toolchain.host.clone().expect("Toolchain target unexpectedly missing");
     info!("Attempting to find toolchain for target {}", target);
     let install_spec = InstallSpec {
-        profile: toolchain_config.profile.clone(),
+        profile: toolchain_config.profile.to_string(),
         components: toolchain_config.components.iter().cloned().collect(),
         targets: toolchain_config.targets.iter().cloned().collect(),
     };
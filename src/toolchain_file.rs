@@ -0,0 +1,136 @@
+use crate::dir_tree::find_up;
+use crate::node::path::Path;
+use crate::{node, Error};
+use serde::Deserialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// File names checked for in each folder, in order of precedence
+const FILE_NAMES: [&str; 2] = ["rust-toolchain", "rust-toolchain.toml"];
+
+#[derive(Debug, Clone, Error)]
+pub enum ParseError {
+    #[error("Toolchain file is empty")]
+    Empty,
+
+    #[error("Toolchain file is not valid TOML: {0}")]
+    Toml(String),
+
+    #[error("Toolchain file does not specify a channel")]
+    MissingChannel,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TomlFile {
+    toolchain: TomlToolchain,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TomlToolchain {
+    channel: Option<String>,
+    profile: Option<String>,
+    components: Vec<String>,
+    targets: Vec<String>,
+}
+
+/// The contents of a `rust-toolchain` or `rust-toolchain.toml` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolchainFile {
+    pub channel: String,
+    pub profile: Option<String>,
+    pub components: Vec<String>,
+    pub targets: Vec<String>,
+}
+
+impl FromStr for ToolchainFile {
+    type Err = ParseError;
+
+    fn from_str(string: &str) -> Result<ToolchainFile, ParseError> {
+        let trimmed = string.trim();
+        if trimmed.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        // The legacy format is a single line containing only the channel
+        if !trimmed.contains(['\n', '[', '=']) {
+            return Ok(ToolchainFile {
+                channel: trimmed.to_string(),
+                profile: None,
+                components: Vec::new(),
+                targets: Vec::new(),
+            });
+        }
+        let parsed: TomlFile = basic_toml::from_str(string).map_err(|e| ParseError::Toml(e.to_string()))?;
+        let toolchain = parsed.toolchain;
+        Ok(ToolchainFile {
+            channel: toolchain.channel.ok_or(ParseError::MissingChannel)?,
+            profile: toolchain.profile,
+            components: toolchain.components,
+            targets: toolchain.targets,
+        })
+    }
+}
+
+/// Locates and parses the toolchain file that applies to the specified folder
+pub async fn find_and_parse(folder: &Path) -> Result<Option<(Path, ToolchainFile)>, Error> {
+    let path = match find_up(folder, &FILE_NAMES).await {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let content = node::fs::read_file(&path).await?;
+    let content = String::from_utf8_lossy(&content);
+    let toolchain_file = ToolchainFile::from_str(&content)?;
+    Ok(Some((path, toolchain_file)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ParseError, ToolchainFile};
+    use std::str::FromStr as _;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn parse_legacy() {
+        let parsed = ToolchainFile::from_str("nightly-2023-06-01\n").expect("Failed to parse legacy file");
+        assert_eq!(parsed.channel, "nightly-2023-06-01");
+        assert!(parsed.profile.is_none());
+        assert!(parsed.components.is_empty());
+        assert!(parsed.targets.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_toml() {
+        let content = r#"
+[toolchain]
+channel = "1.70.0"
+profile = "minimal"
+components = ["rustfmt", "clippy"]
+targets = ["wasm32-unknown-unknown"]
+"#;
+        let parsed = ToolchainFile::from_str(content).expect("Failed to parse TOML file");
+        assert_eq!(parsed.channel, "1.70.0");
+        assert_eq!(parsed.profile.as_deref(), Some("minimal"));
+        assert_eq!(parsed.components, ["rustfmt", "clippy"]);
+        assert_eq!(parsed.targets, ["wasm32-unknown-unknown"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_toml_channel_only() {
+        let parsed = ToolchainFile::from_str("[toolchain]\nchannel = \"stable\"\n").expect("Failed to parse TOML file");
+        assert_eq!(parsed.channel, "stable");
+        assert!(parsed.components.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_invalid() {
+        assert!(matches!(ToolchainFile::from_str(" \n"), Err(ParseError::Empty)));
+        assert!(matches!(
+            ToolchainFile::from_str("[toolchain]\npath = \"/opt/rust\"\n"),
+            Err(ParseError::MissingChannel)
+        ));
+        assert!(matches!(
+            ToolchainFile::from_str("[toolchain\n"),
+            Err(ParseError::Toml(_))
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+use semver::{Version, VersionReq};
+
+/// The oldest version of node.js providing every API the action uses
+pub const MIN_NODE_VERSION: Version = Version::new(16, 7, 0);
+
+/// Parses a node.js version, as reported by `process.version`, which has a
+/// leading `v`
+pub fn parse_node_version(version: &str) -> Option<Version> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    Version::parse(version).ok()
+}
+
+/// Whether the specified node.js version is new enough to run the action. A
+/// version which cannot be parsed is assumed to be supported.
+pub fn is_supported_node_version(version: &str) -> bool {
+    parse_node_version(version).map_or(true, |version| version >= MIN_NODE_VERSION)
+}
+
+/// Whether an installed version meets the specified requirement
+pub fn satisfies(installed: &Version, req: &VersionReq) -> bool {
+    req.matches(installed)
+}
+
+/// Extracts a version from the output of running a tool with `--version`.
+/// This handles the common `name x.y.z (hash date)` format, as well as
+/// versions prefixed with `v`. The first word that parses as a version is
+/// used.
+pub fn parse_version_output(output: &str) -> Option<Version> {
+    output
+        .split_whitespace()
+        .map(|word| word.strip_prefix('v').unwrap_or(word))
+        .find_map(|word| Version::parse(word).ok())
+}
+
+/// Parses a version requirement in the form accepted by `cargo install
+/// --version`. As with Cargo, a bare complete version is treated as an exact
+/// requirement rather than a caret requirement.
+pub fn parse_install_requirement(spec: &str) -> Option<VersionReq> {
+    let spec = spec.trim();
+    if Version::parse(spec).is_ok() {
+        VersionReq::parse(&format!("={}", spec)).ok()
+    } else {
+        VersionReq::parse(spec).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        is_supported_node_version, parse_install_requirement, parse_node_version, parse_version_output, satisfies,
+    };
+    use semver::{Version, VersionReq};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn node_version_support() {
+        assert!(!is_supported_node_version("14.21.3"));
+        assert!(!is_supported_node_version("16.6.2"));
+        assert!(is_supported_node_version("16.7.0"));
+        assert!(is_supported_node_version("v20.9.0"));
+        assert!(is_supported_node_version("unknown"));
+        assert!(is_supported_node_version(&crate::node::process::node_version()));
+    }
+
+    #[wasm_bindgen_test]
+    fn node_versions() {
+        use crate::node;
+
+        assert_eq!(parse_node_version("v18.17.1"), Some(Version::new(18, 17, 1)));
+        let version = node::process::node_version();
+        assert!(version.starts_with('v'), "{}", version);
+        assert!(parse_node_version(&version).is_some());
+        let versions = node::process::versions();
+        assert_eq!(versions.get("node").map(String::as_str), version.strip_prefix('v'));
+        assert!(versions.contains_key("v8"));
+    }
+
+    #[wasm_bindgen_test]
+    fn exact_requirement() {
+        let req = VersionReq::parse("=1.2.3").unwrap();
+        assert!(satisfies(&Version::new(1, 2, 3), &req));
+        assert!(!satisfies(&Version::new(1, 2, 4), &req));
+    }
+
+    #[wasm_bindgen_test]
+    fn caret_requirement() {
+        let req = VersionReq::parse("^1.2").unwrap();
+        assert!(satisfies(&Version::new(1, 2, 0), &req));
+        assert!(satisfies(&Version::new(1, 9, 1), &req));
+        assert!(!satisfies(&Version::new(1, 1, 9), &req));
+        assert!(!satisfies(&Version::new(2, 0, 0), &req));
+    }
+
+    #[wasm_bindgen_test]
+    fn version_output() {
+        assert_eq!(
+            parse_version_output("cargo 1.74.0 (ecb9851af 2023-10-18)\n"),
+            Some(Version::new(1, 74, 0))
+        );
+        assert_eq!(
+            parse_version_output("cargo-nextest v0.9.59"),
+            Some(Version::new(0, 9, 59))
+        );
+        assert_eq!(
+            parse_version_output("wasm-bindgen 0.2.87"),
+            Some(Version::new(0, 2, 87))
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn nonstandard_version_output() {
+        assert_eq!(parse_version_output("mytool version 1.2 (custom build)"), None);
+        assert_eq!(parse_version_output(""), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn install_requirement() {
+        let req = parse_install_requirement("0.2.5").unwrap();
+        assert!(satisfies(&Version::new(0, 2, 5), &req));
+        assert!(!satisfies(&Version::new(0, 2, 6), &req));
+        let req = parse_install_requirement("^0.2").unwrap();
+        assert!(satisfies(&Version::new(0, 2, 6), &req));
+        assert!(parse_install_requirement("not a version").is_none());
+    }
+}
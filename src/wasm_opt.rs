@@ -0,0 +1,213 @@
+use crate::actions::exec::Command;
+use crate::actions::{core, io, tool_cache};
+use crate::node::path::Path;
+use crate::node::{self};
+use crate::{debug, info, warning, Error};
+
+/// The Binaryen release downloaded when `wasm-opt` is not already available
+const BINARYEN_VERSION: &str = "116";
+
+/// Optimization levels accepted by `wasm-opt`
+const OPT_LEVELS: [&str; 8] = ["-O", "-O0", "-O1", "-O2", "-O3", "-O4", "-Os", "-Oz"];
+
+/// Parses an optimization level such as `-Os` or `O3`
+pub fn parse_level(value: &str) -> Result<&'static str, Error> {
+    let value = value.trim();
+    let normalized = if value.starts_with('-') {
+        value.to_string()
+    } else {
+        format!("-{}", value)
+    };
+    OPT_LEVELS
+        .iter()
+        .copied()
+        .find(|level| *level == normalized)
+        .ok_or_else(|| Error::InvalidWasmOptLevel(value.to_string()))
+}
+
+/// The platform suffix of the Binaryen release archive for the given Node.js
+/// platform and architecture
+fn binaryen_platform(platform: &str, arch: &str) -> Option<&'static str> {
+    let result = match (platform, arch) {
+        ("linux", "x64") => "x86_64-linux",
+        ("darwin", "x64") => "x86_64-macos",
+        ("darwin", "arm64") => "arm64-macos",
+        ("win32", "x64") => "x86_64-windows",
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn binaryen_url(version: &str, platform: &str) -> String {
+    format!(
+        "https://github.com/WebAssembly/binaryen/releases/download/version_{version}/binaryen-version_{version}-{platform}.tar.gz",
+        version = version,
+        platform = platform
+    )
+}
+
+fn wasm_opt_args(level: &str, input: &Path, output: &Path) -> Vec<String> {
+    vec![level.to_string(), input.to_string(), "-o".into(), output.to_string()]
+}
+
+/// The name of the optimized copy of an artifact written to an output
+/// directory. Cargo places artifacts built with `--target` in a directory
+/// named after the target triple, which is added to the name so that
+/// same-named artifacts for different targets do not overwrite each other.
+fn output_file_name(target_dir: &Path, artifact: &Path) -> String {
+    let file_name = artifact.file_name();
+    let components: Vec<String> = artifact.relative_to(target_dir).components().collect();
+    match components.as_slice() {
+        [triple, _profile, _file] => {
+            let stem = file_name.strip_suffix(".wasm").unwrap_or(&file_name);
+            format!("{}-{}.wasm", stem, triple)
+        }
+        _ => file_name,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WasmOpt {
+    path: Path,
+}
+
+impl WasmOpt {
+    pub async fn get() -> Result<Option<WasmOpt>, Error> {
+        let path = io::which_optional("wasm-opt").await?;
+        Ok(path.map(|path| WasmOpt { path }))
+    }
+
+    pub async fn get_or_install() -> Result<WasmOpt, Error> {
+        match Self::get().await? {
+            Some(wasm_opt) => Ok(wasm_opt),
+            None => {
+                info!("Unable to find wasm-opt. Installing Binaryen now...");
+                Self::install().await
+            }
+        }
+    }
+
+    async fn install() -> Result<WasmOpt, Error> {
+        use tool_cache::StreamCompression;
+
+        let (platform, arch) = (node::os::platform(), node::os::arch());
+        let binaryen_platform = binaryen_platform(&platform, &arch)
+            .ok_or_else(|| Error::UnsupportedBinaryenPlatform(platform.clone(), arch.clone()))?;
+        let url = binaryen_url(BINARYEN_VERSION, binaryen_platform);
+        debug!("Downloading Binaryen from {}", url);
+        let tarball_path = tool_cache::download_tool(url.as_str()).await?;
+        let extracted = tool_cache::extract_tar(&tarball_path, StreamCompression::Gzip, None).await?;
+        let root = extracted.join(format!("binaryen-version_{}", BINARYEN_VERSION).as_str());
+        let cached = tool_cache::cache_dir("binaryen", BINARYEN_VERSION, &root, Some(&arch)).await?;
+        core::add_path(&cached.join("bin"));
+        Self::get()
+            .await?
+            .ok_or_else(|| Error::MissingToolAfterInstall("wasm-opt".into()))
+    }
+
+    /// Optimizes `input` at the specified level, writing the result to
+    /// `output`. The input and output may be the same file.
+    pub async fn optimize(&self, level: &str, input: &Path, output: &Path) -> Result<(), Error> {
+        Command::from(&self.path)
+            .args(wasm_opt_args(level, input, output))
+            .exec()
+            .await?;
+        Ok(())
+    }
+}
+
+/// Runs `wasm-opt` on each of the specified WebAssembly artifacts of a
+/// `cargo build` invocation. Optimized files overwrite the originals unless
+/// an output directory is specified.
+pub async fn optimize_outputs(
+    target_dir: &Path,
+    artifacts: &[Path],
+    level: &str,
+    output_dir: Option<&Path>,
+) -> Result<(), Error> {
+    if artifacts.is_empty() {
+        warning!("wasm-opt was requested but the build did not produce any WebAssembly artifacts");
+        return Ok(());
+    }
+    let wasm_opt = WasmOpt::get_or_install().await?;
+    if let Some(output_dir) = output_dir {
+        node::fs::create_dir_all(output_dir).await?;
+    }
+    for input in artifacts {
+        let output = output_dir.map_or_else(
+            || input.clone(),
+            |dir| dir.join(output_file_name(target_dir, input).as_str()),
+        );
+        info!("Optimizing {} with wasm-opt {}", input, level);
+        wasm_opt.optimize(level, input, &output).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{binaryen_platform, binaryen_url, output_file_name, parse_level, wasm_opt_args};
+    use crate::node::path::Path;
+    use crate::Error;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn level_parsing() {
+        assert_eq!(parse_level("-Os").unwrap(), "-Os");
+        assert_eq!(parse_level("O3").unwrap(), "-O3");
+        assert_eq!(parse_level(" -Oz ").unwrap(), "-Oz");
+        assert!(matches!(parse_level("-O9"), Err(Error::InvalidWasmOptLevel(level)) if level == "-O9"));
+    }
+
+    #[wasm_bindgen_test]
+    fn command_arguments() {
+        let input = Path::from("target/wasm32-unknown-unknown/release/app.wasm");
+        assert_eq!(
+            wasm_opt_args("-Os", &input, &input),
+            [
+                "-Os",
+                "target/wasm32-unknown-unknown/release/app.wasm",
+                "-o",
+                "target/wasm32-unknown-unknown/release/app.wasm"
+            ]
+        );
+        let output = Path::from("dist/app.wasm");
+        assert_eq!(
+            wasm_opt_args("-O3", &input, &output),
+            [
+                "-O3",
+                "target/wasm32-unknown-unknown/release/app.wasm",
+                "-o",
+                "dist/app.wasm"
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn output_file_names() {
+        let target_dir = Path::from("/ws/target");
+        assert_eq!(
+            output_file_name(&target_dir, &target_dir.join("wasm32-unknown-unknown/release/app.wasm")),
+            "app-wasm32-unknown-unknown.wasm"
+        );
+        assert_eq!(
+            output_file_name(&target_dir, &target_dir.join("wasm32-wasi/release/app.wasm")),
+            "app-wasm32-wasi.wasm"
+        );
+        assert_eq!(
+            output_file_name(&target_dir, &target_dir.join("release/app.wasm")),
+            "app.wasm"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn binaryen_downloads() {
+        assert_eq!(binaryen_platform("linux", "x64"), Some("x86_64-linux"));
+        assert_eq!(binaryen_platform("darwin", "arm64"), Some("arm64-macos"));
+        assert_eq!(binaryen_platform("linux", "arm64"), None);
+        assert_eq!(
+            binaryen_url("116", "x86_64-linux"),
+            "https://github.com/WebAssembly/binaryen/releases/download/version_116/binaryen-version_116-x86_64-linux.tar.gz"
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use crate::actions::core::Annotation;
+use crate::node::path::Path;
+use crate::{error, github, warning};
+
+/// Title used for annotations describing problems with how the action was
+/// configured
+const CONFIGURATION_TITLE: &str = "Ferrous Actions configuration";
+
+/// Returns the path of the running workflow's file relative to the root of the
+/// repository, if it can be determined
+pub fn file_path() -> Option<Path> {
+    let context = github::Context::from_env().ok()?;
+    context.workflow_file_path().map(|path| Path::from(path.as_str()))
+}
+
+fn configuration_annotation(message: &str) -> Option<Annotation> {
+    let path = file_path()?;
+    let mut annotation = Annotation::from(message);
+    annotation.title(CONFIGURATION_TITLE).file(&path);
+    Some(annotation)
+}
+
+/// Outputs an error describing a problem with the action's configuration,
+/// attached to the workflow file where possible
+pub fn configuration_error(message: &str) {
+    match configuration_annotation(message) {
+        Some(annotation) => annotation.error(),
+        None => error!("{}", message),
+    }
+}
+
+/// Outputs a warning describing a problem with the action's configuration,
+/// attached to the workflow file where possible
+pub fn configuration_warning(message: &str) {
+    match configuration_annotation(message) {
+        Some(annotation) => annotation.warning(),
+        None => warning!("{}", message),
+    }
+}